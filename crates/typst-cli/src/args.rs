@@ -476,6 +476,9 @@ pub enum PdfStandard {
     /// PDF/A-3b.
     #[value(name = "a-3b")]
     A_3b,
+    /// PDF/UA-1.
+    #[value(name = "ua-1")]
+    UA_1,
 }
 
 display_possible_values!(PdfStandard);
@@ -138,6 +138,7 @@ impl CompileConfig {
                     PdfStandard::V_1_7 => typst_pdf::PdfStandard::V_1_7,
                     PdfStandard::A_2b => typst_pdf::PdfStandard::A_2b,
                     PdfStandard::A_3b => typst_pdf::PdfStandard::A_3b,
+                    PdfStandard::UA_1 => typst_pdf::PdfStandard::UA_1,
                 })
                 .collect::<Vec<_>>();
             PdfStandards::new(&list)?
@@ -300,6 +301,8 @@ fn export_pdf(document: &PagedDocument, config: &CompileConfig) -> SourceResult<
         timestamp,
         page_ranges: config.pages.clone(),
         standards: config.pdf_standards.clone(),
+        omit_creator: false,
+        attachments: &[],
     };
     let buffer = typst_pdf::pdf(document, &options)?;
     config
@@ -30,7 +30,8 @@ impl Eval for ast::FuncCall<'_> {
         let args = self.args();
         let trailing_comma = args.trailing_comma();
 
-        vm.engine.route.check_call_depth().at(span)?;
+        let max_depth = vm.world().library().max_call_depth;
+        vm.engine.route.check_call_depth(max_depth).at(span)?;
 
         // Try to evaluate as a call to an associated function or field.
         let (callee, args) = if let ast::Expr::FieldAccess(access) = callee {
@@ -1,6 +1,6 @@
 use ecow::{eco_vec, EcoVec};
 use typst_library::diag::{bail, error, warning, At, SourceResult};
-use typst_library::engine::Engine;
+use typst_library::engine::{Engine, Route};
 use typst_library::foundations::{
     ops, Array, Capturer, Closure, Content, ContextElem, Dict, Func, NativeElement,
     Selector, Str, Value,
@@ -75,6 +75,8 @@ impl Eval for ast::Expr<'_> {
 
     fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
         let span = self.span();
+        Route::check_stack().at(span)?;
+        Route::check_deadline();
         let forbidden = |name| {
             error!(span, "{} is only allowed directly in code and content blocks", name)
         };
@@ -385,7 +387,8 @@ fn warn_for_discarded_content(engine: &mut Engine, event: &FlowEvent, joined: &V
         *span,
         "this return unconditionally discards the content before it";
         hint: "try omitting the `return` to automatically join all values"
-    );
+    )
+    .with_identifier("discarded-content");
 
     if tree.query_first(selector).is_some() {
         warning.hint("state/counter updates are content that must end up in the document to have an effect");
@@ -49,10 +49,13 @@ impl Eval for ast::ModuleImport<'_> {
             if let ast::Expr::Ident(ident) = self.source() {
                 if ident.as_str() == new_name.as_str() {
                     // Warn on `import x as x`
-                    vm.engine.sink.warn(warning!(
-                        new_name.span(),
-                        "unnecessary import rename to same name",
-                    ));
+                    vm.engine.sink.warn(
+                        warning!(
+                            new_name.span(),
+                            "unnecessary import rename to same name",
+                        )
+                        .with_identifier("redundant-import-rename"),
+                    );
                 }
             }
 
@@ -70,10 +73,13 @@ impl Eval for ast::ModuleImport<'_> {
                             if !is_str || matches!(source_expr, ast::Expr::Str(_)) =>
                         {
                             if matches!(source_expr, ast::Expr::Ident(_)) {
-                                vm.engine.sink.warn(warning!(
-                                    source_expr.span(),
-                                    "this import has no effect",
-                                ));
+                                vm.engine.sink.warn(
+                                    warning!(
+                                        source_expr.span(),
+                                        "this import has no effect",
+                                    )
+                                    .with_identifier("ineffective-import"),
+                                );
                             }
                             vm.scopes.top.bind(name, Binding::new(source, source_span));
                         }
@@ -146,10 +152,13 @@ impl Eval for ast::ModuleImport<'_> {
                                 if renamed_item.original_name().as_str()
                                     == renamed_item.new_name().as_str()
                                 {
-                                    vm.engine.sink.warn(warning!(
-                                        renamed_item.new_name().span(),
-                                        "unnecessary import rename to same name",
-                                    ));
+                                    vm.engine.sink.warn(
+                                        warning!(
+                                            renamed_item.new_name().span(),
+                                            "unnecessary import rename to same name",
+                                        )
+                                        .with_identifier("redundant-import-rename"),
+                                    );
                                 }
                             }
 
@@ -201,7 +210,19 @@ fn import_file(engine: &mut Engine, id: FileId, span: Span) -> SourceResult<Modu
 
     // Prevent cyclic importing.
     if engine.route.contains(source.id()) {
-        bail!(span, "cyclic import");
+        let files = engine.route.files();
+        let start = files.iter().position(|&file| file == id).unwrap_or(0);
+        let cycle = files[start..]
+            .iter()
+            .chain([&id])
+            .map(|file| eco_format!("{file:?}"))
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        bail!(
+            span, "cyclic import";
+            hint: "the files import each other as {cycle}";
+            hint: "move the definitions both files need into a separate file"
+        );
     }
 
     // Evaluate the file.
@@ -59,16 +59,19 @@ fn eval_markup<'a>(
                             vm.engine.sink.warn(warning!(
                                 elem.span(), "content labelled multiple times";
                                 hint: "only the last label is used, the rest are ignored",
-                            ));
+                            ).with_identifier("multiple-labels"));
                         }
 
                         *elem = std::mem::take(elem).labelled(label);
                     } else {
-                        vm.engine.sink.warn(warning!(
-                            expr.span(),
-                            "label `{}` is not attached to anything",
-                            label.repr()
-                        ));
+                        vm.engine.sink.warn(
+                            warning!(
+                                expr.span(),
+                                "label `{}` is not attached to anything",
+                                label.repr()
+                            )
+                            .with_identifier("unattached-label"),
+                        );
                     }
                 }
                 value => seq.push(value.display().spanned(expr.span())),
@@ -154,7 +157,7 @@ impl Eval for ast::Strong<'_> {
                 .warn(warning!(
                     self.span(), "no text within stars";
                     hint: "using multiple consecutive stars (e.g. **) has no additional effect",
-                ));
+                ).with_identifier("empty-strong"));
         }
 
         Ok(StrongElem::new(body.eval(vm)?).pack())
@@ -172,7 +175,7 @@ impl Eval for ast::Emph<'_> {
                 .warn(warning!(
                     self.span(), "no text within underscores";
                     hint: "using multiple consecutive underscores (e.g. __) has no additional effect"
-                ));
+                ).with_identifier("empty-emph"));
         }
 
         Ok(EmphElem::new(body.eval(vm)?).pack())
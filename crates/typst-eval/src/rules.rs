@@ -70,7 +70,7 @@ fn check_show_par_set_block(vm: &mut Vm, recipe: &Recipe) {
                 "`show par: set block(spacing: ..)` has no effect anymore";
                 hint: "write `set par(spacing: ..)` instead";
                 hint: "this is specific to paragraphs as they are not considered blocks anymore"
-            ))
+            ).with_identifier("ineffective-show-rule"))
         }
     }
 }
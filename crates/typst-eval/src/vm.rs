@@ -60,13 +60,16 @@ impl<'a> Vm<'a> {
 
         // This will become an error in the parser if `is` becomes a keyword.
         if var.get() == "is" {
-            self.engine.sink.warn(warning!(
-                var.span(),
-                "`is` will likely become a keyword in future versions and will \
-                not be allowed as an identifier";
-                hint: "rename this variable to avoid future errors";
-                hint: "try `is_` instead"
-            ));
+            self.engine.sink.warn(
+                warning!(
+                    var.span(),
+                    "`is` will likely become a keyword in future versions and will \
+                    not be allowed as an identifier";
+                    hint: "rename this variable to avoid future errors";
+                    hint: "try `is_` instead"
+                )
+                .with_identifier("future-keyword"),
+            );
         }
 
         self.scopes.top.bind(var.get().clone(), binding);
@@ -251,11 +251,14 @@ fn handle(
         )?;
         output.push(HtmlNode::Frame(frame));
     } else {
-        engine.sink.warn(warning!(
-            child.span(),
-            "{} was ignored during HTML export",
-            child.elem().name()
-        ));
+        engine.sink.warn(
+            warning!(
+                child.span(),
+                "{} was ignored during HTML export",
+                child.elem().name()
+            )
+            .with_identifier("ignored-in-html"),
+        );
     }
     Ok(())
 }
@@ -58,6 +58,9 @@ pub struct Downloader {
     user_agent: EcoString,
     cert_path: Option<PathBuf>,
     cert: OnceCell<Certificate>,
+    proxy: Option<String>,
+    timeout: Option<Duration>,
+    accept_invalid_certs: bool,
 }
 
 impl Downloader {
@@ -67,6 +70,9 @@ impl Downloader {
             user_agent: user_agent.into(),
             cert_path: None,
             cert: OnceCell::new(),
+            proxy: None,
+            timeout: None,
+            accept_invalid_certs: false,
         }
     }
 
@@ -78,6 +84,9 @@ impl Downloader {
             user_agent: user_agent.into(),
             cert_path: Some(cert_path),
             cert: OnceCell::new(),
+            proxy: None,
+            timeout: None,
+            accept_invalid_certs: false,
         }
     }
 
@@ -87,9 +96,33 @@ impl Downloader {
             user_agent: user_agent.into(),
             cert_path: None,
             cert: OnceCell::with_value(cert),
+            proxy: None,
+            timeout: None,
+            accept_invalid_certs: false,
         }
     }
 
+    /// Uses the given proxy for all requests instead of the one configured in
+    /// the environment.
+    pub fn with_proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Aborts connecting and reading if either takes longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Disables certificate verification.
+    ///
+    /// This is insecure and should only be used for testing.
+    pub fn danger_accept_invalid_certs(mut self, value: bool) -> Self {
+        self.accept_invalid_certs = value;
+        self
+    }
+
     /// Returns the certificate this client is using, if a custom certificate
     /// is used it is loaded on first access.
     ///
@@ -114,19 +147,32 @@ impl Downloader {
         // Set user agent.
         builder = builder.user_agent(&self.user_agent);
 
-        // Get the network proxy config from the environment and apply it.
-        if let Some(proxy) = env_proxy::for_url_str(url)
+        // Apply the explicitly configured proxy, or get the network proxy config
+        // from the environment and apply it.
+        if let Some(proxy) = &self.proxy {
+            let proxy = ureq::Proxy::new(proxy)?;
+            builder = builder.proxy(proxy);
+        } else if let Some(proxy) = env_proxy::for_url_str(url)
             .to_url()
             .and_then(|url| ureq::Proxy::new(url).ok())
         {
             builder = builder.proxy(proxy);
         }
 
+        // Apply the timeout, if any.
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout_connect(timeout).timeout_read(timeout);
+        }
+
         // Apply a custom CA certificate if present.
         if let Some(cert) = self.cert() {
             tls.add_root_certificate(cert?.clone());
         }
 
+        if self.accept_invalid_certs {
+            tls.danger_accept_invalid_certs(true);
+        }
+
         // Configure native TLS.
         let connector =
             tls.build().map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
@@ -160,6 +206,9 @@ impl Debug for Downloader {
                     .get()
                     .map(|_| typst_utils::debug(|f| write!(f, "Certificate(..)"))),
             )
+            .field("proxy", &self.proxy)
+            .field("timeout", &self.timeout)
+            .field("accept_invalid_certs", &self.accept_invalid_certs)
             .finish()
     }
 }
@@ -30,6 +30,12 @@ pub struct FontSlot {
 }
 
 impl FontSlot {
+    /// Creates a slot for the font at the given path and collection index.
+    /// The font is only loaded on first access, so the path is not checked.
+    pub fn from_path(path: PathBuf, index: u32) -> Self {
+        Self { path: Some(path), index, font: OnceLock::new() }
+    }
+
     /// Returns the path at which the font can be found on the system, or `None`
     /// if the font was embedded.
     pub fn path(&self) -> Option<&Path> {
@@ -42,6 +48,11 @@ impl FontSlot {
         self.index
     }
 
+    /// Whether the font was already loaded into memory (or failed to load).
+    pub fn is_loaded(&self) -> bool {
+        self.font.get().is_some()
+    }
+
     /// Get the font for this slot. This loads the font into memory on first
     /// access.
     pub fn get(&self) -> Option<Font> {
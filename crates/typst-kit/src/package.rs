@@ -186,9 +186,7 @@ impl PackageStorage {
                     return Err(PackageError::NotFound(spec.clone()));
                 }
             }
-            Err(err) => {
-                return Err(PackageError::NetworkFailed(Some(eco_format!("{err}"))))
-            }
+            Err(err) => return Err(network_error(&err)),
         };
 
         let decompressed = flate2::read::GzDecoder::new(data.as_slice());
@@ -199,6 +197,27 @@ impl PackageStorage {
     }
 }
 
+/// Classifies a failed request by the stage it failed in.
+fn network_error(err: &ureq::Error) -> PackageError {
+    let message = Some(eco_format!("{err}"));
+    match err.kind() {
+        ureq::ErrorKind::Dns => PackageError::DnsFailed(message),
+        ureq::ErrorKind::ConnectionFailed => {
+            // The TLS handshake happens while connecting; its errors are
+            // wrapped by the handshake error of the connector.
+            let mut source = std::error::Error::source(err);
+            while let Some(error) = source {
+                if error.is::<native_tls::Error>() {
+                    return PackageError::TlsFailed(message);
+                }
+                source = error.source();
+            }
+            PackageError::ConnectionFailed(message)
+        }
+        _ => PackageError::NetworkFailed(message),
+    }
+}
+
 /// Minimal information required about a package to determine its latest
 /// version.
 #[derive(Deserialize)]
@@ -209,8 +228,43 @@ struct MinimalPackageInfo {
 
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
+    use std::net::TcpListener;
+
     use super::*;
 
+    fn failure(url: &str) -> PackageError {
+        let err = Downloader::new("typst/test").download(url).unwrap_err();
+        network_error(&err)
+    }
+
+    #[test]
+    fn dns_failure() {
+        let error = failure("https://packages.invalid/preview/index.json");
+        assert!(matches!(error, PackageError::DnsFailed(Some(_))), "{error:?}");
+    }
+
+    #[test]
+    fn connection_failure() {
+        // Nothing listens on the port of a dropped listener.
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let error = failure(&format!("https://{addr}/preview/index.json"));
+        assert!(matches!(error, PackageError::ConnectionFailed(Some(_))), "{error:?}");
+    }
+
+    #[test]
+    fn tls_failure() {
+        // A server that answers in plain text fails the handshake.
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = server.accept().unwrap();
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n");
+        });
+        let error = failure(&format!("https://{addr}/preview/index.json"));
+        assert!(matches!(error, PackageError::TlsFailed(Some(_))), "{error:?}");
+    }
+
     #[test]
     fn lazy_deser_index() {
         let storage = PackageStorage::with_index(
@@ -1,15 +1,16 @@
 use std::cell::LazyCell;
 
 use smallvec::SmallVec;
-use typst_library::diag::SourceResult;
+use typst_library::diag::{warning, SourceResult};
 use typst_library::engine::Engine;
-use typst_library::foundations::{Packed, Resolve, StyleChain};
+use typst_library::foundations::{Packed, Repr, Resolve, StyleChain};
 use typst_library::introspection::Locator;
 use typst_library::layout::{
     Abs, Axes, BlockBody, BlockElem, Fragment, Frame, FrameKind, Region, Regions, Rel,
     Sides, Size, Sizing,
 };
 use typst_library::visualize::Stroke;
+use typst_syntax::Span;
 use typst_utils::Numeric;
 
 use crate::shapes::{clip_rect, fill_and_stroke};
@@ -63,6 +64,10 @@ pub fn layout_single_block(
         frame.set_kind(FrameKind::Hard);
     }
 
+    if !elem.clip(styles) {
+        check_overflow(engine, elem.span(), frame.size(), pod.size);
+    }
+
     // Enforce a correct frame size on the expanded axes. Do this before
     // applying the inset, since the pod shrunk.
     frame.set_size(pod.expand.select(pod.size, frame.size()));
@@ -220,6 +225,10 @@ pub fn layout_multi_block(
             frame.set_kind(FrameKind::Hard);
         }
 
+        if !clip {
+            check_overflow(engine, elem.span(), frame.size(), region);
+        }
+
         // Enforce a correct frame size on the expanded axes. Do this before
         // applying the inset, since the pod shrunk.
         frame.set_size(pod.expand.select(region, frame.size()));
@@ -289,6 +298,33 @@ pub(crate) fn unbreakable_pod(
     Region::new(size, expand)
 }
 
+/// Warns about content of the given size that doesn't fit into the space
+/// available in its box or block, if enabled, see
+/// [`Library::overflow_warnings`](typst_library::Library::overflow_warnings).
+pub(crate) fn check_overflow(
+    engine: &mut Engine,
+    span: Span,
+    content: Size,
+    available: Size,
+) {
+    if !engine.world.library().overflow_warnings {
+        return;
+    }
+    let excess = (content - available).map(|excess| excess.max(Abs::zero()));
+    for (excess, direction) in [(excess.x, "horizontally"), (excess.y, "vertically")] {
+        if excess > Abs::pt(0.01) && excess.is_finite() {
+            engine.sink.warn(
+                warning!(
+                    span,
+                    "content overflows its container {direction} by {}",
+                    excess.repr()
+                )
+                .with_identifier("overfull-box"),
+            );
+        }
+    }
+}
+
 /// Builds the pod regions for a breakable sized container.
 fn breakable_pod<'a>(
     width: &Sizing,
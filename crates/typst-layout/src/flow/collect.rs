@@ -96,11 +96,14 @@ impl<'a> Collector<'a, '_, '_> {
                     hint: "try using a `#colbreak()` instead",
                 );
             } else {
-                self.engine.sink.warn(warning!(
-                    child.span(),
-                    "{} was ignored during paged export",
-                    child.func().name()
-                ));
+                self.engine.sink.warn(
+                    warning!(
+                        child.span(),
+                        "{} was ignored during paged export",
+                        child.func().name()
+                    )
+                    .with_identifier("ignored-in-paged"),
+                );
             }
         }
 
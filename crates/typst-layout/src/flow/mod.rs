@@ -5,7 +5,7 @@ mod collect;
 mod compose;
 mod distribute;
 
-pub(crate) use self::block::unbreakable_pod;
+pub(crate) use self::block::{check_overflow, unbreakable_pod};
 
 use std::collections::HashSet;
 use std::num::NonZeroUsize;
@@ -21,8 +21,8 @@ use typst_library::introspection::{
     Introspector, Location, Locator, LocatorLink, SplitLocator, Tag,
 };
 use typst_library::layout::{
-    Abs, ColumnsElem, Dir, Em, Fragment, Frame, PageElem, PlacementScope, Region,
-    Regions, Rel, Size,
+    Abs, ColumnsElem, Dir, Em, Fragment, Frame, PageElem, PagedDocument, PlacementScope,
+    Region, Regions, Rel, Size,
 };
 use typst_library::model::{FootnoteElem, FootnoteEntry, LineNumberingScope, ParLine};
 use typst_library::routines::{Arenas, FragmentKind, Pair, RealizationKind, Routines};
@@ -217,9 +217,15 @@ pub fn layout_flow<'a>(
 
     // This loop runs once per region produced by the flow layout.
     loop {
+        Route::check_deadline();
         let frame = compose(engine, &mut work, &config, locator.next(&()), regions)?;
         finished.push(frame);
 
+        // Stop runaway page runs before they exhaust memory.
+        if mode == FlowMode::Root {
+            PagedDocument::check_page_count(engine, finished.len())?;
+        }
+
         // Terminate the loop when everything is processed, though draining the
         // backlog if necessary.
         if work.done() && (!regions.expand.y || regions.backlog.is_empty()) {
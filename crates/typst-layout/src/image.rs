@@ -45,17 +45,20 @@ pub fn layout_image(
                 "image contains foreign object";
                 hint: "SVG images with foreign objects might render incorrectly in typst";
                 hint: "see https://github.com/typst/typst/issues/1421 for more information"
-            ));
+            ).with_identifier("svg-foreign-object"));
         }
     }
 
     // Construct the image itself.
+    let limits = engine.world.library().image_limits;
+    limits.check_bytes(data.len()).at(span)?;
     let kind = match format {
         ImageFormat::Raster(format) => ImageKind::Raster(
-            RasterImage::new(
+            RasterImage::with_limits(
                 data.clone(),
                 format,
                 elem.icc(styles).as_ref().map(|icc| icc.derived.clone()),
+                limits,
             )
             .at(span)?,
         ),
@@ -63,7 +66,9 @@ pub fn layout_image(
             SvgImage::with_fonts(
                 data.clone(),
                 engine.world,
-                &families(styles).map(|f| f.as_str()).collect::<Vec<_>>(),
+                &families(styles, engine.world.library().strict_fonts)
+                    .map(|f| f.as_str())
+                    .collect::<Vec<_>>(),
             )
             .at(span)?,
         ),
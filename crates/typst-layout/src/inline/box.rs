@@ -8,7 +8,7 @@ use typst_library::layout::{BoxElem, Frame, FrameKind, Size};
 use typst_library::visualize::Stroke;
 use typst_utils::Numeric;
 
-use crate::flow::unbreakable_pod;
+use crate::flow::{check_overflow, unbreakable_pod};
 use crate::shapes::{clip_rect, fill_and_stroke};
 
 /// Lay out a box as part of inline layout.
@@ -40,6 +40,10 @@ pub fn layout_box(
             .with_kind(FrameKind::Hard),
     };
 
+    if !elem.clip(styles) {
+        check_overflow(engine, elem.span(), frame.size(), pod.size);
+    }
+
     // Enforce a correct frame size on the expanded axes. Do this before
     // applying the inset, since the pod shrunk.
     frame.set_size(pod.expand.select(pod.size, frame.size()));
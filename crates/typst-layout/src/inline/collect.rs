@@ -229,11 +229,14 @@ pub fn collect<'a>(
         } else {
             // Non-paragraph inline layout should never trigger this since it
             // only won't be triggered if we see any non-inline content.
-            engine.sink.warn(warning!(
-                child.span(),
-                "{} may not occur inside of a paragraph and was ignored",
-                child.func().name()
-            ));
+            engine.sink.warn(
+                warning!(
+                    child.span(),
+                    "{} may not occur inside of a paragraph and was ignored",
+                    child.func().name()
+                )
+                .with_identifier("ignored-in-paragraph"),
+            );
         };
 
         let len = collector.full.len() - prev_len;
@@ -159,7 +159,7 @@ fn layout_inline_impl<'a>(
     base: &ConfigBase,
 ) -> SourceResult<Fragment> {
     // Prepare configuration that is shared across the whole inline layout.
-    let config = configuration(base, children, shared, par);
+    let config = configuration(engine, base, children, shared, par);
 
     // Collect all text into one string for BiDi analysis.
     let (text, segments, spans) = collect(children, engine, locator, &config, region)?;
@@ -177,6 +177,7 @@ fn layout_inline_impl<'a>(
 
 /// Determine the inline layout's configuration.
 fn configuration(
+    engine: &Engine,
     base: &ConfigBase,
     children: &[Pair],
     shared: StyleChain,
@@ -235,7 +236,7 @@ fn configuration(
         hyphenate: shared_get(children, shared, TextElem::hyphenate_in)
             .map(|uniform| uniform.unwrap_or(justify)),
         lang: shared_get(children, shared, TextElem::lang_in),
-        fallback: TextElem::fallback_in(shared),
+        fallback: TextElem::fallback_in(shared) && !engine.world.library().strict_fonts,
         cjk_latin_spacing: TextElem::cjk_latin_spacing_in(shared).is_auto(),
         costs: TextElem::costs_in(shared),
     }
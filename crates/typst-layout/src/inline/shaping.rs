@@ -307,6 +307,7 @@ impl<'a> ShapedText<'a> {
                 size: self.size,
                 lang: self.lang,
                 region: self.region,
+                dir: TextElem::dir_in(self.styles),
                 fill: fill.clone(),
                 stroke: stroke.clone().map(|s| s.unwrap_or_default()),
                 text: self.text[range.start - self.base..range.end - self.base].into(),
@@ -350,7 +351,7 @@ impl<'a> ShapedText<'a> {
             // When there are no glyphs, we just use the vertical metrics of the
             // first available font.
             let world = engine.world;
-            for family in families(self.styles) {
+            for family in families(self.styles, world.library().strict_fonts) {
                 if let Some(font) = world
                     .book()
                     .select(family.as_str(), self.variant)
@@ -464,7 +465,7 @@ impl<'a> ShapedText<'a> {
         } else {
             None
         };
-        let mut chain = families(self.styles)
+        let mut chain = families(self.styles, world.library().strict_fonts)
             .filter(|family| family.covers().is_none_or(|c| c.is_match("-")))
             .map(|family| book.select(family.as_str(), self.variant))
             .chain(fallback_func.iter().map(|f| f()))
@@ -674,12 +675,13 @@ fn shape<'a>(
         styles,
         variant: variant(styles),
         features: features(styles),
-        fallback: TextElem::fallback_in(styles),
+        fallback: TextElem::fallback_in(styles) && !engine.world.library().strict_fonts,
         dir,
     };
 
     if !text.is_empty() {
-        shape_segment(&mut ctx, base, text, families(styles));
+        let strict = engine.world.library().strict_fonts;
+        shape_segment(&mut ctx, base, text, families(styles, strict));
     }
 
     track_and_space(&mut ctx);
@@ -3,7 +3,7 @@ use smallvec::smallvec;
 use typst_library::diag::SourceResult;
 use typst_library::engine::Engine;
 use typst_library::foundations::{Content, Context, Depth, Packed, StyleChain};
-use typst_library::introspection::Locator;
+use typst_library::introspection::{Locator, SplitLocator, Tag, TagElem};
 use typst_library::layout::grid::resolve::{Cell, CellGrid};
 use typst_library::layout::{Axes, Fragment, HAlignment, Regions, Sizing, VAlignment};
 use typst_library::model::{EnumElem, EnumParents, ListElem, Numbering, ParElem, ParbreakElem};
@@ -51,6 +51,7 @@ pub fn layout_list(
         cells.push(Cell::new(Content::empty(), locator.next(&())));
         cells.push(Cell::new(marker.clone(), locator.next(&marker.span())));
         cells.push(Cell::new(Content::empty(), locator.next(&())));
+        let body = tag_item(engine, &mut locator, item.clone().pack(), body);
         cells.push(Cell::new(
             body.styled(ListElem::set_depth(Depth(1))),
             locator.next(&item.body.span()),
@@ -141,6 +142,7 @@ pub fn layout_enum(
         cells.push(Cell::new(Content::empty(), locator.next(&())));
         cells.push(Cell::new(resolved, locator.next(&())));
         cells.push(Cell::new(Content::empty(), locator.next(&())));
+        let body = tag_item(engine, &mut locator, item.clone().pack(), body);
         cells.push(Cell::new(
             body.styled(EnumElem::set_parents(EnumParents(smallvec![number]))),
             locator.next(&item.body.span()),
@@ -163,3 +165,24 @@ pub fn layout_enum(
 
     layouter.layout(engine)
 }
+
+/// Surrounds the item's body with tags for the item, so that the frames tell
+/// where each item is, e.g. for tagged PDF export. The item's marker precedes
+/// the tags.
+fn tag_item(
+    engine: &Engine,
+    locator: &mut SplitLocator,
+    mut item: Content,
+    body: Content,
+) -> Content {
+    // Items are laid out by their list instead of being realized, so they
+    // are located here, like paragraph line markers.
+    let key = typst_utils::hash128(&item);
+    let loc = locator.next_location(engine.introspector, key);
+    item.set_location(loc);
+    Content::sequence([
+        TagElem::packed(Tag::Start(item)),
+        body,
+        TagElem::packed(Tag::End(loc, key)),
+    ])
+}
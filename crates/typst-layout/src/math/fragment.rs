@@ -7,7 +7,7 @@ use ttf_parser::{GlyphId, Rect};
 use typst_library::foundations::StyleChain;
 use typst_library::introspection::Tag;
 use typst_library::layout::{
-    Abs, Axis, Corner, Em, Frame, FrameItem, Point, Size, VAlignment,
+    Abs, Axis, Corner, Dir, Em, Frame, FrameItem, Point, Size, VAlignment,
 };
 use typst_library::math::{EquationElem, MathSize, TypstMC};
 use typst_library::text::{Font, Glyph, Lang, Region, TextElem, TextItem};
@@ -234,6 +234,7 @@ pub struct GlyphFragment {
     pub font: Font,
     pub lang: Lang,
     pub region: Option<Region>,
+    pub dir: Dir,
     pub fill: Paint,
     pub shift: Abs,
     pub width: Abs,
@@ -285,6 +286,7 @@ impl GlyphFragment {
             font: ctx.font.clone(),
             lang: TextElem::lang_in(styles),
             region: TextElem::region_in(styles),
+            dir: TextElem::dir_in(styles),
             fill: TextElem::fill_in(styles).as_decoration(),
             shift: TextElem::baseline_in(styles),
             font_size: TextElem::size_in(styles),
@@ -370,6 +372,7 @@ impl GlyphFragment {
             fill: self.fill,
             lang: self.lang,
             region: self.region,
+            dir: self.dir,
             text: self.c.into(),
             stroke: None,
             glyphs: vec![Glyph {
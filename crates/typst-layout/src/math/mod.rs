@@ -243,7 +243,7 @@ fn find_math_font(
 ) -> SourceResult<Font> {
     let variant = variant(styles);
     let world = engine.world;
-    let Some(font) = families(styles).find_map(|family| {
+    let Some(font) = families(styles, world.library().strict_fonts).find_map(|family| {
         let id = world.book().select(family.as_str(), variant)?;
         let font = world.font(id)?;
         let _ = font.ttf().tables().math?.constants?;
@@ -101,12 +101,15 @@ fn stretch_axis(ctx: &mut MathContext, base: &GlyphFragment) -> Option<Axis> {
             // As far as we know, there aren't any glyphs that have both
             // vertical and horizontal constructions. So for the time being, we
             // will assume that a glyph cannot have both.
-            ctx.engine.sink.warn(warning!(
-               base.span,
-               "glyph has both vertical and horizontal constructions";
-               hint: "this is probably a font bug";
-               hint: "please file an issue at https://github.com/typst/typst/issues"
-            ));
+            ctx.engine.sink.warn(
+                warning!(
+                   base.span,
+                   "glyph has both vertical and horizontal constructions";
+                   hint: "this is probably a font bug";
+                   hint: "please file an issue at https://github.com/typst/typst/issues"
+                )
+                .with_identifier("font-bug"),
+            );
 
             None
         }
@@ -206,7 +209,7 @@ fn assemble(
                        "glyph has assembly parts with overlap less than minConnectorOverlap";
                        hint: "its rendering may appear broken - this is probably a font bug";
                        hint: "please file an issue at https://github.com/typst/typst/issues"
-                    ));
+                    ).with_identifier("font-bug"));
                 }
 
                 advance -= max_overlap;
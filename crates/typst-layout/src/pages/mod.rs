@@ -5,13 +5,13 @@ mod finalize;
 mod run;
 
 use comemo::{Tracked, TrackedMut};
-use typst_library::diag::SourceResult;
+use typst_library::diag::{bail, SourceResult};
 use typst_library::engine::{Engine, Route, Sink, Traced};
-use typst_library::foundations::{Content, StyleChain};
+use typst_library::foundations::{Content, Repr, StyleChain};
 use typst_library::introspection::{
     Introspector, Locator, ManualPageCounter, SplitLocator, TagElem,
 };
-use typst_library::layout::{FrameItem, Page, PagedDocument, Point};
+use typst_library::layout::{Frame, FrameItem, Page, PagedDocument, Point};
 use typst_library::model::DocumentInfo;
 use typst_library::routines::{Arenas, Pair, RealizationKind, Routines};
 use typst_library::World;
@@ -123,8 +123,11 @@ fn layout_pages<'a>(
             Item::Run(..) => {
                 let layouted = runs.next().unwrap()?;
                 for layouted in layouted {
+                    Route::check_deadline();
                     let page = finalize(engine, &mut counter, &mut tags, layouted)?;
+                    check_glyphs(engine, &page.frame)?;
                     pages.push(page);
+                    PagedDocument::check_page_count(engine, pages.len())?;
                 }
             }
             Item::Parity(parity, initial, locator) => {
@@ -135,6 +138,7 @@ fn layout_pages<'a>(
                 let layouted = layout_blank_page(engine, locator.relayout(), *initial)?;
                 let page = finalize(engine, &mut counter, &mut tags, layouted)?;
                 pages.push(page);
+                PagedDocument::check_page_count(engine, pages.len())?;
             }
             Item::Tags(items) => {
                 tags.extend(
@@ -157,3 +161,28 @@ fn layout_pages<'a>(
 
     Ok(pages)
 }
+
+/// Ensures that the specified fonts display all text if fonts are strict, see
+/// [`Library::strict_fonts`](typst_library::Library::strict_fonts).
+fn check_glyphs(engine: &Engine, frame: &Frame) -> SourceResult<()> {
+    if !engine.world.library().strict_fonts {
+        return Ok(());
+    }
+    for (_, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => check_glyphs(engine, &group.frame)?,
+            FrameItem::Text(text) => {
+                if let Some(glyph) = text.glyphs.iter().find(|glyph| glyph.id == 0) {
+                    bail!(
+                        glyph.span.0,
+                        "the text {} could not be displayed with the specified fonts",
+                        text.text[glyph.range()].repr();
+                        hint: "strict fonts are enabled, so no other font is substituted"
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
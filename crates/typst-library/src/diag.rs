@@ -166,7 +166,39 @@ pub struct SourceDiagnostic {
     /// Additional hints to the user, indicating how this problem could be avoided
     /// or worked around.
     pub hints: EcoVec<EcoString>,
-}
+    /// A stable, kebab-case name for the kind of problem, one of
+    /// [`WARNING_IDENTIFIERS`] for warnings, or `timeout` for the error of an
+    /// aborted compilation.
+    pub identifier: Option<EcoString>,
+}
+
+/// The identifiers that warnings may carry. Several warnings can share one if
+/// they are about the same kind of problem.
+pub const WARNING_IDENTIFIERS: &[&str] = &[
+    "deprecated",
+    "discarded-content",
+    "empty-emph",
+    "empty-strong",
+    "font-bug",
+    "future-keyword",
+    "html-deep-heading",
+    "html-experimental",
+    "html-non-url-link",
+    "ignored-in-html",
+    "ignored-in-paged",
+    "ignored-in-paragraph",
+    "imprecise-decimal",
+    "ineffective-import",
+    "ineffective-show-rule",
+    "layout-not-converged",
+    "missing-alt-text",
+    "multiple-labels",
+    "overfull-box",
+    "redundant-import-rename",
+    "svg-foreign-object",
+    "unattached-label",
+    "unknown-font-family",
+];
 
 /// The severity of a [`SourceDiagnostic`].
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -186,6 +218,7 @@ impl SourceDiagnostic {
             trace: eco_vec![],
             message: message.into(),
             hints: eco_vec![],
+            identifier: None,
         }
     }
 
@@ -197,6 +230,7 @@ impl SourceDiagnostic {
             trace: eco_vec![],
             message: message.into(),
             hints: eco_vec![],
+            identifier: None,
         }
     }
 
@@ -216,6 +250,12 @@ impl SourceDiagnostic {
         self.hints.extend(hints);
         self
     }
+
+    /// Names the kind of problem, see [`WARNING_IDENTIFIERS`].
+    pub fn with_identifier(mut self, identifier: impl Into<EcoString>) -> Self {
+        self.identifier = Some(identifier.into());
+        self
+    }
 }
 
 impl From<SyntaxError> for SourceDiagnostic {
@@ -226,6 +266,7 @@ impl From<SyntaxError> for SourceDiagnostic {
             message: error.message,
             trace: eco_vec![],
             hints: error.hints,
+            identifier: None,
         }
     }
 }
@@ -243,7 +284,9 @@ impl DeprecationSink for () {
 impl DeprecationSink for (&mut Engine<'_>, Span) {
     /// Emits the deprecation message as a warning.
     fn emit(self, message: &str) {
-        self.0.sink.warn(SourceDiagnostic::warning(self.1, message));
+        self.0.sink.warn(
+            SourceDiagnostic::warning(self.1, message).with_identifier("deprecated"),
+        );
     }
 }
 
@@ -526,6 +569,13 @@ pub enum PackageError {
     VersionNotFound(PackageSpec, PackageVersion),
     /// Failed to retrieve the package through the network.
     NetworkFailed(Option<EcoString>),
+    /// Failed to resolve the host of the package registry.
+    DnsFailed(Option<EcoString>),
+    /// Failed to connect to the package registry.
+    ConnectionFailed(Option<EcoString>),
+    /// Failed to establish a secure connection to the package registry, e.g.
+    /// because its certificate is not trusted.
+    TlsFailed(Option<EcoString>),
     /// The package archive was malformed.
     MalformedArchive(Option<EcoString>),
     /// Another error.
@@ -551,6 +601,22 @@ impl Display for PackageError {
                 write!(f, "failed to download package ({err})")
             }
             Self::NetworkFailed(None) => f.pad("failed to download package"),
+            Self::DnsFailed(Some(err)) => {
+                write!(f, "failed to resolve the package registry ({err})")
+            }
+            Self::DnsFailed(None) => f.pad("failed to resolve the package registry"),
+            Self::ConnectionFailed(Some(err)) => {
+                write!(f, "failed to connect to the package registry ({err})")
+            }
+            Self::ConnectionFailed(None) => {
+                f.pad("failed to connect to the package registry")
+            }
+            Self::TlsFailed(Some(err)) => {
+                write!(f, "failed to connect securely to the package registry ({err})")
+            }
+            Self::TlsFailed(None) => {
+                f.pad("failed to connect securely to the package registry")
+            }
             Self::MalformedArchive(Some(err)) => {
                 write!(f, "failed to decompress package ({err})")
             }
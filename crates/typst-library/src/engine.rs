@@ -1,7 +1,9 @@
 //! Definition of the central compilation context.
 
+use std::cell::Cell;
 use std::collections::HashSet;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
 
 use comemo::{Track, Tracked, TrackedMut, Validate};
 use ecow::EcoVec;
@@ -63,20 +65,24 @@ impl Engine<'_> {
         // using `par_bridge` because it does not retain the ordering.
         let work: Vec<T> = iter.into_iter().collect();
 
-        // Work in parallel.
+        // Work in parallel. The workers are bound by the same deadline.
+        let deadline = Route::deadline();
         let mut pairs: Vec<(U, Sink)> = Vec::with_capacity(work.len());
         work.into_par_iter()
             .map(|value| {
-                let mut sink = Sink::new();
-                let mut engine = Engine {
-                    world,
-                    introspector,
-                    traced,
-                    sink: sink.track_mut(),
-                    route: route.clone(),
-                    routines,
-                };
-                (f(&mut engine, value), sink)
+                Route::with_deadline(deadline, || {
+                    let mut sink = Sink::new();
+                    let mut engine = Engine {
+                        world,
+                        introspector,
+                        traced,
+                        sink: sink.track_mut(),
+                        route: route.clone(),
+                        routines,
+                    };
+                    let output = f(&mut engine, value);
+                    (output, sink)
+                })
             })
             .collect_into_vec(&mut pairs);
 
@@ -159,6 +165,11 @@ impl Sink {
         self.warnings
     }
 
+    /// The warnings stored so far, without taking them.
+    pub fn peek_warnings(&self) -> &[SourceDiagnostic] {
+        &self.warnings
+    }
+
     /// Get the values for the traced span.
     pub fn values(self) -> EcoVec<(Value, Option<Styles>)> {
         self.values
@@ -290,6 +301,16 @@ impl<'a> Route<'a> {
     }
 }
 
+thread_local! {
+    /// When compilation on this thread is aborted, if ever.
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// The payload that compilation unwinds with when its deadline has passed, see
+/// [`Route::check_deadline`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DeadlineExceeded;
+
 /// The maximum nesting depths. They are different so that even if show rule and
 /// call checks are interleaved, for show rule problems we always get the show
 /// rule error. The lower the max depth for a kind of error, the higher its
@@ -304,8 +325,9 @@ impl Route<'_> {
     /// The maximum HTML nesting depth.
     const MAX_HTML_DEPTH: usize = 72;
 
-    /// The maximum function call nesting depth.
-    const MAX_CALL_DEPTH: usize = 80;
+    /// The default maximum function call nesting depth, see
+    /// [`Library::max_call_depth`](crate::Library::max_call_depth).
+    pub const DEFAULT_MAX_CALL_DEPTH: usize = 80;
 
     /// Ensures that we are within the maximum show rule depth.
     pub fn check_show_depth(&self) -> HintedStrResult<()> {
@@ -341,10 +363,65 @@ impl Route<'_> {
         Ok(())
     }
 
-    /// Ensures that we are within the maximum function call depth.
-    pub fn check_call_depth(&self) -> StrResult<()> {
-        if !self.within(Route::MAX_CALL_DEPTH) {
-            bail!("maximum function call depth exceeded");
+    /// Stack space that must be left for the nesting to go deeper. Below it,
+    /// the native stack could overflow, which aborts the process.
+    const STACK_RED_ZONE: usize = 1024 * 1024;
+
+    /// Ensures that there is enough native stack left to go deeper, however
+    /// deep the nesting is. This catches nesting that no depth limit counts,
+    /// e.g. of plain content blocks.
+    pub fn check_stack() -> HintedStrResult<()> {
+        // Stacker can't tell the remaining stack on WASM.
+        #[cfg(not(target_arch = "wasm32"))]
+        if stacker::remaining_stack().is_some_and(|it| it < Route::STACK_RED_ZONE) {
+            bail!(
+                "maximum nesting depth exceeded";
+                hint: "try to reduce the amount of nesting in your document",
+            );
+        }
+        Ok(())
+    }
+
+    /// Runs `f` with a deadline after which compilation on the current thread
+    /// is aborted, see [`check_deadline`](Self::check_deadline). The previous
+    /// deadline is restored afterwards, even if `f` unwinds.
+    /// [`parallelize`](Engine::parallelize) passes the deadline on to the
+    /// threads it runs tasks on.
+    pub fn with_deadline<T>(deadline: Option<Instant>, f: impl FnOnce() -> T) -> T {
+        struct Restore(Option<Instant>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                DEADLINE.set(self.0);
+            }
+        }
+
+        let _restore = Restore(DEADLINE.replace(deadline));
+        f()
+    }
+
+    /// When compilation on the current thread is aborted, if ever.
+    pub fn deadline() -> Option<Instant> {
+        DEADLINE.get()
+    }
+
+    /// Aborts compilation by unwinding with a [`DeadlineExceeded`] payload if
+    /// the current thread's deadline has passed.
+    ///
+    /// Unlike an error, this doesn't leave a failed result behind in the
+    /// memoization cache, which would be reused by the next compilation even
+    /// though it has time left. Whoever sets the deadline is expected to catch
+    /// the unwind. It doesn't run the panic hook.
+    pub fn check_deadline() {
+        if Route::deadline().is_some_and(|deadline| Instant::now() >= deadline) {
+            std::panic::resume_unwind(Box::new(DeadlineExceeded));
+        }
+    }
+
+    /// Ensures that we are within the given maximum function call depth.
+    pub fn check_call_depth(&self, max: usize) -> StrResult<()> {
+        if !self.within(max) {
+            bail!("maximum recursion depth exceeded");
         }
         Ok(())
     }
@@ -358,6 +435,13 @@ impl<'a> Route<'a> {
         self.id == Some(id) || self.outer.is_some_and(|outer| outer.contains(id))
     }
 
+    /// The ids that are part of the route, outermost first.
+    pub fn files(&self) -> EcoVec<FileId> {
+        let mut files = self.outer.map(|outer| outer.files()).unwrap_or_default();
+        files.extend(self.id);
+        files
+    }
+
     /// Whether the route's depth is less than or equal to the given depth.
     pub fn within(&self, depth: usize) -> bool {
         // We only need atomicity and no synchronization of other operations, so
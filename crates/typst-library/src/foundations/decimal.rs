@@ -330,13 +330,16 @@ fn warn_on_float_literal(engine: &mut Engine, span: Span) -> Option<()> {
     let source = engine.world.source(id).ok()?;
     let node = source.find(span)?;
     if node.is::<ast::Float>() {
-        engine.sink.warn(warning!(
-            span,
-            "creating a decimal using imprecise float literal";
-            hint: "use a string in the decimal constructor to avoid loss \
-                   of precision: `decimal({})`",
-            node.text().repr()
-        ));
+        engine.sink.warn(
+            warning!(
+                span,
+                "creating a decimal using imprecise float literal";
+                hint: "use a string in the decimal constructor to avoid loss \
+                       of precision: `decimal({})`",
+                node.text().repr()
+            )
+            .with_identifier("imprecise-decimal"),
+        );
     }
     Some(())
 }
@@ -377,6 +377,8 @@ fn unknown_variable(var: &str) -> HintedString {
             if var.matches('-').count() > 1 { "s" } else { "" },
             var.replace('-', " - ")
         ));
+    } else if var == "html" {
+        res.hint("the `html` module is only available when compiling to HTML");
     }
 
     res
@@ -1,6 +1,7 @@
 //! System-related things.
 
-use crate::foundations::{Dict, Module, Scope, Version};
+use crate::engine::Engine;
+use crate::foundations::{func, Dict, Module, Scope, Str, Value, Version};
 
 /// A module with system-related things.
 pub fn module(inputs: Dict) -> Module {
@@ -14,5 +15,34 @@ pub fn module(inputs: Dict) -> Module {
         ]),
     );
     scope.define("inputs", inputs);
+    scope.define_func::<context_input>();
     Module::new("sys", scope)
 }
+
+/// Reads one of the context inputs the embedder provides.
+///
+/// Unlike `sys.inputs`, which are the same for the whole document, context
+/// inputs are looked up by key where they are needed, so that different parts
+/// of a document can read different values, e.g. a dataset per chapter. The
+/// embedder may change them between compilations without changing the inputs.
+///
+/// The result depends on the embedder's state and not only on the sources:
+/// compiling the same sources with other context inputs gives another
+/// document.
+///
+/// ```example
+/// #sys.context-input("chapter-1", default: (:))
+/// ```
+#[func(name = "context-input", title = "Context Input")]
+pub fn context_input(
+    engine: &mut Engine,
+    /// The key of the input.
+    key: Str,
+    /// The value to return if there is no input with the key. Without one,
+    /// such inputs are `{none}`.
+    #[named]
+    default: Option<Value>,
+) -> Value {
+    let inputs = &engine.world.library().context_inputs;
+    inputs.get(&key).ok().cloned().or(default).unwrap_or_default()
+}
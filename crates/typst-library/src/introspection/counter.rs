@@ -402,6 +402,36 @@ impl Counter {
     pub fn select_any() -> Selector {
         CounterUpdateElem::elem().select()
     }
+
+    /// The key identifying this counter.
+    pub fn key(&self) -> &CounterKey {
+        &self.0
+    }
+
+    /// The counters that are explicitly updated somewhere in the document, in
+    /// the order of their first update.
+    pub fn updated(introspector: &Introspector) -> Vec<Counter> {
+        let mut counters: Vec<Counter> = vec![];
+        for elem in introspector.query(&Self::select_any()) {
+            let Some(update) = elem.to_packed::<CounterUpdateElem>() else { continue };
+            let counter = Counter::new(update.key.clone());
+            if !counters.contains(&counter) {
+                counters.push(counter);
+            }
+        }
+        counters
+    }
+
+    /// Gets the value of the counter at the end of the document.
+    pub fn final_state(&self, engine: &mut Engine) -> SourceResult<CounterState> {
+        let sequence = self.sequence(engine)?;
+        let (mut state, page) = sequence.last().unwrap().clone();
+        if self.is_page() {
+            let delta = engine.introspector.pages().get().saturating_sub(page.get());
+            state.step(NonZeroUsize::ONE, delta);
+        }
+        Ok(state)
+    }
 }
 
 #[scope]
@@ -498,13 +528,7 @@ impl Counter {
         span: Span,
     ) -> SourceResult<CounterState> {
         context.introspect().at(span)?;
-        let sequence = self.sequence(engine)?;
-        let (mut state, page) = sequence.last().unwrap().clone();
-        if self.is_page() {
-            let delta = engine.introspector.pages().get().saturating_sub(page.get());
-            state.step(NonZeroUsize::ONE, delta);
-        }
-        Ok(state)
+        self.final_state(engine)
     }
 
     /// Increases the value of the counter by one.
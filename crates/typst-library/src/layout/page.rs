@@ -6,6 +6,7 @@ use std::str::FromStr;
 use comemo::Track;
 use serde::{Serialize, Serializer};
 use serde::ser::SerializeMap;
+use typst_syntax::Span;
 use typst_utils::{singleton, NonZeroExt, Scalar};
 
 use crate::diag::{bail, SourceResult};
@@ -465,8 +466,23 @@ pub struct PagedDocument {
     pub introspector: Introspector,
 }
 
+impl PagedDocument {
+    /// Ensures that `count` pages are within the maximum of the world's
+    /// library, see [`Library::max_pages`](crate::Library::max_pages).
+    pub fn check_page_count(engine: &Engine, count: usize) -> SourceResult<()> {
+        let max = engine.world.library().max_pages;
+        if max != 0 && count > max {
+            bail!(
+                Span::detached(), "page limit exceeded";
+                hint: "the document must not have more than {max} pages"
+            );
+        }
+        Ok(())
+    }
+}
+
 /// A finished page.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Hash)]
 pub struct Page {
     /// The frame that defines the page.
     pub frame: Frame,
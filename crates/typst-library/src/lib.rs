@@ -34,10 +34,13 @@ use typst_syntax::{FileId, Source, Span};
 use typst_utils::{LazyHash, SmallBitSet};
 
 use crate::diag::FileResult;
-use crate::foundations::{Array, Binding, Bytes, Datetime, Dict, Module, Scope, Styles};
+use crate::engine::Route;
+use crate::foundations::{
+    Array, Binding, Bytes, Datetime, Dict, Module, Scope, Styles, Value,
+};
 use crate::layout::{Alignment, Dir};
 use crate::text::{Font, FontBook};
-use crate::visualize::Color;
+use crate::visualize::{Color, ImageLimits};
 
 /// The environment in which typesetting occurs.
 ///
@@ -152,6 +155,29 @@ pub struct Library {
     pub std: Binding,
     /// In-development features that were enabled.
     pub features: Features,
+    /// Whether show rules that transform elements are skipped, for extracting
+    /// the document's structure faster. Show-set rules still apply, as do
+    /// `show: ..` rules, which transform the rest of the document right away.
+    /// Positions may differ from the ones of a document with show rules.
+    pub skip_show_rules: bool,
+    /// The inputs read by key through `sys.context-input`.
+    pub context_inputs: Dict,
+    /// The maximum number of pages of a document, 0 if there is none. Layout
+    /// fails as soon as a document exceeds it.
+    pub max_pages: usize,
+    /// Limits on the size of the document's images.
+    pub image_limits: ImageLimits,
+    /// Whether documents must use exactly the fonts they specify. Then,
+    /// unknown font families are errors instead of warnings, text is not
+    /// shaped with fallback fonts, and layout fails on text that the specified
+    /// fonts can't display.
+    pub strict_fonts: bool,
+    /// Whether layout warns about boxes and blocks whose content is larger
+    /// than they are, like content that doesn't fit into a box of fixed width
+    /// or a table that is wider than the page.
+    pub overflow_warnings: bool,
+    /// How deeply function calls may nest before evaluation fails.
+    pub max_call_depth: usize,
 }
 
 impl Library {
@@ -159,6 +185,62 @@ impl Library {
     pub fn builder() -> LibraryBuilder {
         LibraryBuilder::default()
     }
+
+    /// Rebuilds the library with other features enabled.
+    ///
+    /// The inputs, the default styles and any definitions that were added to
+    /// the global scope after building are carried over.
+    pub fn with_features(&self, features: Features) -> Library {
+        let mut library = Library::builder()
+            .with_inputs(self.inputs())
+            .with_features(features)
+            .build();
+
+        // Definitions that no feature provides were added by the embedder.
+        let everything = Library::builder()
+            .with_features([Feature::Html].into_iter().collect())
+            .build();
+        for (name, binding) in self.global.scope().iter() {
+            if everything.global.scope().get(name).is_none() {
+                library.global.scope_mut().bind(name.clone(), binding.clone());
+            }
+        }
+
+        library.styles = self.styles.clone();
+        library.std = Binding::detached(library.global.clone());
+        library.skip_show_rules = self.skip_show_rules;
+        library.context_inputs = self.context_inputs.clone();
+        library.max_pages = self.max_pages;
+        library.image_limits = self.image_limits;
+        library.strict_fonts = self.strict_fonts;
+        library.overflow_warnings = self.overflow_warnings;
+        library.max_call_depth = self.max_call_depth;
+        library
+    }
+
+    /// Replaces the inputs visible through `sys.inputs`.
+    pub fn set_inputs(&mut self, inputs: Dict) {
+        let sys = Value::Module(foundations::sys::module(inputs));
+        match self.global.scope_mut().get_mut("sys").map(Binding::write) {
+            Some(Ok(value)) => *value = sys,
+            _ => {
+                self.global.scope_mut().bind("sys".into(), Binding::detached(sys));
+            }
+        }
+        self.std = Binding::detached(self.global.clone());
+    }
+
+    /// The inputs visible through `sys.inputs`.
+    pub fn inputs(&self) -> Dict {
+        let Some(Value::Module(sys)) = self.global.scope().get("sys").map(Binding::read)
+        else {
+            return Dict::new();
+        };
+        match sys.scope().get("inputs").map(Binding::read) {
+            Some(Value::Dict(inputs)) => inputs.clone(),
+            _ => Dict::new(),
+        }
+    }
 }
 
 impl Default for Library {
@@ -203,6 +285,13 @@ impl LibraryBuilder {
             styles: Styles::new(),
             std: Binding::detached(global),
             features: self.features,
+            skip_show_rules: false,
+            context_inputs: Dict::new(),
+            max_pages: 0,
+            image_limits: ImageLimits::default(),
+            strict_fonts: false,
+            overflow_warnings: false,
+            max_call_depth: Route::DEFAULT_MAX_CALL_DEPTH,
         }
     }
 }
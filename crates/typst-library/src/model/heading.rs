@@ -271,14 +271,17 @@ impl Show for Packed<HeadingElem> {
             // a `<h2>`.
             let level = self.resolve_level(styles).get();
             if level >= 6 {
-                engine.sink.warn(warning!(span,
+                engine.sink.warn(
+                    warning!(span,
                     "heading of level {} was transformed to \
                     <div role=\"heading\" aria-level=\"{}\">, which is not \
                     supported by all assistive technology",
                     level, level + 1;
                     hint: "HTML only supports <h1> to <h6>, not <h{}>", level + 1;
                     hint: "you may want to restructure your document so that \
-                          it doesn't contain deep headings"));
+                          it doesn't contain deep headings")
+                    .with_identifier("html-deep-heading"),
+                );
                 HtmlElem::new(tag::div)
                     .with_body(Some(realized))
                     .with_attr(attr::role, "heading")
@@ -111,17 +111,29 @@ impl Show for Packed<LinkElem> {
         let body = self.body.clone();
 
         Ok(if TargetElem::target_in(styles).is_html() {
-            if let LinkTarget::Dest(Destination::Url(url)) = &self.dest {
+            let href = match &self.dest {
+                LinkTarget::Dest(Destination::Url(url)) => Some(url.clone().into_inner()),
+                // Labels become fragments, which embedders may map to other pages.
+                LinkTarget::Label(label) => {
+                    engine.introspector.query_label(*label).at(self.span())?;
+                    Some(eco_format!("#{}", label.resolve()))
+                }
+                LinkTarget::Dest(_) => None,
+            };
+            if let Some(href) = href {
                 HtmlElem::new(tag::a)
-                    .with_attr(attr::href, url.clone().into_inner())
+                    .with_attr(attr::href, href)
                     .with_body(Some(body))
                     .pack()
                     .spanned(self.span())
             } else {
-                engine.sink.warn(warning!(
-                    self.span(),
-                    "non-URL links are not yet supported by HTML export"
-                ));
+                engine.sink.warn(
+                    warning!(
+                        self.span(),
+                        "non-URL links are not yet supported by HTML export"
+                    )
+                    .with_identifier("html-non-url-link"),
+                );
                 body
             }
         } else {
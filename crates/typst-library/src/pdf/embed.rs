@@ -84,9 +84,10 @@ pub struct EmbedElem {
 impl Show for Packed<EmbedElem> {
     fn show(&self, engine: &mut Engine, styles: StyleChain) -> SourceResult<Content> {
         if TargetElem::target_in(styles) == Target::Html {
-            engine
-                .sink
-                .warn(warning!(self.span(), "embed was ignored during HTML export"));
+            engine.sink.warn(
+                warning!(self.span(), "embed was ignored during HTML export")
+                    .with_identifier("ignored-in-html"),
+            );
         }
         Ok(Content::empty())
     }
@@ -4,7 +4,7 @@ use std::ops::Range;
 use ecow::EcoString;
 use typst_syntax::Span;
 
-use crate::layout::{Abs, Em};
+use crate::layout::{Abs, Dir, Em};
 use crate::text::{is_default_ignorable, Font, Lang, Region};
 use crate::visualize::{FixedStroke, Paint};
 
@@ -23,6 +23,10 @@ pub struct TextItem {
     pub lang: Lang,
     /// The region of the text.
     pub region: Option<Region>,
+    /// The text direction in effect, as set with `text.dir` or following the
+    /// language. Embedded runs of the other direction, like a Latin word in
+    /// Arabic text, keep it.
+    pub dir: Dir,
     /// The item's plain text.
     pub text: EcoString,
     /// The glyphs. The number of glyphs may be different from the number of
@@ -161,7 +161,7 @@ pub struct TextElem {
     #[parse({
         let font_list: Option<Spanned<FontList>> = args.named("font")?;
         if let Some(list) = &font_list {
-            check_font_list(engine, list);
+            check_font_list(engine, list)?;
         }
         font_list.map(|font_list| font_list.v)
     })]
@@ -916,8 +916,13 @@ cast! {
     values: Array => Self(values.into_iter().map(|v| v.cast()).collect::<HintedStrResult<_>>()?),
 }
 
-/// Resolve a prioritized iterator over the font families.
-pub fn families(styles: StyleChain) -> impl Iterator<Item = &FontFamily> + Clone {
+/// Resolve a prioritized iterator over the font families. Strict fonts, see
+/// [`Library::strict_fonts`](crate::Library::strict_fonts), disable the
+/// fallback.
+pub fn families(
+    styles: StyleChain,
+    strict: bool,
+) -> impl Iterator<Item = &FontFamily> + Clone {
     let fallbacks = singleton!(Vec<FontFamily>, {
         [
             "libertinus serif",
@@ -931,7 +936,8 @@ pub fn families(styles: StyleChain) -> impl Iterator<Item = &FontFamily> + Clone
         .collect()
     });
 
-    let tail = if TextElem::fallback_in(styles) { fallbacks.as_slice() } else { &[] };
+    let fallback = TextElem::fallback_in(styles) && !strict;
+    let tail = if fallback { fallbacks.as_slice() } else { &[] };
     TextElem::font_in(styles).into_iter().chain(tail.iter())
 }
 
@@ -1378,18 +1384,25 @@ pub fn is_default_ignorable(c: char) -> bool {
     DEFAULT_IGNORABLE_DATA.as_borrowed().contains(c)
 }
 
-/// Checks for font families that are not available.
-fn check_font_list(engine: &mut Engine, list: &Spanned<FontList>) {
+/// Checks for font families that are not available. They are errors if fonts
+/// are strict, see [`Library::strict_fonts`](crate::Library::strict_fonts).
+fn check_font_list(engine: &mut Engine, list: &Spanned<FontList>) -> SourceResult<()> {
     let book = engine.world.book();
     for family in &list.v {
         if !book.contains_family(family.as_str()) {
-            engine.sink.warn(warning!(
-                list.span,
-                "unknown font family: {}",
-                family.as_str(),
-            ));
+            if engine.world.library().strict_fonts {
+                bail!(
+                    list.span, "unknown font family: {}", family.as_str();
+                    hint: "strict fonts are enabled, so no other font is substituted"
+                );
+            }
+            engine.sink.warn(
+                warning!(list.span, "unknown font family: {}", family.as_str())
+                    .with_identifier("unknown-font-family"),
+            );
         }
     }
+    Ok(())
 }
 
 #[cfg(test)]
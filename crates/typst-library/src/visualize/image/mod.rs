@@ -16,7 +16,7 @@ use serde::{Serialize, Serializer};
 use typst_syntax::{Span, Spanned};
 use typst_utils::LazyHash;
 
-use crate::diag::{SourceResult, StrResult};
+use crate::diag::{bail, SourceResult, StrResult};
 use crate::engine::Engine;
 use crate::foundations::{
     cast, elem, func, scope, Bytes, Cast, Content, Derived, NativeElement, Packed, Show,
@@ -350,6 +350,41 @@ impl Image {
     }
 }
 
+/// Limits on the images of a document, bounding the memory that untrusted
+/// documents can take up. Zero means that there is no limit.
+///
+/// They are carried by the [`Library`](crate::Library), so memoized results
+/// computed under different limits are not mixed up.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ImageLimits {
+    /// The maximum size of an image file in bytes.
+    pub max_bytes: u64,
+    /// The maximum number of pixels of a decoded raster image.
+    pub max_pixels: u64,
+}
+
+impl ImageLimits {
+    /// Ensures that an image file of `len` bytes is within the limit.
+    pub fn check_bytes(&self, len: usize) -> StrResult<()> {
+        let max = self.max_bytes;
+        if max != 0 && len as u64 > max {
+            bail!("image file is {len} bytes large, but at most {max} are allowed");
+        }
+        Ok(())
+    }
+
+    /// Ensures that a raster image of the given dimensions is within the limit.
+    /// Checked before decoding, so that oversized images are never allocated.
+    pub fn check_pixels(&self, width: u32, height: u32) -> StrResult<()> {
+        let max = self.max_pixels;
+        let pixels = width as u64 * height as u64;
+        if max != 0 && pixels > max {
+            bail!("image has {width}x{height} pixels, but at most {max} are allowed");
+        }
+        Ok(())
+    }
+}
+
 impl Debug for Image {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         f.debug_struct("Image")
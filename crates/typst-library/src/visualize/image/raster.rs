@@ -13,6 +13,7 @@ use image::{
 
 use crate::diag::{bail, StrResult};
 use crate::foundations::{cast, dict, Bytes, Cast, Dict, Smart, Value};
+use crate::visualize::ImageLimits;
 
 /// A decoded raster image.
 #[derive(Clone, Hash)]
@@ -34,7 +35,17 @@ impl RasterImage {
         format: impl Into<RasterFormat>,
         icc: Smart<Bytes>,
     ) -> StrResult<Self> {
-        Self::new_impl(data, format.into(), icc)
+        Self::with_limits(data, format, icc, ImageLimits::default())
+    }
+
+    /// Decode a raster image, failing if it exceeds the given limits.
+    pub fn with_limits(
+        data: Bytes,
+        format: impl Into<RasterFormat>,
+        icc: Smart<Bytes>,
+        limits: ImageLimits,
+    ) -> StrResult<Self> {
+        Self::new_impl(data, format.into(), icc, limits)
     }
 
     /// Create a raster image with optional properties set to the default.
@@ -49,14 +60,18 @@ impl RasterImage {
         data: Bytes,
         format: RasterFormat,
         icc: Smart<Bytes>,
+        limits: ImageLimits,
     ) -> StrResult<RasterImage> {
         let (dynamic, icc, dpi) = match format {
             RasterFormat::Exchange(format) => {
                 fn decode<T: ImageDecoder>(
                     decoder: ImageResult<T>,
                     icc: Smart<Bytes>,
-                ) -> ImageResult<(image::DynamicImage, Option<Bytes>)> {
-                    let mut decoder = decoder?;
+                    limits: ImageLimits,
+                ) -> StrResult<(image::DynamicImage, Option<Bytes>)> {
+                    let mut decoder = decoder.map_err(format_image_error)?;
+                    let (width, height) = decoder.dimensions();
+                    limits.check_pixels(width, height)?;
                     let icc = icc.custom().or_else(|| {
                         decoder
                             .icc_profile()
@@ -65,18 +80,18 @@ impl RasterImage {
                             .filter(|icc| !icc.is_empty())
                             .map(Bytes::new)
                     });
-                    decoder.set_limits(Limits::default())?;
-                    let dynamic = image::DynamicImage::from_decoder(decoder)?;
+                    decoder.set_limits(Limits::default()).map_err(format_image_error)?;
+                    let dynamic = image::DynamicImage::from_decoder(decoder)
+                        .map_err(format_image_error)?;
                     Ok((dynamic, icc))
                 }
 
                 let cursor = io::Cursor::new(&data);
                 let (mut dynamic, icc) = match format {
-                    ExchangeFormat::Jpg => decode(JpegDecoder::new(cursor), icc),
-                    ExchangeFormat::Png => decode(PngDecoder::new(cursor), icc),
-                    ExchangeFormat::Gif => decode(GifDecoder::new(cursor), icc),
-                }
-                .map_err(format_image_error)?;
+                    ExchangeFormat::Jpg => decode(JpegDecoder::new(cursor), icc, limits),
+                    ExchangeFormat::Png => decode(PngDecoder::new(cursor), icc, limits),
+                    ExchangeFormat::Gif => decode(GifDecoder::new(cursor), icc, limits),
+                }?;
 
                 let exif = exif::Reader::new()
                     .read_from_container(&mut std::io::Cursor::new(&data))
@@ -97,6 +112,7 @@ impl RasterImage {
                 if format.width == 0 || format.height == 0 {
                     bail!("zero-sized images are not allowed");
                 }
+                limits.check_pixels(format.width, format.height)?;
 
                 let channels = match format.encoding {
                     PixelEncoding::Rgb8 => 3,
@@ -12,7 +12,9 @@ use typst_syntax::Span;
 use xmp_writer::{DateTime, LangId, RenditionClass, XmpWriter};
 
 use crate::page::PdfPageLabel;
-use crate::{hash_base64, outline, TextStrExt, Timestamp, Timezone, WithEverything};
+use crate::{
+    hash_base64, outline, tags, TextStrExt, Timestamp, Timezone, WithEverything,
+};
 
 /// Write the document catalog.
 pub fn write_catalog(
@@ -39,6 +41,10 @@ pub fn write_catalog(
     // Write the page labels.
     let page_labels = write_page_labels(pdf, alloc, &ctx);
 
+    // Write the structure tree.
+    let struct_tree_root_id =
+        ctx.tags.map(|tree| tags::write_struct_tree(pdf, alloc, &ctx, tree));
+
     // Write the document information.
     let info_ref = alloc.bump();
     let mut info = pdf.document_info(info_ref);
@@ -77,8 +83,10 @@ pub fn write_catalog(
     }
 
     let creator = eco_format!("Typst {}", env!("CARGO_PKG_VERSION"));
-    info.creator(TextStr(&creator));
-    xmp.creator_tool(&creator);
+    if !ctx.options.omit_creator {
+        info.creator(TextStr(&creator));
+        xmp.creator_tool(&creator);
+    }
 
     let keywords = &ctx.document.info.keywords;
     if !keywords.is_empty() {
@@ -153,6 +161,10 @@ pub fn write_catalog(
         xmp.pdfa_conformance(conformance);
     }
 
+    if ctx.tags.is_some() {
+        xmp.pdfua_part(1);
+    }
+
     let xmp_buf = xmp.finish(None);
     let meta_ref = alloc.bump();
     pdf.stream(meta_ref, xmp_buf.as_bytes())
@@ -166,7 +178,13 @@ pub fn write_catalog(
     let catalog_ref = alloc.bump();
     let mut catalog = pdf.catalog(catalog_ref);
     catalog.pages(ctx.page_tree_ref);
-    catalog.viewer_preferences().direction(dir);
+    let mut viewer_preferences = catalog.viewer_preferences();
+    viewer_preferences.direction(dir);
+    if ctx.tags.is_some() {
+        // PDF/UA requires the title to be shown instead of the file name.
+        viewer_preferences.pair(Name(b"DisplayDocTitle"), true);
+    }
+    viewer_preferences.finish();
     catalog.metadata(meta_ref);
 
     let has_dests = !ctx.references.named_destinations.dests.is_empty();
@@ -218,6 +236,11 @@ pub fn write_catalog(
         catalog.lang(TextStr(lang.as_str()));
     }
 
+    if let Some(struct_tree_root_id) = struct_tree_root_id {
+        catalog.pair(Name(b"StructTreeRoot"), struct_tree_root_id);
+        catalog.insert(Name(b"MarkInfo")).dict().pair(Name(b"Marked"), true);
+    }
+
     if ctx.options.standards.pdfa {
         catalog
             .output_intents()
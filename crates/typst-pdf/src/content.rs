@@ -40,15 +40,22 @@ use crate::{deflate_deferred, AbsExt, ContentExt, EmExt, PdfOptions, StrExt};
 /// glyph].
 ///
 /// [color glyph]: `crate::color_font`
+///
+/// If `tagged` is set, every item is wrapped into its own marked-content
+/// sequence, numbered in the order of [`crate::tags::StructTree::build`].
 pub fn build(
     options: &PdfOptions,
     resources: &mut Resources<()>,
     frame: &Frame,
     fill: Option<Paint>,
     color_glyph_width: Option<f32>,
+    tagged: bool,
 ) -> SourceResult<Encoded> {
     let size = frame.size();
     let mut ctx = Builder::new(options, resources, size);
+    if tagged {
+        ctx.next_mcid = Some(0);
+    }
 
     if let Some(width) = color_glyph_width {
         ctx.content.start_color_glyph(width);
@@ -63,8 +70,15 @@ pub fn build(
     );
 
     if let Some(fill) = fill {
+        // The background is not part of the logical structure.
+        if tagged {
+            ctx.content.begin_marked_content(Name(b"Artifact"));
+        }
         let shape = Geometry::Rect(frame.size()).filled(fill);
         write_shape(&mut ctx, Point::zero(), &shape)?;
+        if tagged {
+            ctx.content.end_marked_content();
+        }
     }
 
     // Encode the frame into the content stream.
@@ -112,6 +126,9 @@ pub struct Builder<'a, R = ()> {
     uses_opacities: bool,
     /// All clickable links that are present in this content.
     links: Vec<(Destination, Rect)>,
+    /// The identifier of the next marked-content sequence, if the content is
+    /// tagged.
+    next_mcid: Option<i32>,
 }
 
 impl<'a, R> Builder<'a, R> {
@@ -129,6 +146,7 @@ impl<'a, R> Builder<'a, R> {
             state: State::new(size),
             saves: vec![],
             links: vec![],
+            next_mcid: None,
         }
     }
 }
@@ -374,10 +392,12 @@ pub(crate) fn write_frame(ctx: &mut Builder, frame: &Frame) -> SourceResult<()>
         let y = pos.y.to_f32();
         match item {
             FrameItem::Group(group) => write_group(ctx, pos, group)?,
-            FrameItem::Text(text) => write_text(ctx, pos, text)?,
-            FrameItem::Shape(shape, _) => write_shape(ctx, pos, shape)?,
+            FrameItem::Text(text) => write_marked(ctx, |ctx| write_text(ctx, pos, text))?,
+            FrameItem::Shape(shape, _) => {
+                write_marked(ctx, |ctx| write_shape(ctx, pos, shape))?
+            }
             FrameItem::Image(image, size, span) => {
-                write_image(ctx, x, y, image, *size, *span)?
+                write_marked(ctx, |ctx| write_image(ctx, x, y, image, *size, *span))?
             }
             FrameItem::Link(dest, size) => write_link(ctx, pos, dest, *size),
             FrameItem::Tag(_) => {}
@@ -386,6 +406,24 @@ pub(crate) fn write_frame(ctx: &mut Builder, frame: &Frame) -> SourceResult<()>
     Ok(())
 }
 
+/// Encode an item, in its own marked-content sequence if the content is
+/// tagged.
+fn write_marked(
+    ctx: &mut Builder,
+    f: impl FnOnce(&mut Builder) -> SourceResult<()>,
+) -> SourceResult<()> {
+    let Some(mcid) = ctx.next_mcid else { return f(ctx) };
+    ctx.next_mcid = Some(mcid + 1);
+
+    let mut marked = ctx.content.begin_marked_content_with_properties(Name(b"Span"));
+    marked.properties().pair(Name(b"MCID"), mcid);
+    marked.finish();
+
+    f(ctx)?;
+    ctx.content.end_marked_content();
+    Ok(())
+}
+
 /// Encode a group into the content stream.
 fn write_group(ctx: &mut Builder, pos: Point, group: &GroupItem) -> SourceResult<()> {
     let translation = Transform::translate(pos.x, pos.y);
@@ -10,7 +10,8 @@ use typst_library::pdf::{EmbedElem, EmbeddedFileRelationship};
 use crate::catalog::{document_date, pdf_date};
 use crate::{deflate, NameExt, PdfChunk, StrExt, WithGlobalRefs};
 
-/// Query for all [`EmbedElem`] and write them and their file specifications.
+/// Query for all [`EmbedElem`] and write them, together with the attachments of
+/// the options, and their file specifications.
 ///
 /// This returns a map of embedding names and references so that we can later
 /// add them to the catalog's `/Names` dictionary.
@@ -21,18 +22,21 @@ pub fn write_embedded_files(
     let mut embedded_files = BTreeMap::default();
 
     let elements = ctx.document.introspector.query(&EmbedElem::elem().select());
-    for elem in &elements {
+    let embeds = elements
+        .iter()
+        .map(|elem| elem.to_packed::<EmbedElem>().unwrap())
+        .chain(ctx.options.attachments);
+    for embed in embeds {
         if !ctx.options.standards.embedded_files {
             // PDF/A-2 requires embedded files to be PDF/A-1 or PDF/A-2,
             // which we don't currently check.
             bail!(
-                elem.span(),
+                embed.span(),
                 "file embeddings are not currently supported for PDF/A-2";
                 hint: "PDF/A-3 supports arbitrary embedded files"
             );
         }
 
-        let embed = elem.to_packed::<EmbedElem>().unwrap();
         if embed.path.derived.len() > Str::PDFA_LIMIT {
             bail!(embed.span(), "embedded file path is too long");
         }
@@ -40,7 +44,7 @@ pub fn write_embedded_files(
         let id = embed_file(ctx, &mut chunk, embed)?;
         if embedded_files.insert(embed.path.derived.clone(), id).is_some() {
             bail!(
-                elem.span(),
+                embed.span(),
                 "duplicate embedded file for path `{}`", embed.path.derived;
                 hint: "embedded file paths must be unique",
             );
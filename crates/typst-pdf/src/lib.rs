@@ -13,6 +13,7 @@ mod named_destination;
 mod outline;
 mod page;
 mod resources;
+mod tags;
 mod tiling;
 
 use std::collections::{BTreeMap, HashMap};
@@ -24,9 +25,10 @@ use base64::Engine;
 use ecow::EcoString;
 use pdf_writer::{Chunk, Name, Pdf, Ref, Str, TextStr};
 use serde::{Deserialize, Serialize};
-use typst_library::diag::{bail, SourceResult, StrResult};
-use typst_library::foundations::{Datetime, Smart};
+use typst_library::diag::{bail, SourceResult, StrResult, Warned};
+use typst_library::foundations::{Datetime, Packed, Smart};
 use typst_library::layout::{Abs, Em, PageRanges, PagedDocument, Transform};
+use typst_library::pdf::EmbedElem;
 use typst_library::text::Font;
 use typst_library::visualize::Image;
 use typst_syntax::Span;
@@ -45,14 +47,38 @@ use crate::page::{alloc_page_refs, traverse_pages, write_page_tree, EncodedPage}
 use crate::resources::{
     alloc_resources_refs, write_resource_dictionaries, Resources, ResourcesRefs,
 };
+use crate::tags::StructTree;
 use crate::tiling::{write_tilings, PdfTiling};
 
 /// Export a document into a PDF file.
 ///
 /// Returns the raw bytes making up the PDF file.
-#[typst_macros::time(name = "pdf")]
 pub fn pdf(document: &PagedDocument, options: &PdfOptions) -> SourceResult<Vec<u8>> {
-    PdfBuilder::new(document, options)
+    pdf_with_warnings(document, options).output
+}
+
+/// Export a document into a PDF file, together with the warnings that came up
+/// during export.
+///
+/// Currently, only tagged export produces warnings, for content that cannot be
+/// made accessible.
+#[typst_macros::time(name = "pdf")]
+pub fn pdf_with_warnings(
+    document: &PagedDocument,
+    options: &PdfOptions,
+) -> Warned<SourceResult<Vec<u8>>> {
+    let tags = options.standards.tagged.then(|| StructTree::build(document, options));
+    let output = export(document, options, tags.as_ref());
+    let warnings = tags.map(|tags| tags.warnings).unwrap_or_default();
+    Warned { output, warnings }
+}
+
+fn export(
+    document: &PagedDocument,
+    options: &PdfOptions,
+    tags: Option<&StructTree>,
+) -> SourceResult<Vec<u8>> {
+    PdfBuilder::new(document, options, tags)
         .phase(|builder| builder.run(traverse_pages))?
         .phase(|builder| {
             Ok(GlobalRefs {
@@ -101,6 +127,12 @@ pub struct PdfOptions<'a> {
     pub page_ranges: Option<PageRanges>,
     /// A list of PDF standards that Typst will enforce conformance with.
     pub standards: PdfStandards,
+    /// Whether to leave out that the PDF was created by Typst, both from the
+    /// document information dictionary and the XMP metadata.
+    pub omit_creator: bool,
+    /// Files to embed in addition to those of the document's `pdf.embed`
+    /// calls, with the same restrictions.
+    pub attachments: &'a [Packed<EmbedElem>],
 }
 
 /// A timestamp with timezone information.
@@ -160,6 +192,9 @@ pub struct PdfStandards {
     pub(crate) embedded_files: bool,
     /// Part of the PDF/A standard.
     pub(crate) pdfa_part: Option<(i32, &'static str)>,
+    /// Whether to write a structure tree and mark up the page contents, as
+    /// required by PDF/UA.
+    pub(crate) tagged: bool,
 }
 
 impl PdfStandards {
@@ -178,6 +213,7 @@ impl PdfStandards {
             pdfa,
             embedded_files: !a2b,
             pdfa_part: pdfa.then_some((if a2b { 2 } else { 3 }, "B")),
+            tagged: list.contains(&PdfStandard::UA_1),
         })
     }
 }
@@ -190,7 +226,12 @@ impl Debug for PdfStandards {
 
 impl Default for PdfStandards {
     fn default() -> Self {
-        Self { pdfa: false, embedded_files: true, pdfa_part: None }
+        Self {
+            pdfa: false,
+            embedded_files: true,
+            pdfa_part: None,
+            tagged: false,
+        }
     }
 }
 
@@ -210,6 +251,9 @@ pub enum PdfStandard {
     /// PDF/A-3b.
     #[serde(rename = "a-3b")]
     A_3b,
+    /// PDF/UA-1.
+    #[serde(rename = "ua-1")]
+    UA_1,
 }
 
 /// A struct to build a PDF following a fixed succession of phases.
@@ -247,6 +291,8 @@ struct WithDocument<'a> {
     document: &'a PagedDocument,
     /// Settings for PDF export.
     options: &'a PdfOptions<'a>,
+    /// The logical structure of the document, if it is exported as tagged PDF.
+    tags: Option<&'a StructTree>,
 }
 
 /// At this point, resources were listed, but they don't have any reference
@@ -256,6 +302,7 @@ struct WithDocument<'a> {
 struct WithResources<'a> {
     document: &'a PagedDocument,
     options: &'a PdfOptions<'a>,
+    tags: Option<&'a StructTree>,
     /// The content of the pages encoded as PDF content streams.
     ///
     /// The pages are at the index corresponding to their page number, but they
@@ -291,6 +338,7 @@ impl<'a> From<(WithDocument<'a>, (Vec<Option<EncodedPage>>, Resources<()>))>
         Self {
             document: previous.document,
             options: previous.options,
+            tags: previous.tags,
             pages,
             resources,
         }
@@ -305,6 +353,7 @@ impl<'a> From<(WithDocument<'a>, (Vec<Option<EncodedPage>>, Resources<()>))>
 struct WithGlobalRefs<'a> {
     document: &'a PagedDocument,
     options: &'a PdfOptions<'a>,
+    tags: Option<&'a StructTree>,
     pages: Vec<Option<EncodedPage>>,
     /// Resources are the same as in previous phases, but each dictionary now has a reference.
     resources: Resources,
@@ -317,6 +366,7 @@ impl<'a> From<(WithResources<'a>, GlobalRefs)> for WithGlobalRefs<'a> {
         Self {
             document: previous.document,
             options: previous.options,
+            tags: previous.tags,
             pages: previous.pages,
             resources: previous.resources.with_refs(&globals.resources),
             globals,
@@ -350,6 +400,7 @@ struct References {
 struct WithRefs<'a> {
     document: &'a PagedDocument,
     options: &'a PdfOptions<'a>,
+    tags: Option<&'a StructTree>,
     globals: GlobalRefs,
     pages: Vec<Option<EncodedPage>>,
     resources: Resources,
@@ -362,6 +413,7 @@ impl<'a> From<(WithGlobalRefs<'a>, References)> for WithRefs<'a> {
         Self {
             document: previous.document,
             options: previous.options,
+            tags: previous.tags,
             globals: previous.globals,
             pages: previous.pages,
             resources: previous.resources,
@@ -376,6 +428,7 @@ impl<'a> From<(WithGlobalRefs<'a>, References)> for WithRefs<'a> {
 struct WithEverything<'a> {
     document: &'a PagedDocument,
     options: &'a PdfOptions<'a>,
+    tags: Option<&'a StructTree>,
     globals: GlobalRefs,
     pages: Vec<Option<EncodedPage>>,
     resources: Resources,
@@ -395,6 +448,7 @@ impl<'a> From<(WithRefs<'a>, Ref)> for WithEverything<'a> {
         Self {
             document: previous.document,
             options: previous.options,
+            tags: previous.tags,
             globals: previous.globals,
             resources: previous.resources,
             references: previous.references,
@@ -406,11 +460,15 @@ impl<'a> From<(WithRefs<'a>, Ref)> for WithEverything<'a> {
 
 impl<'a> PdfBuilder<WithDocument<'a>> {
     /// Start building a PDF for a Typst document.
-    fn new(document: &'a PagedDocument, options: &'a PdfOptions<'a>) -> Self {
+    fn new(
+        document: &'a PagedDocument,
+        options: &'a PdfOptions<'a>,
+        tags: Option<&'a StructTree>,
+    ) -> Self {
         Self {
             alloc: Ref::new(1),
             pdf: Pdf::new(),
-            state: WithDocument { document, options },
+            state: WithDocument { document, options, tags },
         }
     }
 }
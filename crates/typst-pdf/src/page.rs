@@ -71,6 +71,7 @@ fn construct_page(
             &page.frame,
             page.fill_or_transparent(),
             None,
+            options.standards.tagged,
         )?,
         label: None,
     })
@@ -187,6 +188,12 @@ fn write_page(
     page_writer.contents(content_id);
     page_writer.pair(Name(b"Resources"), ctx.resources.reference);
 
+    if ctx.tags.is_some() {
+        // The page's key in the parent tree is its index, see `write_struct_tree`.
+        page_writer.pair(Name(b"StructParents"), i as i32);
+        page_writer.pair(Name(b"Tabs"), Name(b"S"));
+    }
+
     if page.content.uses_opacities {
         page_writer
             .group()
@@ -0,0 +1,298 @@
+//! Tagged PDF: the structure tree and the marked content it refers to.
+//!
+//! Every text run, shape and image on a page becomes its own marked-content
+//! sequence, numbered in the order in which [`content::build`] encounters
+//! them. The structure tree is derived from the introspection tags in the
+//! frames and attributes each sequence to the innermost element it lies in.
+//!
+//! [`content::build`]: crate::content::build
+
+use std::collections::{BTreeMap, HashSet};
+
+use ecow::{eco_format, EcoString, EcoVec};
+use pdf_writer::{Finish, Name, Pdf, Ref, TextStr};
+use typst_library::diag::SourceDiagnostic;
+use typst_library::foundations::{Content, StyleChain};
+use typst_library::introspection::{Location, Tag};
+use typst_library::layout::{Frame, FrameItem, PagedDocument};
+use typst_library::math::EquationElem;
+use typst_library::model::{
+    EnumElem, EnumItem, FigureElem, FootnoteElem, HeadingElem, ListElem, ListItem,
+    OutlineElem, QuoteElem,
+};
+use typst_syntax::Span;
+
+use crate::{PdfOptions, WithEverything};
+
+/// The logical structure of a document.
+pub struct StructTree {
+    /// All structure elements. The first one is the document itself.
+    elems: Vec<StructElem>,
+    /// Warnings about content that cannot be made accessible.
+    pub warnings: EcoVec<SourceDiagnostic>,
+}
+
+/// A node of the structure tree.
+struct StructElem {
+    /// The standard structure type.
+    role: EcoString,
+    /// An alternative description of the element's content.
+    alt: Option<EcoString>,
+    /// The index of the parent element.
+    parent: usize,
+    kids: Vec<StructKid>,
+}
+
+enum StructKid {
+    /// Another structure element, by index.
+    Elem(usize),
+    /// A marked-content sequence on the page with the given index.
+    Content { page: usize, mcid: i32 },
+}
+
+impl StructTree {
+    /// Derive the structure tree from the document's frames.
+    pub fn build(document: &PagedDocument, options: &PdfOptions) -> Self {
+        let mut builder = TreeBuilder {
+            elems: vec![StructElem {
+                role: "Document".into(),
+                alt: None,
+                parent: 0,
+                kids: vec![],
+            }],
+            stack: vec![],
+            label: None,
+            warnings: EcoVec::new(),
+            warned: HashSet::new(),
+        };
+
+        for (i, page) in document.pages.iter().enumerate() {
+            // Tags on non-exported pages still open and close elements, but
+            // there is no content to refer to.
+            let exported = options
+                .page_ranges
+                .as_ref()
+                .is_none_or(|ranges| ranges.includes_page_index(i));
+            builder.visit(&page.frame, exported.then_some(i), &mut 0);
+        }
+
+        StructTree { elems: builder.elems, warnings: builder.warnings }
+    }
+}
+
+/// Walks the frames, keeping track of the open elements.
+struct TreeBuilder {
+    elems: Vec<StructElem>,
+    /// The open elements with their locations, innermost last.
+    stack: Vec<(Location, usize)>,
+    /// The list item and its label whose body is yet to come.
+    label: Option<(usize, usize)>,
+    warnings: EcoVec<SourceDiagnostic>,
+    /// Spans that were already warned about, to warn only once per element.
+    warned: HashSet<Span>,
+}
+
+impl TreeBuilder {
+    fn visit(&mut self, frame: &Frame, page: Option<usize>, mcid: &mut i32) {
+        for (_, item) in frame.items() {
+            match item {
+                FrameItem::Group(group) => self.visit(&group.frame, page, mcid),
+                FrameItem::Tag(Tag::Start(elem)) => self.open(elem),
+                FrameItem::Tag(Tag::End(loc, _)) => self.close(*loc),
+                FrameItem::Text(_) | FrameItem::Shape(..) | FrameItem::Image(..) => {
+                    if let FrameItem::Image(image, _, span) = item {
+                        self.image(image.alt(), *span);
+                    }
+                    if let Some(page) = page {
+                        let mut current = self.current();
+                        if self.elems[current].role == "L" {
+                            current = self.label(current);
+                        }
+                        self.elems[current]
+                            .kids
+                            .push(StructKid::Content { page, mcid: *mcid });
+                        *mcid += 1;
+                    }
+                }
+                FrameItem::Link(..) => {}
+            }
+        }
+    }
+
+    /// The index of the innermost open element.
+    fn current(&self) -> usize {
+        self.stack.last().map_or(0, |&(_, index)| index)
+    }
+
+    fn open(&mut self, elem: &Content) {
+        let Some(loc) = elem.location() else { return };
+        let parent = self.current();
+
+        // The tags of an item surround its body, its marker comes before them.
+        if elem.is::<ListItem>() || elem.is::<EnumItem>() {
+            if self.elems[parent].role == "L" {
+                let item = match self.label.take() {
+                    Some((item, _)) if self.elems[item].parent == parent => item,
+                    _ => self.push("LI".into(), parent),
+                };
+                let body = self.push("LBody".into(), item);
+                self.stack.push((loc, body));
+            }
+            return;
+        }
+
+        let Some(role) = role(elem) else { return };
+        if elem.is::<EquationElem>() {
+            self.warn(elem.span(), "equation has no alternative description");
+        }
+
+        let index = self.push(role, parent);
+        self.stack.push((loc, index));
+    }
+
+    /// Adds an element to the parent and returns its index.
+    fn push(&mut self, role: EcoString, parent: usize) -> usize {
+        let index = self.elems.len();
+        self.elems.push(StructElem { role, alt: None, parent, kids: vec![] });
+        self.elems[parent].kids.push(StructKid::Elem(index));
+        index
+    }
+
+    /// The label of the list's next item, which holds the content directly in
+    /// the list, i.e. the item's bullet or number.
+    fn label(&mut self, list: usize) -> usize {
+        match self.label {
+            Some((item, label)) if self.elems[item].parent == list => label,
+            _ => {
+                let item = self.push("LI".into(), list);
+                let label = self.push("Lbl".into(), item);
+                self.label = Some((item, label));
+                label
+            }
+        }
+    }
+
+    fn close(&mut self, loc: Location) {
+        if let Some(depth) = self.stack.iter().rposition(|&(l, _)| l == loc) {
+            self.stack.truncate(depth);
+        }
+    }
+
+    /// Lends the image's description to the figure it is in, or warns if it
+    /// has none.
+    fn image(&mut self, alt: Option<&str>, span: Span) {
+        let Some(alt) = alt else {
+            self.warn(span, "image has no alternative description");
+            return;
+        };
+        let elem = &mut self.elems[self.current()];
+        if elem.role == "Figure" && elem.alt.is_none() {
+            elem.alt = Some(alt.into());
+        }
+    }
+
+    fn warn(&mut self, span: Span, message: &str) {
+        if self.warned.insert(span) {
+            self.warnings.push(
+                SourceDiagnostic::warning(span, message)
+                    .with_hint("PDF/UA requires a description of non-textual content")
+                    .with_identifier("missing-alt-text"),
+            );
+        }
+    }
+}
+
+/// The standard structure type of an element, if it is part of the
+/// structure tree. The items of lists are handled by [`TreeBuilder::open`].
+///
+/// Term lists are laid out as a whole, so their terms and descriptions cannot
+/// be told apart; they are attributed to the enclosing element.
+fn role(elem: &Content) -> Option<EcoString> {
+    let styles = StyleChain::default();
+    if let Some(heading) = elem.to_packed::<HeadingElem>() {
+        let level = heading.resolve_level(styles).get().min(6);
+        Some(eco_format!("H{level}"))
+    } else if let Some(quote) = elem.to_packed::<QuoteElem>() {
+        Some(if quote.block(styles) { "BlockQuote" } else { "Quote" }.into())
+    } else if elem.is::<ListElem>() || elem.is::<EnumElem>() {
+        Some("L".into())
+    } else if elem.is::<FigureElem>() {
+        Some("Figure".into())
+    } else if elem.is::<EquationElem>() {
+        Some("Formula".into())
+    } else if elem.is::<FootnoteElem>() {
+        Some("Note".into())
+    } else if elem.is::<OutlineElem>() {
+        Some("TOC".into())
+    } else {
+        None
+    }
+}
+
+/// Write the structure tree and its parent tree. Returns the reference of the
+/// structure tree root.
+pub fn write_struct_tree(
+    pdf: &mut Pdf,
+    alloc: &mut Ref,
+    ctx: &WithEverything,
+    tree: &StructTree,
+) -> Ref {
+    let root_ref = alloc.bump();
+    let refs: Vec<Ref> = tree.elems.iter().map(|_| alloc.bump()).collect();
+
+    // For every page, the element that each marked-content sequence belongs
+    // to, indexed by the sequence's identifier.
+    let mut parents: BTreeMap<usize, Vec<Ref>> = BTreeMap::new();
+
+    for (i, elem) in tree.elems.iter().enumerate() {
+        let mut dict = pdf.indirect(refs[i]).dict();
+        dict.pair(Name(b"Type"), Name(b"StructElem"));
+        dict.pair(Name(b"S"), Name(elem.role.as_bytes()));
+        dict.pair(Name(b"P"), if i == 0 { root_ref } else { refs[elem.parent] });
+        if let Some(alt) = &elem.alt {
+            dict.pair(Name(b"Alt"), TextStr(alt));
+        }
+
+        let mut kids = dict.insert(Name(b"K")).array();
+        for kid in &elem.kids {
+            match *kid {
+                StructKid::Elem(index) => {
+                    kids.item(refs[index]);
+                }
+                StructKid::Content { page, mcid } => {
+                    let Some(page_ref) = ctx.globals.pages[page] else { continue };
+                    let mut mcr = kids.push().dict();
+                    mcr.pair(Name(b"Type"), Name(b"MCR"));
+                    mcr.pair(Name(b"Pg"), page_ref);
+                    mcr.pair(Name(b"MCID"), mcid);
+                    mcr.finish();
+
+                    let parents = parents.entry(page).or_default();
+                    let mcid = mcid as usize;
+                    if parents.len() <= mcid {
+                        parents.resize(mcid + 1, refs[0]);
+                    }
+                    parents[mcid] = refs[i];
+                }
+            }
+        }
+        kids.finish();
+        dict.finish();
+    }
+
+    let mut root = pdf.indirect(root_ref).dict();
+    root.pair(Name(b"Type"), Name(b"StructTreeRoot"));
+    root.pair(Name(b"K"), refs[0]);
+    root.pair(Name(b"ParentTreeNextKey"), ctx.document.pages.len() as i32);
+    let mut parent_tree = root.insert(Name(b"ParentTree")).dict();
+    let mut nums = parent_tree.insert(Name(b"Nums")).array();
+    for (page, parents) in &parents {
+        nums.item(*page as i32);
+        nums.push().array().items(parents.iter().copied());
+    }
+    nums.finish();
+    parent_tree.finish();
+    root.finish();
+
+    root_ref
+}
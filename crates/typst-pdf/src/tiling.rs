@@ -111,6 +111,7 @@ fn register_pattern(
         pattern.frame(),
         None,
         None,
+        false,
     )?;
 
     let pdf_pattern = PdfTiling {
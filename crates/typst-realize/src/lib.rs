@@ -12,7 +12,7 @@ use bumpalo::collections::{String as BumpString, Vec as BumpVec};
 use comemo::Track;
 use ecow::EcoString;
 use typst_library::diag::{bail, At, SourceResult};
-use typst_library::engine::Engine;
+use typst_library::engine::{Engine, Route};
 use typst_library::foundations::{
     Content, Context, ContextElem, Element, NativeElement, Recipe, RecipeIndex, Selector,
     SequenceElem, Show, ShowSet, Style, StyleChain, StyledElem, Styles, SymbolElem,
@@ -237,6 +237,9 @@ fn visit<'a>(
         return Ok(());
     }
 
+    Route::check_stack().at(content.span())?;
+    Route::check_deadline();
+
     // Transformations for math content based on the realization kind. Needs
     // to happen before show rules.
     if visit_math_rules(s, content, styles)? {
@@ -302,7 +305,9 @@ fn visit_math_rules<'a>(
         // In normal realization, we apply regex show rules to consecutive
         // textual elements via `TEXTUAL` grouping. However, in math, this is
         // not desirable, so we just do it on a per-element basis.
-        if let Some(elem) = content.to_packed::<SymbolElem>() {
+        if show_rules_skipped(s.engine) {
+            // No regex show rules to apply.
+        } else if let Some(elem) = content.to_packed::<SymbolElem>() {
             if let Some(m) =
                 find_regex_match_in_str(elem.text.encode_utf8(&mut [0; 4]), styles)
             {
@@ -420,6 +425,12 @@ fn visit_show_rules<'a>(
     Ok(true)
 }
 
+/// Whether show rules that transform elements are skipped, see
+/// [`Library::skip_show_rules`](typst_library::Library::skip_show_rules).
+fn show_rules_skipped(engine: &Engine) -> bool {
+    engine.world.library().skip_show_rules
+}
+
 /// Inspects a target element and the current styles and determines how to
 /// proceed with the styling.
 fn verdict<'a>(
@@ -470,7 +481,7 @@ fn verdict<'a>(
         }
 
         // If we already have a show step, don't look for one.
-        if step.is_some() {
+        if step.is_some() || show_rules_skipped(engine) {
             continue;
         }
 
@@ -1039,6 +1050,10 @@ fn finish_list_like<T: ListLike>(grouped: Grouped) -> SourceResult<()> {
 /// Visit textual elements in `s.sink[start..]` and apply regex show rules to
 /// them.
 fn visit_textual(s: &mut State, start: usize) -> SourceResult<bool> {
+    if show_rules_skipped(s.engine) {
+        return Ok(false);
+    }
+
     // Try to find a regex match in the grouped textual elements.
     if let Some(m) = find_regex_match_in_elems(s, &s.sink[start..]) {
         collapse_spaces(&mut s.sink, start);
@@ -16,16 +16,59 @@ use typst_library::visualize::{Color, Geometry, Paint};
 ///
 /// This renders the page at the given number of pixels per point and returns
 /// the resulting `tiny-skia` pixel buffer.
-#[typst_macros::time(name = "render")]
 pub fn render(page: &Page, pixel_per_pt: f32) -> sk::Pixmap {
-    let size = page.frame.size();
+    render_with_options(page, pixel_per_pt, RenderOptions::default())
+}
+
+/// Options for [`render_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenderOptions {
+    /// Whether to smooth the edges of shapes and glyphs. Without it, a pixel
+    /// is either fully covered or not at all, so edges are jagged, but crisp
+    /// at integer coordinates.
+    pub anti_alias: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self { anti_alias: true }
+    }
+}
+
+/// Export a page into a raster image, like [`render`], but with the given
+/// options.
+pub fn render_with_options(
+    page: &Page,
+    pixel_per_pt: f32,
+    options: RenderOptions,
+) -> sk::Pixmap {
+    render_region(page, pixel_per_pt, Point::zero(), page.frame.size(), options).unwrap()
+}
+
+/// Export the rectangle of a page at `origin` with the given `size` into a
+/// raster image. The pixels are the same as the ones of that rectangle in the
+/// page's [`render_with_options`], but only the rectangle is rasterized, so
+/// small parts can be rendered at high resolutions. `None` if the image would
+/// be too large.
+#[typst_macros::time(name = "render")]
+pub fn render_region(
+    page: &Page,
+    pixel_per_pt: f32,
+    origin: Point,
+    size: Size,
+    options: RenderOptions,
+) -> Option<sk::Pixmap> {
     let pxw = (pixel_per_pt * size.x.to_f32()).round().max(1.0) as u32;
     let pxh = (pixel_per_pt * size.y.to_f32()).round().max(1.0) as u32;
 
-    let ts = sk::Transform::from_scale(pixel_per_pt, pixel_per_pt);
-    let state = State::new(size, ts, pixel_per_pt);
+    let ts = sk::Transform::from_scale(pixel_per_pt, pixel_per_pt)
+        .pre_translate(-origin.x.to_f32(), -origin.y.to_f32());
+    let state = State {
+        anti_alias: options.anti_alias,
+        ..State::new(page.frame.size(), ts, pixel_per_pt)
+    };
 
-    let mut canvas = sk::Pixmap::new(pxw, pxh).unwrap();
+    let mut canvas = sk::Pixmap::new(pxw, pxh)?;
 
     if let Some(fill) = page.fill_or_white() {
         if let Paint::Solid(color) = fill {
@@ -38,7 +81,7 @@ pub fn render(page: &Page, pixel_per_pt: f32) -> sk::Pixmap {
 
     render_frame(&mut canvas, state, &page.frame);
 
-    canvas
+    Some(canvas)
 }
 
 /// Export a document with potentially multiple pages into a single raster image.
@@ -91,6 +134,8 @@ struct State<'a> {
     pixel_per_pt: f32,
     /// The size of the first hard frame in the hierarchy.
     size: Size,
+    /// Whether to smooth edges, see [`RenderOptions`].
+    anti_alias: bool,
 }
 
 impl State<'_> {
@@ -100,6 +145,7 @@ impl State<'_> {
             transform,
             container_transform: transform,
             pixel_per_pt,
+            anti_alias: true,
             ..Default::default()
         }
     }
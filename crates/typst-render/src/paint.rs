@@ -251,6 +251,7 @@ pub fn to_sk_paint<'a>(
         }
     }
 
+    sk_paint.anti_alias &= state.anti_alias;
     sk_paint
 }
 
@@ -275,7 +276,10 @@ pub fn render_tiling_frame(state: &State, tilings: &Tiling) -> sk::Pixmap {
 
     // Render the tilings into a new canvas.
     let ts = sk::Transform::from_scale(state.pixel_per_pt, state.pixel_per_pt);
-    let temp_state = State::new(tilings.size(), ts, state.pixel_per_pt);
+    let temp_state = State {
+        anti_alias: state.anti_alias,
+        ..State::new(tilings.size(), ts, state.pixel_per_pt)
+    };
     crate::render_frame(&mut canvas, temp_state, tilings.frame());
     canvas
 }
@@ -155,6 +155,16 @@ fn render_outline_glyph(
     Some(())
 }
 
+/// The glyph's coverage of a pixel, either partial or, without anti-aliasing,
+/// full or none.
+fn coverage(state: &State, coverage: u8) -> u8 {
+    match coverage {
+        _ if state.anti_alias => coverage,
+        128.. => u8::MAX,
+        _ => 0,
+    }
+}
+
 fn write_bitmap<S: PaintSampler>(
     canvas: &mut sk::Pixmap,
     bitmap: &Bitmap,
@@ -178,7 +188,7 @@ fn write_bitmap<S: PaintSampler>(
         let pixels = bytemuck::cast_slice_mut::<u8, u32>(pixmap.data_mut());
         for x in 0..mw {
             for y in 0..mh {
-                let alpha = bitmap.coverage[(y * mw + x) as usize];
+                let alpha = coverage(state, bitmap.coverage[(y * mw + x) as usize]);
 
                 // To sample at the correct position, we need to convert each
                 // pixel's position in the bitmap (x and y) to its final
@@ -225,7 +235,7 @@ fn write_bitmap<S: PaintSampler>(
         for x in left.clamp(0, cw)..right.clamp(0, cw) {
             for y in top.clamp(0, ch)..bottom.clamp(0, ch) {
                 let ai = ((y - top) * mw + (x - left)) as usize;
-                let cov = bitmap.coverage[ai];
+                let cov = coverage(state, bitmap.coverage[ai]);
                 if cov == 0 {
                     continue;
                 }
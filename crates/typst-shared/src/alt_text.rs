@@ -0,0 +1,106 @@
+use crate::compile::compile_paged;
+use crate::extended_info::{
+    ExtendedSourceDiagnostic, ExtendedSpan, ExtendedWarned, Resolve,
+};
+use crate::java_world::JavaWorld;
+use crate::memory_management::JavaResult;
+use serde::{Deserialize, Serialize};
+use typst::foundations::StyleChain;
+use typst::introspection::Tag;
+use typst::layout::{Frame, FrameItem};
+use typst::model::FigureElem;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AltTextEntry {
+    /// `image` or `figure`.
+    pub element: String,
+    /// The image's `alt`, or the plain text of the figure's caption. `None` if
+    /// there is none.
+    pub alt: Option<String>,
+    /// Zero-based index of the page the element is on.
+    pub page: i64,
+    pub span: ExtendedSpan,
+}
+
+/// Lists the document's images and figures in order with their descriptions,
+/// to find the ones that lack them. Images in figures are listed on their own.
+/// Images are found in the laid out pages, so hidden ones are not listed.
+#[no_mangle]
+pub extern "C" fn list_alt_text(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<ExtendedWarned<Result<Vec<AltTextEntry>, Vec<ExtendedSourceDiagnostic>>>>
+{
+    JavaResult::pack(compile_paged(world_ptr, |world, document, _| {
+        let mut entries = vec![];
+        for (i, page) in document.pages.iter().enumerate() {
+            collect(world, &page.frame, i, &mut entries);
+        }
+        Ok(entries)
+    }))
+}
+
+fn collect(
+    world: &JavaWorld,
+    frame: &Frame,
+    page: usize,
+    entries: &mut Vec<AltTextEntry>,
+) {
+    for (_, item) in frame.items() {
+        let (element, alt, span) = match item {
+            FrameItem::Group(group) => {
+                collect(world, &group.frame, page, entries);
+                continue;
+            }
+            FrameItem::Image(image, _, span) => {
+                ("image", image.alt().map(Into::into), *span)
+            }
+            FrameItem::Tag(Tag::Start(elem)) => {
+                let Some(figure) = elem.to_packed::<FigureElem>() else { continue };
+                let caption = figure.caption(StyleChain::default());
+                let caption = caption.as_ref().map(|it| it.body.plain_text().to_string());
+                ("figure", caption, elem.span())
+            }
+            _ => continue,
+        };
+        entries.push(AltTextEntry {
+            element: element.to_string(),
+            alt: alt.filter(|it: &String| !it.trim().is_empty()),
+            page: page as i64,
+            span: span.resolve(world),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{add_file, json, world};
+
+    const SVG: &str =
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"/>"#;
+
+    #[test]
+    fn test_list_alt_text() {
+        let world = world(
+            "#image(\"/dot.svg\", alt: \"A dot\")\n\
+             #figure(image(\"/dot.svg\"), caption: [Dots])",
+        );
+        add_file(world, "/dot.svg", SVG);
+        let entries = json(list_alt_text(world));
+        let entries = entries["output"]["Ok"].as_array().unwrap();
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry["element"].clone(), entry["alt"].clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("image".into(), "A dot".into()),
+                ("figure".into(), "Dots".into()),
+                ("image".into(), serde_json::Value::Null),
+            ]
+        );
+        free_world(world);
+    }
+}
@@ -23,6 +23,11 @@ impl<T: Clone> CacheCell<T> {
         self.accessed
     }
 
+    /// The processed data, if it was loaded successfully.
+    pub(crate) fn data(&self) -> Option<&T> {
+        self.data.as_ref()?.as_ref().ok()
+    }
+
     /// Marks the cell as not yet accessed in preparation of the next
     /// compilation.
     pub(crate) fn reset(&mut self) {
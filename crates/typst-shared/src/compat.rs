@@ -0,0 +1,182 @@
+use crate::imports::package_manifest;
+use crate::java_world::JavaWorld;
+use crate::memory_management::JavaResult;
+use serde::Serialize;
+use std::collections::HashSet;
+use typst::syntax::package::PackageVersion;
+use typst::syntax::{ast, FileId, LinkedNode};
+use typst::utils::tick;
+use typst::World;
+
+#[derive(Debug, Serialize)]
+pub struct CompatInfo {
+    /// The minimum Typst version the document appears to require, if anything
+    /// tells.
+    pub min_version: Option<PackageVersion>,
+    /// Whether this compiler is at least `min_version`.
+    pub compatible: bool,
+    /// The in-development features the document uses, like `html`.
+    pub features: Vec<String>,
+}
+
+/// Estimates which Typst versions and features the document needs, from the
+/// syntax of the main file and the files it imports or includes by a literal
+/// path. The minimum version comes from the `compiler` field of the manifests of
+/// imported packages and from comparisons like `sys.version >= version(0, 12)`.
+/// The `html` feature is used if its module or the `target` function are.
+///
+/// Nothing is compiled, so documents that don't compile with this compiler can
+/// be checked, but versions or features used in other ways are not detected.
+#[no_mangle]
+pub extern "C" fn document_compat(world_ptr: *mut JavaWorld) -> JavaResult<CompatInfo> {
+    tick!();
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let mut scan = Scan::default();
+    scan.file(&world, world.main());
+    let _ = Box::into_raw(world); // Not to drop the world!
+    let compatible = scan
+        .min_version
+        .is_none_or(|version| PackageVersion::compiler() >= version);
+    let mut features = vec![];
+    if scan.html {
+        features.push("html".to_string());
+    }
+    JavaResult::pack(CompatInfo {
+        min_version: scan.min_version,
+        compatible,
+        features,
+    })
+}
+
+#[derive(Default)]
+struct Scan {
+    seen: HashSet<FileId>,
+    min_version: Option<PackageVersion>,
+    html: bool,
+}
+
+impl Scan {
+    fn file(&mut self, world: &JavaWorld, id: FileId) {
+        if !self.seen.insert(id) {
+            return;
+        }
+        if let Ok(source) = world.source(id) {
+            self.node(world, id, &LinkedNode::new(source.root()));
+        }
+    }
+
+    fn node(&mut self, world: &JavaWorld, id: FileId, node: &LinkedNode) {
+        if let Some(expr) = node.cast::<ast::Expr>() {
+            self.expr(world, id, expr);
+        }
+        for child in node.children() {
+            self.node(world, id, &child);
+        }
+    }
+
+    fn expr(&mut self, world: &JavaWorld, id: FileId, expr: ast::Expr) {
+        match expr {
+            ast::Expr::ModuleImport(import) => self.path(world, id, import.source()),
+            ast::Expr::ModuleInclude(include) => self.path(world, id, include.source()),
+            ast::Expr::FieldAccess(access) => {
+                self.html |= is_ident(access.target(), "html");
+            }
+            ast::Expr::FuncCall(call) => {
+                self.html |= is_ident(call.callee(), "target");
+            }
+            ast::Expr::Binary(binary) => {
+                let bound = match binary.op() {
+                    ast::BinOp::Geq | ast::BinOp::Gt => {
+                        is_sys_version(binary.lhs()).then(|| version(binary.rhs()))
+                    }
+                    ast::BinOp::Leq | ast::BinOp::Lt => {
+                        is_sys_version(binary.rhs()).then(|| version(binary.lhs()))
+                    }
+                    _ => None,
+                };
+                if let Some(bound) = bound.flatten() {
+                    self.require(bound);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Scans the file or package an `import` or `include` refers to.
+    fn path(&mut self, world: &JavaWorld, id: FileId, source: ast::Expr) {
+        let ast::Expr::Str(path) = source else { return };
+        let path = path.get();
+        if !path.starts_with('@') {
+            self.file(world, id.join(&path));
+            return;
+        }
+        let Ok((manifest_id, manifest)) = package_manifest(world, &path) else {
+            return;
+        };
+        if let Some(bound) = manifest.package.compiler {
+            self.require(PackageVersion {
+                major: bound.major,
+                minor: bound.minor.unwrap_or(0),
+                patch: bound.patch.unwrap_or(0),
+            });
+        }
+        self.file(world, manifest_id.join(&manifest.package.entrypoint));
+    }
+
+    fn require(&mut self, version: PackageVersion) {
+        self.min_version = self.min_version.max(Some(version));
+    }
+}
+
+fn is_ident(expr: ast::Expr, name: &str) -> bool {
+    matches!(expr, ast::Expr::Ident(ident) if ident.as_str() == name)
+}
+
+/// Whether the expression is `sys.version`.
+fn is_sys_version(expr: ast::Expr) -> bool {
+    matches!(expr, ast::Expr::FieldAccess(access)
+        if is_ident(access.target(), "sys") && access.field().as_str() == "version")
+}
+
+/// The version of a call like `version(0, 12, 0)`, with missing parts as zero.
+fn version(expr: ast::Expr) -> Option<PackageVersion> {
+    let ast::Expr::FuncCall(call) = expr else { return None };
+    if !is_ident(call.callee(), "version") {
+        return None;
+    }
+    let mut parts = vec![];
+    for arg in call.args().items() {
+        let ast::Arg::Pos(ast::Expr::Int(int)) = arg else { return None };
+        parts.push(u32::try_from(int.get()).ok()?);
+    }
+    let part = |i: usize| parts.get(i).copied().unwrap_or(0);
+    Some(PackageVersion { major: part(0), minor: part(1), patch: part(2) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{add_file, json, world};
+
+    #[test]
+    fn test_document_compat_reports_html() {
+        let world = world("#html.elem(\"div\")[Hi]");
+        let compat = json(document_compat(world));
+        assert_eq!(compat["features"], serde_json::json!(["html"]));
+        assert!(compat["min_version"].is_null());
+        assert_eq!(compat["compatible"], true);
+        free_world(world);
+    }
+
+    #[test]
+    fn test_document_compat_reads_version_checks_of_included_files() {
+        let world = world("#include \"chapter.typ\"");
+        add_file(world, "/chapter.typ", "#if sys.version >= version(99, 1) [New]");
+        let compat = json(document_compat(world));
+        assert_eq!(compat["min_version"], "99.1.0");
+        assert_eq!(compat["compatible"], false);
+        assert_eq!(compat["features"], serde_json::json!([]));
+        free_world(world);
+    }
+}
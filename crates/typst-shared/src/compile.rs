@@ -1,21 +1,122 @@
-use crate::extended_info::{ExtendedSourceDiagnostic, ExtendedWarned, Resolve};
-use crate::java_world::JavaWorld;
-use crate::memory_management::{Base16ByteArray, JavaResult};
+use crate::exception::Except;
+use crate::extended_info::{
+    ExtendedSourceDiagnostic, ExtendedSpan, ExtendedWarned, Resolve,
+};
+use crate::java_world::{report_warning, JavaWorld, WarningCallback};
+use crate::font_index;
+use crate::memory_management::{
+    evict_after_compile, Base16ByteArray, JavaResult, ThickBytePtr,
+};
+use crate::memory_world::MemoryWorld;
+use crate::pdf::{export_pdf, PdfExportOptions};
+use crate::throw;
+use crate::watermark::Stamp;
+use base64::Engine;
 use chrono::{Datelike, Timelike};
+use parking_lot::Mutex;
 use serde::Serialize;
-use typst::diag::{Warned};
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use std::{mem, panic};
+use tiny_skia::{Color, Pixmap, PixmapPaint, Transform};
+use typst_render::RenderOptions;
+use typst::diag::{bail, SourceDiagnostic, SourceResult, Warned};
 use typst::foundations::Datetime;
-use typst::html::HtmlDocument;
-use typst::layout::{Page, PagedDocument};
-use typst::utils::tick;
+use typst::html::{attr, tag, HtmlAttr, HtmlDocument, HtmlElement, HtmlNode};
+use typst::ecow::{eco_format, eco_vec, EcoVec};
+use typst::engine::{DeadlineExceeded, Route};
+use typst::layout::{Abs, Page, PagedDocument, Point, Size};
+use typst::syntax::Span;
+use typst::utils::{hash128, tick, LazyHash};
+use typst::visualize::ImageLimits;
+use typst::{Document, Feature, Features, Library, World};
+
+/// Sets how deeply function calls may nest in the world's documents before
+/// evaluation fails with a "maximum recursion depth exceeded" diagnostic at the
+/// offending call. Non-positive values restore the default.
+#[no_mangle]
+pub extern "C" fn set_max_call_depth(world_ptr: *mut JavaWorld, depth: i64) {
+    tick!();
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    world.library.max_call_depth =
+        if depth > 0 { depth as usize } else { Route::DEFAULT_MAX_CALL_DEPTH };
+    let _ = Box::into_raw(world); // Not to drop the world!
+}
+
+/// Sets how many pages the world's documents may have before layout fails with
+/// a "page limit exceeded" diagnostic. Non-positive values remove the limit.
+#[no_mangle]
+pub extern "C" fn set_max_pages(world_ptr: *mut JavaWorld, max: i64) {
+    tick!();
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    world.library.max_pages = max.max(0) as usize;
+    let _ = Box::into_raw(world); // Not to drop the world!
+}
+
+/// Sets whether documents must use exactly the fonts they specify. Then, an
+/// unknown font family is an error instead of a warning, and text that the
+/// specified fonts can't display fails the compilation instead of falling back
+/// to other fonts.
+#[no_mangle]
+pub extern "C" fn set_strict_fonts(
+    world_ptr: *mut JavaWorld,
+    strict: i32, // 1 -- true, 0 -- false
+) {
+    tick!();
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    world.library.strict_fonts = strict == 1;
+    let _ = Box::into_raw(world); // Not to drop the world!
+}
+
+/// Sets whether compilation warns about boxes and blocks whose content is
+/// larger than they are, like text that doesn't fit into a box of fixed width
+/// or a table that is wider than the page. Clipped content is not reported.
+#[no_mangle]
+pub extern "C" fn set_overflow_warnings(
+    world_ptr: *mut JavaWorld,
+    enabled: i32, // 1 -- true, 0 -- false
+) {
+    tick!();
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    world.library.overflow_warnings = enabled == 1;
+    let _ = Box::into_raw(world); // Not to drop the world!
+}
+
+/// Sets how large the world's images may be: `max_bytes` bounds the size of
+/// image files and `max_pixels` the number of pixels of raster images, which is
+/// checked before they are decoded. Larger images fail with a diagnostic at the
+/// `image` call. Non-positive values remove the respective limit.
+#[no_mangle]
+pub extern "C" fn set_image_limits(
+    world_ptr: *mut JavaWorld,
+    max_bytes: i64,
+    max_pixels: i64,
+) {
+    tick!();
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    world.library.image_limits = ImageLimits {
+        max_bytes: max_bytes.max(0) as u64,
+        max_pixels: max_pixels.max(0) as u64,
+    };
+    let _ = Box::into_raw(world); // Not to drop the world!
+}
 
 #[no_mangle]
 pub extern "C" fn compile_html(
     world_ptr: *mut JavaWorld,
 ) -> JavaResult<ExtendedWarned<Result<String, Vec<ExtendedSourceDiagnostic>>>> {
     let world = unsafe { Box::from_raw(world_ptr) };
-    let Warned { output, warnings } = typst::compile::<HtmlDocument>(world.as_ref());
-    let html = output.and_then(|it| typst_html::html(&it)); // .map(|it| it.into_bytes());
+    let Warned { output, warnings } = run_compilation(world.timeout, || {
+        compile_reporting::<HtmlDocument>(world.as_ref(), world.warning_callback)
+    });
+    evict_after_compile();
+    let html = output.and_then(|mut it| {
+        rewrite_links(&mut it.root, &world.link_map);
+        typst_html::html(&it)
+    }); // .map(|it| it.into_bytes());
     let result = ExtendedWarned {
         output: html.map_err(|it| it.resolve(world.as_ref())),
         warnings: warnings.resolve(world.as_ref()),
@@ -24,42 +125,428 @@ pub extern "C" fn compile_html(
     JavaResult::pack(result)
 }
 
+#[derive(Debug, Serialize)]
+pub struct HtmlWithSourceMap {
+    pub html: String,
+    /// The source span of each element carrying a `data-typst-span` attribute,
+    /// indexed by the attribute's value.
+    pub spans: Vec<ExtendedSpan>,
+}
+
+/// Like [`compile_html`], but marks every element that stems from the sources
+/// with a `data-typst-span` attribute, so that positions in the HTML can be
+/// mapped back to the markup producing them.
+#[no_mangle]
+pub extern "C" fn compile_html_with_sourcemap(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<ExtendedWarned<Result<HtmlWithSourceMap, Vec<ExtendedSourceDiagnostic>>>>
+{
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let Warned { output, warnings } = run_compilation(world.timeout, || {
+        compile_reporting::<HtmlDocument>(world.as_ref(), world.warning_callback)
+    });
+    evict_after_compile();
+    let mapped = output.and_then(|mut it| {
+        rewrite_links(&mut it.root, &world.link_map);
+        let mut spans = vec![];
+        mark_spans(&mut it.root, &mut spans);
+        let html = typst_html::html(&it)?;
+        let spans = spans.into_iter().map(|span| span.resolve(world.as_ref())).collect();
+        Ok(HtmlWithSourceMap { html, spans })
+    });
+    let result = ExtendedWarned {
+        output: mapped.map_err(|it| it.resolve(world.as_ref())),
+        warnings: warnings.resolve(world.as_ref()),
+    };
+    let _ = Box::into_raw(world); // Not to drop the world!
+    JavaResult::pack(result)
+}
+
 #[no_mangle]
 pub extern "C" fn compile_svg(
     world_ptr: *mut JavaWorld,
     from: i32,
     to: i32,
 ) -> JavaResult<ExtendedWarned<Result<Vec<String>, Vec<ExtendedSourceDiagnostic>>>> {
-    compile_images(world_ptr, from, to, |page| typst_svg::svg(page))
+    compile_images(world_ptr, from, to, |page, stamp| {
+        let svg = typst_svg::svg(page);
+        match stamp {
+            Some(stamp) => stamp.stamp_svg(svg, page),
+            None => svg,
+        }
+    })
 }
 
+/// Renders the pages as PNG. Without `antialias`, pixels are either fully
+/// covered by a shape or glyph or not at all: edges are jagged instead of
+/// smooth, but crisp at integer coordinates. Images are not affected.
 #[no_mangle]
 pub extern "C" fn compile_png(
     world_ptr: *mut JavaWorld,
     from: i32,
     to: i32,
     ppi: f32,
+    antialias: i32, // 1 -- true, 0 -- false
 ) -> JavaResult<ExtendedWarned<Result<Vec<Base16ByteArray>, Vec<ExtendedSourceDiagnostic>>>>
 {
-    compile_images(world_ptr, from, to, |page| {
-        let pixmap = typst_render::render(page, ppi / 72.0);
-        let buf = pixmap.encode_png().unwrap();
-        Base16ByteArray(buf)
+    let options = RenderOptions { anti_alias: antialias == 1 };
+    compile_images(world_ptr, from, to, |page, stamp| {
+        let mut pixmap = typst_render::render_with_options(page, ppi / 72.0, options);
+        if let Some(stamp) = stamp {
+            stamp.stamp_png(&mut pixmap, ppi);
+        }
+        Base16ByteArray(encode_png(&pixmap, ppi))
     })
 }
 
+/// Renders the pages as PNG like [`compile_png`], each given as a
+/// `data:image/png;base64,..` URI that can be embedded directly, e.g. as the
+/// source of an `<img>`.
+#[no_mangle]
+pub extern "C" fn compile_png_data_uris(
+    world_ptr: *mut JavaWorld,
+    from: i32,
+    to: i32,
+    ppi: f32,
+) -> JavaResult<ExtendedWarned<Result<Vec<String>, Vec<ExtendedSourceDiagnostic>>>> {
+    compile_images(world_ptr, from, to, |page, stamp| {
+        let mut pixmap = typst_render::render(page, ppi / 72.0);
+        if let Some(stamp) = stamp {
+            stamp.stamp_png(&mut pixmap, ppi);
+        }
+        let png = encode_png(&pixmap, ppi);
+        let data = base64::engine::general_purpose::STANDARD.encode(png);
+        format!("data:image/png;base64,{data}")
+    })
+}
+
+/// Renders all pages as thumbnails arranged in a grid with `columns` columns
+/// into one PNG, e.g. as a preview of the document. Each page is scaled to fit
+/// a square cell of `thumb_px` pixels and centered in it; cells are filled row
+/// by row on a white background. `ppi` only sets the physical size of the PNG.
+/// An error if the sheet is too large to render.
+#[no_mangle]
+pub extern "C" fn contact_sheet(
+    world_ptr: *mut JavaWorld,
+    columns: i32,
+    thumb_px: i32,
+    ppi: f32,
+) -> JavaResult<ExtendedWarned<Result<Base16ByteArray, Vec<ExtendedSourceDiagnostic>>>> {
+    let thumb = thumb_px.max(1) as u32;
+    JavaResult::pack(compile_paged(world_ptr, |world, document, _| {
+        let stamp = world.watermark.as_ref().map(|it| it.layout(world)).transpose()?;
+        let pages = document.pages.len().max(1) as u32;
+        let columns = (columns.max(1) as u32).min(pages);
+        let rows = pages.div_ceil(columns);
+        let sheet = columns
+            .checked_mul(thumb)
+            .zip(rows.checked_mul(thumb))
+            .and_then(|(width, height)| Pixmap::new(width, height));
+        let Some(mut sheet) = sheet else {
+            bail!(
+                Span::detached(),
+                "contact sheet of {columns}x{rows} thumbnails is too large to render";
+                hint: "use smaller thumbnails or more columns"
+            );
+        };
+        sheet.fill(Color::WHITE);
+        for (i, page) in document.pages.iter().enumerate() {
+            let size = page.frame.size();
+            let pixel_per_pt = thumb as f32 / size.x.max(size.y).to_pt().max(1.0) as f32;
+            let mut pixmap = typst_render::render(page, pixel_per_pt);
+            if let Some(stamp) = &stamp {
+                stamp.stamp_png(&mut pixmap, pixel_per_pt * 72.0);
+            }
+            // Rounding may make the thumbnail a pixel larger than the cell.
+            let offset = |len: u32| (thumb - len.min(thumb)) / 2;
+            let x = (i as u32 % columns) * thumb + offset(pixmap.width());
+            let y = (i as u32 / columns) * thumb + offset(pixmap.height());
+            sheet.draw_pixmap(
+                x as i32,
+                y as i32,
+                pixmap.as_ref(),
+                &PixmapPaint::default(),
+                Transform::identity(),
+                None,
+            );
+        }
+        Ok(Base16ByteArray(encode_png(&sheet, ppi)))
+    }))
+}
+
+/// Renders the rectangle of the zero-based `page` at (`x`, `y`) with width `w`
+/// and height `h`, all in points from the page's top left corner, as PNG. Only
+/// the rectangle is rasterized, so it may be magnified a lot through `ppi`. The
+/// rectangle is clamped to the page, and the world's watermark is stamped onto
+/// it like in [`compile_png`]. `None` if the document has no such page; an error
+/// if the rectangle is too large to render at `ppi`.
+#[no_mangle]
+pub extern "C" fn render_region(
+    world_ptr: *mut JavaWorld,
+    page: i32,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    ppi: f32,
+) -> JavaResult<
+    ExtendedWarned<Result<Option<Base16ByteArray>, Vec<ExtendedSourceDiagnostic>>>,
+> {
+    JavaResult::pack(compile_paged(world_ptr, |world, document, _| {
+        let Some(page) = usize::try_from(page).ok().and_then(|i| document.pages.get(i))
+        else {
+            return Ok(None);
+        };
+        let bounds = page.frame.size();
+        let clamp = |x: f64, y: f64| {
+            Point::new(Abs::pt(x), Abs::pt(y))
+                .max(Point::zero())
+                .min(bounds.to_point())
+        };
+        let origin = clamp(x, y);
+        let end = clamp(x + w.max(0.0), y + h.max(0.0));
+        let size = Size::new(end.x - origin.x, end.y - origin.y);
+
+        let stamp = world.watermark.as_ref().map(|it| it.layout(world)).transpose()?;
+        let options = RenderOptions::default();
+        let pixmap = typst_render::render_region(page, ppi / 72.0, origin, size, options)
+            .and_then(|mut pixmap| {
+                if let Some(stamp) = &stamp {
+                    stamp.stamp_png_region(&mut pixmap, ppi, bounds, origin)?;
+                }
+                Some(pixmap)
+            });
+        let Some(pixmap) = pixmap else {
+            bail!(
+                Span::detached(), "region is too large to render at {ppi} ppi";
+                hint: "render a smaller region or lower the ppi"
+            );
+        };
+        Ok(Some(Base16ByteArray(encode_png(&pixmap, ppi))))
+    }))
+}
+
+/// Like [`compile_png`], but encodes the pages as WebP: lossy with the given
+/// `quality` from 0 to 100, or lossless if it is 100.
+#[no_mangle]
+pub extern "C" fn compile_webp(
+    world_ptr: *mut JavaWorld,
+    from: i32,
+    to: i32,
+    ppi: f32,
+    quality: f32,
+) -> JavaResult<ExtendedWarned<Result<Vec<Base16ByteArray>, Vec<ExtendedSourceDiagnostic>>>>
+{
+    let quality = quality.clamp(0.0, 100.0);
+    compile_images(world_ptr, from, to, |page, stamp| {
+        let mut pixmap = typst_render::render(page, ppi / 72.0);
+        if let Some(stamp) = stamp {
+            stamp.stamp_png(&mut pixmap, ppi);
+        }
+        Base16ByteArray(encode_webp(&pixmap, quality))
+    })
+}
+
+fn encode_webp(pixmap: &Pixmap, quality: f32) -> Vec<u8> {
+    let rgba = straight_rgba(pixmap);
+    let encoder = webp::Encoder::from_rgba(&rgba, pixmap.width(), pixmap.height());
+    let webp = if quality >= 100.0 {
+        encoder.encode_lossless()
+    } else {
+        encoder.encode(quality)
+    };
+    webp.to_vec()
+}
+
+/// Encodes the pixmap as PNG with a pHYs chunk, so that the image has the
+/// physical size of the page it was rendered from at `ppi`.
+fn encode_png(pixmap: &Pixmap, ppi: f32) -> Vec<u8> {
+    let mut buf = vec![];
+    let mut encoder = png::Encoder::new(&mut buf, pixmap.width(), pixmap.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    // pHYs counts pixels per meter.
+    let ppm = (ppi as f64 / 0.0254).round() as u32;
+    encoder.set_pixel_dims(Some(png::PixelDimensions {
+        xppu: ppm,
+        yppu: ppm,
+        unit: png::Unit::Meter,
+    }));
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(&straight_rgba(pixmap)).unwrap();
+    writer.finish().unwrap();
+    buf
+}
+
+/// The pixels with straight alpha, as encoders expect them. The pixmap has
+/// them premultiplied.
+fn straight_rgba(pixmap: &Pixmap) -> Vec<u8> {
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect()
+}
+
+/// Hashes the laid out pages and the document metadata. The hash only changes
+/// when the exported output would, so it can be compared to the previous one
+/// instead of the exported files themselves.
+#[no_mangle]
+pub extern "C" fn document_hash(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<ExtendedWarned<Result<String, Vec<ExtendedSourceDiagnostic>>>> {
+    JavaResult::pack(compile_paged(world_ptr, |_, document, _| {
+        Ok(format!("{:032x}", hash128(&(&document.pages, &document.info))))
+    }))
+}
+
+/// Compiles the world's main file for a target and exports it in a format.
+///
+/// - `target`: 0 -- paged, 1 -- html.
+/// - `format`: for paged documents 0 -- pdf, 1 -- svg, 2 -- png; for html only
+///   0 -- html.
+///
+/// Every page in `from..to` becomes a file of its own for svg and png, `ppi`
+/// only applies to png. The other formats produce a single file.
+///
+/// The world's library is rebuilt for the target for the duration of the call,
+/// so the `html` module is available when compiling to HTML and unknown when
+/// compiling to pages.
+#[no_mangle]
+pub extern "C" fn compile(
+    world_ptr: *mut JavaWorld,
+    target: i32,
+    format: i32,
+    from: i32,
+    to: i32,
+    ppi: f32,
+) -> JavaResult<
+    Except<ExtendedWarned<Result<Vec<Base16ByteArray>, Vec<ExtendedSourceDiagnostic>>>>,
+> {
+    tick!();
+    let html = match html_target(target, format) {
+        Ok(html) => html,
+        Err(err) => return JavaResult::pack(Err(err)),
+    };
+
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    let original = retarget(&mut world, html);
+    let result = compile_target(
+        world.as_ref(),
+        &world.link_map,
+        world.timeout,
+        world.warning_callback,
+        html,
+        format,
+        from,
+        to,
+        ppi,
+    );
+    if let Some(original) = original {
+        world.library = original;
+    }
+    let _ = Box::into_raw(world); // Not to drop the world!
+    JavaResult::pack(Ok(result))
+}
+
+/// Like [`compile`], but compiles the given source instead of a world's main
+/// file. The source cannot import other files and `sys.inputs` is empty.
+#[no_mangle]
+pub extern "C" fn compile_string(
+    source: ThickBytePtr,
+    target: i32,
+    format: i32,
+    from: i32,
+    to: i32,
+    ppi: f32,
+) -> JavaResult<
+    Except<ExtendedWarned<Result<Vec<Base16ByteArray>, Vec<ExtendedSourceDiagnostic>>>>,
+> {
+    tick!();
+    let text = source.to_str();
+    let result = html_target(target, format).map(|html| {
+        let library = Library::builder().with_features(target_features(html)).build();
+        let world = MemoryWorld::new(library, font_index::system_fonts(), text.clone());
+        compile_target(&world, &HashMap::new(), None, None, html, format, from, to, ppi)
+    });
+    mem::forget(text);
+    JavaResult::pack(result)
+}
+
+/// The result of [`compile_partial`].
+#[derive(Debug, Serialize)]
+pub struct PartialOutput<T> {
+    /// The output, unless compilation failed fatally.
+    pub output: Option<T>,
+    /// Errors of parts of the document that are missing from the output.
+    pub errors: Vec<ExtendedSourceDiagnostic>,
+    pub warnings: Vec<ExtendedSourceDiagnostic>,
+}
+
+/// Like [`compile`] for the paged target, but returns what could be laid out
+/// when elements fail, e.g. because of an error in a show rule, together with
+/// the errors. Fatal errors, like syntax errors, still produce no output.
+#[no_mangle]
+pub extern "C" fn compile_partial(
+    world_ptr: *mut JavaWorld,
+    format: i32,
+    from: i32,
+    to: i32,
+    ppi: f32,
+) -> JavaResult<Except<PartialOutput<Vec<Base16ByteArray>>>> {
+    tick!();
+    if let Err(err) = html_target(0, format) {
+        return JavaResult::pack(Err(err));
+    }
+
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    let original = retarget(&mut world, false);
+    let Warned { output: (document, mut errors), mut warnings } =
+        run_compilation(world.timeout, || {
+            compile_partial_reporting::<PagedDocument>(
+                world.as_ref(),
+                world.warning_callback,
+            )
+        });
+    evict_after_compile();
+    let reported = warnings.len();
+    let output = document.and_then(|document| {
+        export_paged(document, format, from, to, ppi, &mut warnings)
+            .map_err(|err| errors.extend(err))
+            .ok()
+    });
+    let result = PartialOutput {
+        output,
+        errors: errors.resolve(world.as_ref()),
+        warnings: warnings.resolve(world.as_ref()),
+    };
+    world.report_warnings(&result.warnings[reported..]);
+    if let Some(original) = original {
+        world.library = original;
+    }
+    let _ = Box::into_raw(world); // Not to drop the world!
+    JavaResult::pack(Ok(result))
+}
+
 fn compile_images<T: Serialize>(
     world_ptr: *mut JavaWorld,
     from: i32,
     to: i32,
-    extractor: impl Fn(&Page) -> T,
+    extractor: impl Fn(&Page, Option<&Stamp>) -> T,
 ) -> JavaResult<ExtendedWarned<Result<Vec<T>, Vec<ExtendedSourceDiagnostic>>>> {
     tick!();
     let world = unsafe { Box::from_raw(world_ptr) };
     tick!();
-    let Warned { output, warnings } = typst::compile::<PagedDocument>(world.as_ref());
+    let Warned { output, warnings } = run_compilation(world.timeout, || {
+        compile_reporting::<PagedDocument>(world.as_ref(), world.warning_callback)
+    });
+    evict_after_compile();
     tick!();
-    let pages = output.map(|document| {
+    let pages = output.and_then(|document| {
+        let stamp = world.watermark.as_ref().map(|it| it.layout(&world)).transpose()?;
         tick!();
         let mut doc_pages = document.pages;
         tick!();
@@ -67,10 +554,10 @@ fn compile_images<T: Serialize>(
         tick!();
         let end = (to as usize).min(doc_pages.len());
         tick!();
-        doc_pages
+        Ok(doc_pages
             .drain(start..end)
-            .map(|it| extractor(&it))
-            .collect::<Vec<_>>()
+            .map(|it| extractor(&it, stamp.as_ref()))
+            .collect::<Vec<_>>())
     });
     tick!();
     let result = ExtendedWarned {
@@ -83,6 +570,334 @@ fn compile_images<T: Serialize>(
     JavaResult::pack(result)
 }
 
+/// Whether the target of [`compile`] is HTML. Fails for unknown targets and
+/// formats the target does not support.
+fn html_target(target: i32, format: i32) -> Except<bool> {
+    match (target, format) {
+        (0, 0..=2) => Ok(false),
+        (1, 0) => Ok(true),
+        _ => Err(throw!(
+            "java.lang.IllegalArgumentException".to_string(),
+            Some(format!("Unexpected format {format} for target {target}"))
+        )),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn compile_target(
+    world: &dyn World,
+    links: &HashMap<String, String>,
+    timeout: Option<Duration>,
+    callback: Option<WarningCallback>,
+    html: bool,
+    format: i32,
+    from: i32,
+    to: i32,
+    ppi: f32,
+) -> ExtendedWarned<Result<Vec<Base16ByteArray>, Vec<ExtendedSourceDiagnostic>>> {
+    // The warnings of the compilation were reported while it ran.
+    let reported;
+    let Warned { output, warnings } = if html {
+        let Warned { output, warnings } = run_compilation(timeout, || {
+            compile_reporting::<HtmlDocument>(world, callback)
+        });
+        evict_after_compile();
+        reported = warnings.len();
+        let output = output
+            .and_then(|mut it| {
+                rewrite_links(&mut it.root, links);
+                typst_html::html(&it)
+            })
+            .map(|it| vec![Base16ByteArray(it.into_bytes())]);
+        Warned { output, warnings }
+    } else {
+        let Warned { output, mut warnings } = run_compilation(timeout, || {
+            compile_reporting::<PagedDocument>(world, callback)
+        });
+        evict_after_compile();
+        reported = warnings.len();
+        let output = output.and_then(|document| {
+            export_paged(document, format, from, to, ppi, &mut warnings)
+        });
+        Warned { output, warnings }
+    };
+
+    let result = ExtendedWarned {
+        output: output.map_err(|it| it.resolve(world)),
+        warnings: warnings.resolve(world),
+    };
+    if let Some(callback) = callback {
+        for warning in &result.warnings[reported..] {
+            report_warning(callback, warning);
+        }
+    }
+    result
+}
+
+/// Replaces the `href`s of links that are in `links`, see `set_link_map`.
+fn rewrite_links(element: &mut HtmlElement, links: &HashMap<String, String>) {
+    if links.is_empty() {
+        return;
+    }
+    if element.tag == tag::a {
+        for (key, value) in element.attrs.0.make_mut() {
+            if *key == attr::href {
+                if let Some(href) = links.get(value.as_str()) {
+                    *value = href.as_str().into();
+                }
+            }
+        }
+    }
+    for child in &mut element.children {
+        if let HtmlNode::Element(child) = child {
+            rewrite_links(child, links);
+        }
+    }
+}
+
+/// Numbers the elements with attached spans in document order.
+fn mark_spans(element: &mut HtmlElement, spans: &mut Vec<Span>) {
+    if !element.span.is_detached() {
+        let attr = HtmlAttr::intern("data-typst-span").unwrap();
+        element.attrs.push(attr, eco_format!("{}", spans.len()));
+        spans.push(element.span);
+    }
+    for child in &mut element.children {
+        if let HtmlNode::Element(child) = child {
+            mark_spans(child, spans);
+        }
+    }
+}
+
+/// Exports a paged document in one of the formats of [`compile`].
+fn export_paged(
+    document: PagedDocument,
+    format: i32,
+    from: i32,
+    to: i32,
+    ppi: f32,
+    warnings: &mut EcoVec<SourceDiagnostic>,
+) -> SourceResult<Vec<Base16ByteArray>> {
+    if format == 0 {
+        let options = PdfExportOptions::default();
+        return export_pdf(document, &options, &[], warnings).map(|it| vec![it]);
+    }
+    let start = (from.max(0) as usize).min(document.pages.len());
+    let end = (to.max(0) as usize).min(document.pages.len());
+    let pages = document.pages[start..end].iter();
+    Ok(match format {
+        1 => pages
+            .map(|page| Base16ByteArray(typst_svg::svg(page).into_bytes()))
+            .collect(),
+        _ => pages
+            .map(|page| {
+                let pixmap = typst_render::render(page, ppi / 72.0);
+                Base16ByteArray(encode_png(&pixmap, ppi))
+            })
+            .collect(),
+    })
+}
+
+/// Builds a copy of the world's library with the features the target needs and
+/// returns the original one, or `None` if it already fits.
+fn retarget(world: &mut JavaWorld, html: bool) -> Option<LazyHash<Library>> {
+    if world.library.features.is_enabled(Feature::Html) == html {
+        return None;
+    }
+    let library = LazyHash::new(world.library.with_features(target_features(html)));
+    Some(mem::replace(&mut world.library, library))
+}
+
+/// The features to compile with for the target. HTML is the only feature there is.
+fn target_features(html: bool) -> Features {
+    if html {
+        [Feature::Html].into_iter().collect()
+    } else {
+        Features::default()
+    }
+}
+
+/// The stack size of the threads documents are compiled on. It is only
+/// reserved; memory is committed as the stack grows.
+const COMPILE_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// A compilation waiting for a worker, with its lifetime erased, see
+/// [`on_worker`].
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The workers that wait for a compilation. They are kept around, so that a
+/// compilation doesn't pay for spawning a thread and reserving its stack.
+static IDLE_WORKERS: Mutex<Vec<Sender<Job>>> = Mutex::new(Vec::new());
+
+/// Runs a compilation on a worker thread with a large stack and aborts it once
+/// `timeout` has passed, see [`Route::check_deadline`]. An aborted compilation
+/// fails with a "compilation timed out" error with the `timeout` code, so the
+/// functions compiling documents report it like any other error.
+///
+/// Together with [`Route::check_stack`], the large stack makes deeply nested
+/// documents fail with a diagnostic instead of overflowing the caller's stack,
+/// which may be a small JVM one, and aborting the process.
+pub fn run_compilation<T: Abortable + Send>(
+    timeout: Option<Duration>,
+    f: impl FnOnce() -> T + Send,
+) -> T {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    on_worker(|| Route::with_deadline(deadline, f)).unwrap_or_else(|payload| {
+        if !payload.is::<DeadlineExceeded>() {
+            panic::resume_unwind(payload);
+        }
+        let timeout = timeout.unwrap_or_default().as_millis();
+        T::aborted(
+            SourceDiagnostic::error(
+                Span::detached(),
+                eco_format!("compilation timed out after {timeout} ms"),
+            )
+            .with_hint("try to simplify your document")
+            .with_identifier("timeout"),
+        )
+    })
+}
+
+/// The result of a compilation that [`run_compilation`] may abort.
+pub trait Abortable {
+    /// The result of a compilation that was aborted with the error.
+    fn aborted(error: SourceDiagnostic) -> Self;
+}
+
+impl<T> Abortable for Warned<SourceResult<T>> {
+    fn aborted(error: SourceDiagnostic) -> Self {
+        Warned {
+            output: Err(eco_vec![error]),
+            warnings: EcoVec::new(),
+        }
+    }
+}
+
+impl<T> Abortable for Warned<(Option<T>, EcoVec<SourceDiagnostic>)> {
+    fn aborted(error: SourceDiagnostic) -> Self {
+        Warned {
+            output: (None, eco_vec![error]),
+            warnings: EcoVec::new(),
+        }
+    }
+}
+
+/// Runs `f` on an idle worker, or on a new one if all are busy, and waits for
+/// its result, or for the payload it panicked with.
+fn on_worker<T: Send>(f: impl FnOnce() -> T + Send) -> thread::Result<T> {
+    let (sender, receiver) = mpsc::channel();
+    let job: Box<dyn FnOnce() + Send + '_> = Box::new(move || {
+        let _ = sender.send(panic::catch_unwind(AssertUnwindSafe(f)));
+    });
+    // SAFETY: The job may borrow from the caller, so it must not outlive this
+    // call. It doesn't: the job sends its result even if `f` panics, and the
+    // result is awaited below. If the job is dropped without running instead,
+    // waiting fails, but then nothing is borrowed anymore.
+    let job = unsafe { mem::transmute::<Box<dyn FnOnce() + Send + '_>, Job>(job) };
+    let worker = IDLE_WORKERS.lock().pop().unwrap_or_else(spawn_worker);
+    worker.send(job).expect("compilation workers never exit");
+    let result = receiver.recv().expect("compilation workers run every job");
+    IDLE_WORKERS.lock().push(worker);
+    result
+}
+
+/// Spawns a worker that runs the jobs sent to it, one after another.
+fn spawn_worker() -> Sender<Job> {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    thread::Builder::new()
+        .name("typst-compile".to_string())
+        .stack_size(COMPILE_STACK_SIZE)
+        .spawn(move || receiver.into_iter().for_each(|job| job()))
+        .expect("failed to spawn a compilation thread");
+    sender
+}
+
+/// Compiles like [`typst::compile`], but hands each warning to `callback` as
+/// soon as it is final, see `set_warning_callback`.
+pub fn compile_reporting<D: Document>(
+    world: &dyn World,
+    callback: Option<WarningCallback>,
+) -> Warned<SourceResult<D>> {
+    typst::compile_reporting(world, &mut reporter(world, callback))
+}
+
+/// Like [`compile_reporting`], but compiles partially, see
+/// [`typst::compile_partial`].
+pub fn compile_partial_reporting<D: Document>(
+    world: &dyn World,
+    callback: Option<WarningCallback>,
+) -> Warned<(Option<D>, EcoVec<SourceDiagnostic>)> {
+    typst::compile_partial_reporting(world, &mut reporter(world, callback))
+}
+
+/// Resolves warnings against the world and passes them to the callback, if
+/// there is one.
+fn reporter(
+    world: &dyn World,
+    callback: Option<WarningCallback>,
+) -> impl FnMut(&SourceDiagnostic) + '_ {
+    move |warning| {
+        if let Some(callback) = callback {
+            report_warning(callback, &warning.clone().resolve(world));
+        }
+    }
+}
+
+/// Compiles the world's main file into a paged document and converts it with `f`,
+/// which may fail with diagnostics of its own and add further warnings.
+pub fn compile_paged<T>(
+    world_ptr: *mut JavaWorld,
+    f: impl FnOnce(
+        &JavaWorld,
+        PagedDocument,
+        &mut EcoVec<SourceDiagnostic>,
+    ) -> SourceResult<T>,
+) -> ExtendedWarned<Result<T, Vec<ExtendedSourceDiagnostic>>> {
+    tick!();
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let Warned { output, mut warnings } = run_compilation(world.timeout, || {
+        compile_reporting::<PagedDocument>(world.as_ref(), world.warning_callback)
+    });
+    evict_after_compile();
+    let reported = warnings.len();
+    let result = ExtendedWarned {
+        output: output
+            .and_then(|document| f(world.as_ref(), document, &mut warnings))
+            .map_err(|it| it.resolve(world.as_ref())),
+        warnings: warnings.resolve(world.as_ref()),
+    };
+    world.report_warnings(&result.warnings[reported..]);
+    let _ = Box::into_raw(world); // Not to drop the world!
+    result
+}
+
+/// Like [`compile_paged`], but skips show rules that transform elements if
+/// `skip_show_rules` is set, see [`Library::skip_show_rules`]. This is faster
+/// when only the document's structure is needed, like its headings or labels,
+/// but positions may differ from the ones of a full compilation.
+pub fn compile_structure<T>(
+    world_ptr: *mut JavaWorld,
+    skip_show_rules: bool,
+    f: impl FnOnce(
+        &JavaWorld,
+        PagedDocument,
+        &mut EcoVec<SourceDiagnostic>,
+    ) -> SourceResult<T>,
+) -> ExtendedWarned<Result<T, Vec<ExtendedSourceDiagnostic>>> {
+    if !skip_show_rules {
+        return compile_paged(world_ptr, f);
+    }
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    let library = Library { skip_show_rules: true, ..(**world.library).clone() };
+    let original = mem::replace(&mut world.library, LazyHash::new(library));
+    let world_ptr = Box::into_raw(world);
+    let result = compile_paged(world_ptr, f);
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    world.library = original;
+    let _ = Box::into_raw(world); // Not to drop the world!
+    result
+}
+
 /// Convert [`chrono::DateTime`] to [`Datetime`]
 fn convert_datetime<Tz: chrono::TimeZone>(
     date_time: chrono::DateTime<Tz>,
@@ -96,3 +911,397 @@ fn convert_datetime<Tz: chrono::TimeZone>(
         date_time.second().try_into().ok()?,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::java_world::{set_compile_timeout, set_warning_callback};
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, thick, world, world_with_library};
+    use crate::watermark::set_watermark;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_compile_partial_keeps_output() {
+        let world = world("#show emph: _ => panic(\"broken\")\nBefore _this_ after");
+        let result = json(compile_partial(world, 1, 0, 1, 72.0));
+        let result = &result["Ok"];
+        assert_eq!(result["output"].as_array().unwrap().len(), 1);
+        let errors = result["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]["message"].as_str().unwrap().contains("broken"));
+        free_world(world);
+    }
+
+    #[test]
+    fn test_html_links_are_rewritten() {
+        let world = world("= Intro <intro>\n#link(<intro>)[Back]");
+        json(crate::java_world::set_link_map(
+            world,
+            thick(r##"{"#intro": "/docs/start#intro"}"##),
+        ));
+        let result = json(compile(world, 1, 0, 0, 0, 72.0));
+        let html = result["Ok"]["output"]["Ok"][0].as_str().unwrap();
+        let html = String::from_utf8(hex::decode(html).unwrap()).unwrap();
+        assert!(html.contains(r##"href="/docs/start#intro""##));
+        free_world(world);
+    }
+
+    #[test]
+    fn test_html_sourcemap_maps_a_heading() {
+        let library = Library::builder().with_features(target_features(true)).build();
+        let world = world_with_library(Box::into_raw(Box::new(library)), "= Intro\nText");
+        let result = json(compile_html_with_sourcemap(world));
+        let output = &result["output"]["Ok"];
+        let spans = output["spans"].as_array().unwrap();
+        assert!(!spans.is_empty());
+        let html = output["html"].as_str().unwrap();
+        let heading = &html[html.find("<h2").unwrap()..];
+        let attr = "data-typst-span=\"";
+        let index = &heading[heading.find(attr).unwrap() + attr.len()..];
+        let index: usize = index[..index.find('"').unwrap()].parse().unwrap();
+        assert_eq!(spans[index]["start_ind"], 0);
+        assert_eq!(spans[index]["end_ind"], "= Intro".len());
+        free_world(world);
+    }
+
+    #[test]
+    fn test_html_elements_fail_under_the_paged_target() {
+        let world = world("#html.elem(\"b\")[Hi]");
+        let result = json(compile(world, 1, 0, 0, 0, 72.0));
+        assert!(result["Ok"]["output"]["Ok"].is_array());
+        let result = json(compile(world, 0, 0, 0, 1, 72.0));
+        let errors = result["Ok"]["output"]["Err"].as_array().unwrap();
+        assert_eq!(errors[0]["message"], "unknown variable: html");
+        assert_eq!(
+            errors[0]["hints"][0],
+            "the `html` module is only available when compiling to HTML"
+        );
+        free_world(world);
+    }
+
+    #[test]
+    fn test_html_links_to_missing_labels_fail() {
+        let world = world("#link(<missing>)[Nowhere]");
+        let result = json(compile(world, 1, 0, 0, 0, 72.0));
+        let errors = result["Ok"]["output"]["Err"].as_array().unwrap();
+        assert!(errors[0]["message"].as_str().unwrap().contains("<missing>"));
+        free_world(world);
+    }
+
+    #[test]
+    fn test_max_pages_stops_layout() {
+        let world = world("#for _ in range(1000) { pagebreak() }");
+        set_max_pages(world, 10);
+        let result = json(compile(world, 0, 1, 0, 1, 72.0));
+        let errors = result["Ok"]["output"]["Err"].as_array().unwrap();
+        assert_eq!(errors[0]["message"], "page limit exceeded");
+        // The limit is part of the world, so the failed layout is not reused.
+        set_max_pages(world, 0);
+        let result = json(compile(world, 0, 1, 0, 1, 72.0));
+        assert_eq!(result["Ok"]["output"]["Ok"].as_array().unwrap().len(), 1);
+        free_world(world);
+    }
+
+    #[test]
+    fn test_max_call_depth_stops_recursion() {
+        let world = world("#let rec(n) = if n > 0 { rec(n - 1) } else { n }\n#rec(20)");
+        set_max_call_depth(world, 10);
+        let result = json(compile(world, 0, 1, 0, 1, 72.0));
+        let errors = result["Ok"]["output"]["Err"].as_array().unwrap();
+        assert_eq!(errors[0]["message"], "maximum recursion depth exceeded");
+        // The limit is part of the world, so the failed evaluation is not reused.
+        set_max_call_depth(world, 0);
+        let result = json(compile(world, 0, 1, 0, 1, 72.0));
+        assert!(result["Ok"]["output"]["Ok"].is_array());
+        free_world(world);
+    }
+
+    #[test]
+    fn test_infinite_recursion_fails_at_the_call() {
+        let text = "#let rec(n) = rec(n) + 1\n#rec(1)";
+        let world = world(text);
+        let result = json(compile(world, 0, 1, 0, 1, 72.0));
+        let errors = result["Ok"]["output"]["Err"].as_array().unwrap();
+        assert_eq!(errors[0]["message"], "maximum recursion depth exceeded");
+        let span = &errors[0]["span"];
+        // The call in the function's body, not the outer one.
+        assert_eq!(span["start_ind"], 14);
+        assert_eq!(span["end_ind"], 20);
+        assert_eq!(&text[14..20], "rec(n)");
+        free_world(world);
+    }
+
+    #[test]
+    fn test_image_limits_fail_cleanly() {
+        let world = world(
+            "#image(bytes(range(12)), format: (encoding: \"rgb8\", width: 2, height: 2))",
+        );
+        set_image_limits(world, 0, 3);
+        let result = json(compile(world, 0, 1, 0, 1, 72.0));
+        let errors = result["Ok"]["output"]["Err"].as_array().unwrap();
+        assert_eq!(
+            errors[0]["message"],
+            "image has 2x2 pixels, but at most 3 are allowed"
+        );
+        set_image_limits(world, 8, 0);
+        let result = json(compile(world, 0, 1, 0, 1, 72.0));
+        let errors = result["Ok"]["output"]["Err"].as_array().unwrap();
+        assert_eq!(
+            errors[0]["message"],
+            "image file is 12 bytes large, but at most 8 are allowed"
+        );
+        // Within the limits, the image is decoded after all.
+        set_image_limits(world, 0, 0);
+        let result = json(compile(world, 0, 1, 0, 1, 72.0));
+        assert!(result["Ok"]["output"]["Ok"].is_array());
+        free_world(world);
+    }
+
+    #[test]
+    fn test_strict_fonts_apply_to_one_world() {
+        let text = "#set text(font: \"nonexistent\")\nHi";
+        let (strict, lenient) = (world(text), world(text));
+        set_strict_fonts(strict, 1);
+        let result = json(compile(strict, 0, 1, 0, 1, 72.0));
+        let errors = result["Ok"]["output"]["Err"].as_array().unwrap();
+        assert_eq!(errors[0]["message"], "unknown font family: nonexistent");
+        // The other world still falls back to other fonts.
+        let result = json(compile(lenient, 0, 1, 0, 1, 72.0));
+        assert!(result["Ok"]["output"]["Ok"].is_array());
+        assert_eq!(
+            result["Ok"]["warnings"][0]["message"],
+            "unknown font family: nonexistent"
+        );
+        // The setting is part of the world, so the failed layout is not reused.
+        set_strict_fonts(strict, 0);
+        let result = json(compile(strict, 0, 1, 0, 1, 72.0));
+        assert!(result["Ok"]["output"]["Ok"].is_array());
+        free_world(strict);
+        free_world(lenient);
+    }
+
+    #[test]
+    fn test_overflow_warnings_apply_to_one_world() {
+        let text = "#box(width: 20pt)[Supercalifragilistic]";
+        let (warned, quiet) = (world(text), world(text));
+        set_overflow_warnings(warned, 1);
+        let result = json(compile(warned, 0, 1, 0, 1, 72.0));
+        let warnings = result["Ok"]["warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 1);
+        let message = warnings[0]["message"].as_str().unwrap();
+        assert!(message.starts_with("content overflows its container horizontally by "));
+        let result = json(compile(quiet, 0, 1, 0, 1, 72.0));
+        assert!(result["Ok"]["warnings"].as_array().unwrap().is_empty());
+        // The setting is part of the world, so the warned layout is not reused.
+        set_overflow_warnings(warned, 0);
+        let result = json(compile(warned, 0, 1, 0, 1, 72.0));
+        assert!(result["Ok"]["warnings"].as_array().unwrap().is_empty());
+        free_world(warned);
+        free_world(quiet);
+    }
+
+    #[test]
+    fn test_compilations_run_on_workers() {
+        let name = || thread::current().name().map(str::to_string);
+        let compiled = on_worker(name).ok().flatten();
+        assert_eq!(compiled.as_deref(), Some("typst-compile"));
+        // A compilation may start another one, e.g. to lay out a watermark.
+        let nested = on_worker(|| on_worker(name).ok().flatten());
+        assert_eq!(nested.ok().flatten().as_deref(), Some("typst-compile"));
+        // A panic is handed to the caller and leaves the workers usable.
+        assert!(on_worker(|| panic!("oops")).is_err());
+        assert_eq!(on_worker(|| 1 + 1).ok(), Some(2));
+    }
+
+    #[test]
+    fn test_timeout_fails_and_leaves_no_trace() {
+        let world = world("#for i in range(200000) { }\nDone");
+        set_compile_timeout(world, 1);
+        let result = json(compile(world, 0, 1, 0, 1, 72.0));
+        let errors = result["Ok"]["output"]["Err"].as_array().unwrap();
+        assert_eq!(errors[0]["message"], "compilation timed out after 1 ms");
+        assert_eq!(errors[0]["code"], "timeout");
+        assert!(errors[0]["span"]["file"].is_null());
+        // The functions that can't throw report it the same way.
+        let result = json(compile_svg(world, 0, 1));
+        assert_eq!(result["output"]["Err"][0]["code"], "timeout");
+        // Nothing of the aborted compilation is reused, so with enough time the
+        // same document compiles.
+        set_compile_timeout(world, 0);
+        let result = json(compile(world, 0, 1, 0, 1, 72.0));
+        assert_eq!(result["Ok"]["output"]["Ok"].as_array().unwrap().len(), 1);
+        free_world(world);
+    }
+
+    #[test]
+    fn test_deep_nesting_stays_on_the_worker_stack() {
+        // Far deeper than the stack of the test thread allows.
+        let depth = 2000;
+        let world = world(&format!("#{}1{}", "(".repeat(depth), ")".repeat(depth)));
+        let result = json(compile(world, 0, 1, 0, 1, 72.0));
+        assert_eq!(result["Ok"]["output"]["Ok"].as_array().unwrap().len(), 1);
+        free_world(world);
+    }
+
+    /// The width, height and RGBA pixels of a PNG handed to Java.
+    fn decode_png(png: &serde_json::Value) -> (usize, usize, Vec<u8>) {
+        let png = hex::decode(png.as_str().unwrap()).unwrap();
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        (info.width as usize, info.height as usize, pixels)
+    }
+
+    #[test]
+    fn test_contact_sheet_arranges_pages_in_a_grid() {
+        let world = world("#for _ in range(4) { pagebreak() }");
+        let result = json(contact_sheet(world, 3, 50, 72.0));
+        let (width, height, _) = decode_png(&result["output"]["Ok"]);
+        assert_eq!((width, height), (150, 100));
+        let result = json(contact_sheet(world, 3, i32::MAX, 72.0));
+        let errors = result["output"]["Err"].as_array().unwrap();
+        let message = errors[0]["message"].as_str().unwrap();
+        assert_eq!(message, "contact sheet of 3x2 thumbnails is too large to render");
+        free_world(world);
+    }
+
+    #[test]
+    fn test_png_has_the_ppi_in_its_phys_chunk() {
+        let world = world("#set page(width: 1in, height: 1in)\nA");
+        for (ppi, ppm) in [(72.0, 2835), (300.0, 11811)] {
+            let result = json(compile_png(world, 0, 1, ppi, 1));
+            let png = hex::decode(result["output"]["Ok"][0].as_str().unwrap()).unwrap();
+            let reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+            let dims = reader.info().pixel_dims.unwrap();
+            assert_eq!((dims.xppu, dims.yppu), (ppm, ppm));
+            assert_eq!(dims.unit, png::Unit::Meter);
+            // The page is an inch wide, so the image has as many pixels as the ppi.
+            assert_eq!(reader.info().width, ppi as u32);
+        }
+        free_world(world);
+    }
+
+    #[test]
+    fn test_rect_edges_without_antialias_are_crisp() {
+        // Fills of rectangles are never smoothed, but their strokes are.
+        let world = world(
+            "#set page(width: 12pt, height: 12pt, margin: 0pt)\n\
+             #place(dx: 2.3pt, dy: 2.3pt, rect(\n\
+             width: 6.4pt, height: 6.4pt, stroke: 0.5pt))",
+        );
+        let render = |antialias| {
+            let result = json(compile_png(world, 0, 1, 72.0, antialias));
+            let (_, _, pixels) = decode_png(&result["output"]["Ok"][0]);
+            pixels.chunks(4).map(|pixel| pixel[0]).collect::<Vec<_>>()
+        };
+        let (smooth, crisp) = (render(1), render(0));
+        let gray = |value: &u8| *value != 0 && *value != 255;
+        assert!(smooth.iter().any(gray));
+        assert!(!crisp.iter().any(gray));
+        assert!(crisp.contains(&0));
+        // The edge pixels are covered partially, so only those differ.
+        let differing = smooth.iter().zip(&crisp).filter(|(a, b)| a != b).count();
+        assert!(differing > 0);
+        assert!(smooth.iter().zip(&crisp).all(|(a, b)| a == b || gray(a)));
+        free_world(world);
+    }
+
+    #[test]
+    fn test_data_uris_decode_to_pngs() {
+        let world = world("#set page(width: 50pt, height: 20pt)\nA\n#pagebreak()\nB");
+        let result = json(compile_png_data_uris(world, 0, 2, 144.0));
+        let uris = result["output"]["Ok"].as_array().unwrap();
+        assert_eq!(uris.len(), 2);
+        for uri in uris {
+            let uri = uri.as_str().unwrap();
+            let data = uri.strip_prefix("data:image/png;base64,").unwrap();
+            let png = base64::engine::general_purpose::STANDARD.decode(data).unwrap();
+            // Decoded like the PNGs of `compile_png`.
+            let (width, height, _) = decode_png(&hex::encode(png).into());
+            assert_eq!((width, height), (100, 40));
+        }
+        free_world(world);
+    }
+
+    #[test]
+    fn test_render_region_matches_the_cropped_page() {
+        let world = world(
+            "#set page(width: 60pt, height: 60pt, margin: 10pt)\n\
+             #circle(radius: 20pt, fill: blue)",
+        );
+        let result = json(compile_png(world, 0, 1, 144.0, 1));
+        let (width, _, page) = decode_png(&result["output"]["Ok"][0]);
+        let result = json(render_region(world, 0, 5.0, 10.0, 20.0, 30.0, 144.0));
+        let (w, h, region) = decode_png(&result["output"]["Ok"]);
+        assert_eq!((w, h), (40, 60));
+        for (row, pixels) in region.chunks(w * 4).enumerate() {
+            let start = ((20 + row) * width + 10) * 4;
+            assert_eq!(pixels, &page[start..start + w * 4]);
+        }
+        // The part outside of the page is cut off.
+        let result = json(render_region(world, 0, 40.0, -10.0, 100.0, 30.0, 144.0));
+        let (w, h, _) = decode_png(&result["output"]["Ok"]);
+        assert_eq!((w, h), (40, 40));
+        let result = json(render_region(world, 1, 0.0, 0.0, 10.0, 10.0, 144.0));
+        assert!(result["output"]["Ok"].is_null());
+        free_world(world);
+    }
+
+    #[test]
+    fn test_render_region_fails_cleanly_when_too_large() {
+        let world = world("Hello");
+        let result = json(render_region(world, 0, 0.0, 0.0, 1000.0, 1000.0, 1e9));
+        let errors = result["output"]["Err"].as_array().unwrap();
+        let message = errors[0]["message"].as_str().unwrap();
+        assert!(message.starts_with("region is too large to render"));
+        free_world(world);
+    }
+
+    #[test]
+    fn test_render_region_is_watermarked() {
+        let world = world("#set page(width: 100pt, height: 100pt)");
+        let render = || {
+            let result = json(render_region(world, 0, 40.0, 40.0, 20.0, 20.0, 144.0));
+            decode_png(&result["output"]["Ok"]).2
+        };
+        let plain = render();
+        let watermark = r#"{"text": "DRAFT", "size": 30, "opacity": 1, "rotation": 45}"#;
+        json(set_watermark(world, thick(watermark)));
+        assert_ne!(render(), plain);
+        free_world(world);
+    }
+
+    #[test]
+    fn test_webp_has_riff_header() {
+        let world = world("Hello");
+        for (quality, chunk) in [(80.0, b"VP8 "), (100.0, b"VP8L")] {
+            let result = json(compile_webp(world, 0, 1, 72.0, quality));
+            let webp = result["output"]["Ok"][0].as_str().unwrap();
+            let webp = hex::decode(webp).unwrap();
+            assert_eq!(&webp[..4], b"RIFF");
+            assert_eq!(&webp[8..12], b"WEBP");
+            assert_eq!(&webp[12..16], chunk);
+        }
+        free_world(world);
+    }
+
+    static REPORTED: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn count_warning(_: ThickBytePtr) {
+        REPORTED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn test_every_warning_is_reported_once() {
+        let world = world(
+            "#set text(font: \"nonexistent-a\")\nA #text(font: \"nonexistent-b\")[B]",
+        );
+        set_warning_callback(world, Some(count_warning));
+        let result = json(compile(world, 0, 1, 0, 1, 72.0));
+        let warnings = result["Ok"]["warnings"].as_array().unwrap();
+        assert!(!warnings.is_empty());
+        assert_eq!(REPORTED.load(Ordering::SeqCst), warnings.len());
+        free_world(world);
+    }
+}
@@ -0,0 +1,318 @@
+use crate::compile::compile_paged;
+use crate::exception::Except;
+use crate::extended_info::{ExtendedSourceDiagnostic, ExtendedWarned};
+use crate::java_world::JavaWorld;
+use crate::memory_management::{CVec, JavaResult, ThickBytePtr};
+use crate::memory_world::MemoryWorld;
+use crate::query::retrieve;
+use crate::throw;
+use serde::{Deserialize, Serialize};
+use std::mem;
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use typst::foundations::Repr;
+use typst::layout::PagedDocument;
+use typst::text::FontBook;
+use typst::utils::{hash128, tick};
+use typst::Library;
+use typst_kit::fonts::Fonts;
+
+/// Marks a live [`CompiledDoc`]. Cleared when the handle is dropped, so that a
+/// handle that was already freed can be told apart in debug builds.
+#[cfg(debug_assertions)]
+const MAGIC: u64 = 0x7479_7073_7444_6f63;
+
+/// How many [`CompiledDoc`]s were not dropped yet, see `live_compiled_docs`.
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+/// A document compiled once and kept on the native side, so that it can be
+/// queried repeatedly without compiling it again.
+pub struct CompiledDoc {
+    #[cfg(debug_assertions)]
+    magic: u64,
+    pub document: PagedDocument,
+    /// Has the library of the world the document was compiled in, to evaluate
+    /// selectors without it.
+    pub world: MemoryWorld,
+}
+
+impl CompiledDoc {
+    fn new(document: PagedDocument, library: Library) -> Self {
+        let fonts = Fonts { book: FontBook::new(), fonts: vec![] };
+        LIVE.fetch_add(1, Ordering::Relaxed);
+        CompiledDoc {
+            #[cfg(debug_assertions)]
+            magic: MAGIC,
+            document,
+            world: MemoryWorld::new(library, Arc::new(fonts), String::new()),
+        }
+    }
+
+    /// Whether the pointer refers to a handle that was not freed yet.
+    ///
+    /// Only debug builds can tell; release builds trust every non-null pointer.
+    pub fn is_valid(ptr: *const CompiledDoc) -> bool {
+        if ptr.is_null() {
+            return false;
+        }
+        #[cfg(debug_assertions)]
+        {
+            unsafe { std::ptr::read_volatile(&(*ptr).magic) == MAGIC }
+        }
+        #[cfg(not(debug_assertions))]
+        {
+            true
+        }
+    }
+}
+
+impl Drop for CompiledDoc {
+    fn drop(&mut self) {
+        LIVE.fetch_sub(1, Ordering::Relaxed);
+        #[cfg(debug_assertions)]
+        unsafe {
+            std::ptr::write_volatile(&mut self.magic, 0)
+        };
+    }
+}
+
+/// Counts the compiled documents that were not freed yet, to find leaked
+/// handles.
+#[no_mangle]
+pub extern "C" fn live_compiled_docs() -> i64 {
+    LIVE.load(Ordering::Relaxed) as i64
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompiledDocHandle {
+    /// The address of the handle.
+    pub handle: i64,
+    pub page_count: i64,
+    /// The size of each page in points.
+    pub pages: Vec<PageDimensions>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageDimensions {
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Compiles the world's main file and keeps the document. On success, the
+/// output has the handle, which must be released with `free_compiled_doc`,
+/// and the pages' sizes.
+#[no_mangle]
+pub extern "C" fn compile_and_cache(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<ExtendedWarned<Result<CompiledDocHandle, Vec<ExtendedSourceDiagnostic>>>>
+{
+    JavaResult::pack(compile_paged(world_ptr, |world, document, _| {
+        let pages = document
+            .pages
+            .iter()
+            .map(|page| {
+                let size = page.frame.size();
+                PageDimensions { width: size.x.to_pt(), height: size.y.to_pt() }
+            })
+            .collect::<Vec<_>>();
+        Ok(CompiledDocHandle {
+            page_count: pages.len() as i64,
+            pages,
+            handle: Box::into_raw(Box::new(CompiledDoc::new(
+                document,
+                (*world.library).clone(),
+            ))) as i64,
+        })
+    }))
+}
+
+/// Returns 1 if the handle is still valid, 0 otherwise.
+#[no_mangle]
+pub extern "C" fn compiled_doc_is_valid(doc_ptr: *const CompiledDoc) -> i32 {
+    CompiledDoc::is_valid(doc_ptr) as i32
+}
+
+/// Returns the number of pages of the document, or -1 if the handle is invalid.
+#[no_mangle]
+pub extern "C" fn compiled_doc_page_count(doc_ptr: *const CompiledDoc) -> i32 {
+    tick!();
+    if !CompiledDoc::is_valid(doc_ptr) {
+        return -1;
+    }
+    let doc = unsafe { &*doc_ptr };
+    doc.document.pages.len() as i32
+}
+
+/// Returns the zero-based indices of the pages that differ between the two
+/// documents, including the pages only one of them has. Pages are compared by
+/// their content hash. The array must be released with `free_i64_array`; its
+/// length is -1 if either handle is invalid.
+#[no_mangle]
+pub extern "C" fn changed_pages(
+    old_doc_ptr: *const CompiledDoc,
+    new_doc_ptr: *const CompiledDoc,
+) -> CVec<i64> {
+    tick!();
+    if !CompiledDoc::is_valid(old_doc_ptr) || !CompiledDoc::is_valid(new_doc_ptr) {
+        return CVec { ptr: null_mut(), len: -1, cap: 0 };
+    }
+    let (old, new) =
+        unsafe { (&(*old_doc_ptr).document.pages, &(*new_doc_ptr).document.pages) };
+    let changed = (0..old.len().max(new.len()))
+        .filter(|&index| match (old.get(index), new.get(index)) {
+            (Some(old), Some(new)) => hash128(old) != hash128(new),
+            _ => true,
+        })
+        .map(|index| index as i64)
+        .collect::<Vec<_>>();
+    changed.into()
+}
+
+/// Returns the reprs of up to `limit` elements matching the selector, without
+/// compiling again. Meant for previews while the selector is being typed.
+/// Non-positive limits return all matches.
+#[no_mangle]
+pub extern "C" fn query_preview(
+    doc_ptr: *const CompiledDoc,
+    selector: ThickBytePtr,
+    limit: i32,
+) -> JavaResult<Except<Vec<String>>> {
+    tick!();
+    let selector = selector.to_str();
+    let result = if CompiledDoc::is_valid(doc_ptr) {
+        let doc = unsafe { &*doc_ptr };
+        retrieve(&doc.world, &selector, &doc.document)
+            .map(|matches| {
+                let limit = if limit > 0 { limit as usize } else { matches.len() };
+                matches.iter().take(limit).map(|it| it.repr().to_string()).collect()
+            })
+            .map_err(|err| {
+                throw!(
+                    "java.lang.IllegalArgumentException".to_string(),
+                    Some(err.message().to_string())
+                )
+            })
+    } else {
+        Err(throw!(
+            "java.lang.IllegalStateException".to_string(),
+            Some("Invalid compiled document handle".to_string())
+        ))
+    };
+    mem::forget(selector);
+    JavaResult::pack(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::java_world::reset_world;
+    use crate::memory_management::{free_compiled_doc, free_world};
+    use crate::test_utils::{add_file, json, thick, world, COMPILED_DOCS};
+
+    /// Compiles the world and keeps the document.
+    fn cache(world: *mut JavaWorld) -> *mut CompiledDoc {
+        let result = json(compile_and_cache(world));
+        result["output"]["Ok"]["handle"].as_i64().unwrap() as *mut CompiledDoc
+    }
+
+    #[test]
+    fn test_compiled_doc_handle_lifecycle() {
+        let _docs = COMPILED_DOCS.lock();
+        let world = world("= Intro\n#pagebreak()\n= Outro");
+        let result = json(compile_and_cache(world));
+        let output = &result["output"]["Ok"];
+        assert_eq!(output["page_count"], 2);
+        let doc = output["handle"].as_i64().unwrap() as *mut CompiledDoc;
+        assert_eq!(compiled_doc_is_valid(doc), 1);
+        assert_eq!(compiled_doc_page_count(doc), 2);
+        let headings = json(query_preview(doc, thick("heading"), 0));
+        assert_eq!(headings["Ok"].as_array().unwrap().len(), 2);
+        assert_eq!(json(free_compiled_doc(doc))["Ok"], serde_json::Value::Null);
+        // Debug builds notice that the handle is gone.
+        if cfg!(debug_assertions) {
+            assert_eq!(compiled_doc_is_valid(doc), 0);
+            let freed = json(free_compiled_doc(doc));
+            assert_eq!(freed["Err"]["class"], "java.lang.IllegalStateException");
+        }
+        free_world(world);
+    }
+
+    #[test]
+    fn test_changed_pages_finds_the_edited_page() {
+        let _docs = COMPILED_DOCS.lock();
+        let world = world("A\n#pagebreak()\nB\n#pagebreak()\nC");
+        let old = cache(world);
+        add_file(world, "/main.typ", "A\n#pagebreak()\nBee\n#pagebreak()\nC");
+        reset_world(world);
+        let new = cache(world);
+        let changed = Vec::from(changed_pages(old, new));
+        assert_eq!(changed, vec![1]);
+        assert!(Vec::from(changed_pages(new, new)).is_empty());
+        json(free_compiled_doc(old));
+        json(free_compiled_doc(new));
+        free_world(world);
+    }
+
+    #[test]
+    fn test_page_count_matches_the_rendered_pages() {
+        let _docs = COMPILED_DOCS.lock();
+        let world = world(
+            "#set page(width: 100pt, height: 50pt)\nA\n#pagebreak()\nB\n\
+             #set page(width: 200pt)\nC",
+        );
+        let result = json(compile_and_cache(world));
+        let output = &result["output"]["Ok"];
+        let pages = output["pages"].as_array().unwrap();
+        assert_eq!(output["page_count"], 3);
+        assert_eq!(pages.len(), 3);
+        let mut rendered = 0;
+        loop {
+            let result =
+                json(crate::compile::compile_png(world, rendered, rendered + 1, 72.0, 0));
+            let Some(png) = result["output"]["Ok"].as_array().unwrap().first() else {
+                break;
+            };
+            let png = hex::decode(png.as_str().unwrap()).unwrap();
+            let reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+            let (width, height) = reader.info().size();
+            let page = &pages[rendered as usize];
+            assert_eq!(width as f64, page["width"].as_f64().unwrap());
+            assert_eq!(height as f64, page["height"].as_f64().unwrap());
+            rendered += 1;
+        }
+        assert_eq!(output["page_count"], rendered);
+        let doc = output["handle"].as_i64().unwrap() as *mut CompiledDoc;
+        json(free_compiled_doc(doc));
+        free_world(world);
+    }
+
+    #[test]
+    fn test_preview_queries_share_one_document() {
+        let _docs = COMPILED_DOCS.lock();
+        let world = world("= Intro <intro>\n== Details\n== More\n#figure[X]");
+        let doc = cache(world);
+        // Previews don't compile again, so changing the world doesn't matter.
+        add_file(world, "/main.typ", "Nothing");
+        reset_world(world);
+        let preview =
+            |selector: &str, limit| json(query_preview(doc, thick(selector), limit));
+        assert_eq!(preview("heading", 0)["Ok"].as_array().unwrap().len(), 3);
+        let limited = preview("heading", 2);
+        let limited = limited["Ok"].as_array().unwrap();
+        assert_eq!(limited.len(), 2);
+        assert!(limited[0].as_str().unwrap().contains("Intro"));
+        let nested = preview("heading.where(level: 2)", 0);
+        let nested = nested["Ok"].as_array().unwrap();
+        assert_eq!(nested.len(), 2);
+        assert!(nested[1].as_str().unwrap().contains("More"));
+        assert_eq!(preview("<intro>", 0)["Ok"].as_array().unwrap().len(), 1);
+        assert_eq!(preview("figure", 0)["Ok"].as_array().unwrap().len(), 1);
+        // A selector that is still being typed fails without harming the handle.
+        let typing = preview("heading.where(", 0);
+        assert_eq!(typing["Err"]["class"], "java.lang.IllegalArgumentException");
+        assert_eq!(preview("heading", 1)["Ok"].as_array().unwrap().len(), 1);
+        json(free_compiled_doc(doc));
+        free_world(world);
+    }
+}
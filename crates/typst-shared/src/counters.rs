@@ -0,0 +1,95 @@
+use crate::compile::compile_paged;
+use crate::extended_info::{ExtendedSourceDiagnostic, ExtendedWarned};
+use crate::java_world::JavaWorld;
+use crate::memory_management::JavaResult;
+use serde::{Deserialize, Serialize};
+use typst::comemo::Track;
+use typst::engine::{Engine, Route, Sink, Traced};
+use typst::foundations::{NativeElement, Repr};
+use typst::introspection::{Counter, CounterKey};
+use typst::math::EquationElem;
+use typst::model::{FigureElem, FootnoteElem, HeadingElem};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CounterInfo {
+    /// The key as it is written in `counter(..)`, e.g. `page`, `heading` or
+    /// `figure.where(kind: image)`.
+    pub key: String,
+    /// The numbers of the counter at the end of the document.
+    pub value: Vec<i64>,
+}
+
+/// Lists the counters of the document with their final values: the page,
+/// heading, figure, equation and footnote counters, the counters of the kinds
+/// of figures present, and the counters updated with `step` or `update`.
+#[no_mangle]
+pub extern "C" fn list_counters(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<ExtendedWarned<Result<Vec<CounterInfo>, Vec<ExtendedSourceDiagnostic>>>> {
+    JavaResult::pack(compile_paged(world_ptr, |world, document, _| {
+        let introspector = &document.introspector;
+        let mut counters = vec![
+            Counter::new(CounterKey::Page),
+            Counter::of(HeadingElem::elem()),
+            Counter::of(FigureElem::elem()),
+            Counter::of(EquationElem::elem()),
+            Counter::of(FootnoteElem::elem()),
+        ];
+        let figures = introspector.query(&FigureElem::elem().select());
+        let kinds = figures.iter().filter_map(|elem| {
+            elem.to_packed::<FigureElem>()?.counter().cloned().flatten()
+        });
+        for counter in kinds.chain(Counter::updated(introspector)) {
+            if !counters.contains(&counter) {
+                counters.push(counter);
+            }
+        }
+
+        let traced = Traced::default();
+        let mut sink = Sink::new();
+        let mut engine = Engine {
+            routines: &typst::ROUTINES,
+            world: world.track(),
+            introspector: introspector.track(),
+            traced: traced.track(),
+            sink: sink.track_mut(),
+            route: Route::default(),
+        };
+        counters
+            .into_iter()
+            .map(|counter| {
+                let state = counter.final_state(&mut engine)?;
+                Ok(CounterInfo {
+                    key: counter.key().repr().to_string(),
+                    value: state.0.iter().map(|&it| it as i64).collect(),
+                })
+            })
+            .collect()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, world};
+
+    #[test]
+    fn test_figure_counter_counts_the_figures() {
+        let world = world(
+            "#figure(rect(), caption: [A])\n#figure(rect(), caption: [B])\n\
+             #figure(table[x], caption: [C])\n#counter(\"custom\").step()",
+        );
+        let result = json(list_counters(world));
+        let counters = result["output"]["Ok"].as_array().unwrap();
+        let value = |key: &str| {
+            let counter = counters.iter().find(|it| it["key"] == key);
+            counter.unwrap_or_else(|| panic!("no counter {key}"))["value"].clone()
+        };
+        assert_eq!(value("figure"), serde_json::json!([3]));
+        assert_eq!(value("figure.where(kind: table)"), serde_json::json!([1]));
+        assert_eq!(value("\"custom\""), serde_json::json!([1]));
+        assert_eq!(value("page"), serde_json::json!([1]));
+        free_world(world);
+    }
+}
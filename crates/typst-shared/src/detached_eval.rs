@@ -1,13 +1,17 @@
 use std::mem;
-use crate::extended_info::{ExtendedSourceDiagnostic, Resolve};
+use crate::exception::Except;
+use crate::extended_info::{ExtendedFileDescriptor, ExtendedSourceDiagnostic, Resolve};
 use crate::java_world::JavaWorld;
-use crate::memory_management::{JavaResult, ThickBytePtr};
+use crate::memory_management::{self, JavaResult, ThickBytePtr};
+use crate::stdlib::capture_prints;
+use crate::throw;
+use serde::{Deserialize, Serialize};
 use typst::comemo::Track;
 use typst::diag::SourceDiagnostic;
 use typst::ecow::EcoVec;
-use typst::foundations::{Scope, Value};
+use typst::foundations::{Repr, Scope, Value};
 use typst::routines::EvalMode;
-use typst::syntax::Span;
+use typst::syntax::{FileId, Span, VirtualPath};
 use typst::utils::tick;
 use typst::World;
 use typst_eval::eval_string;
@@ -27,7 +31,7 @@ pub extern "C" fn detached_eval(
     tick!();
     let result = eval(world.as_ref(), source.as_str())
         .map_err(|it| it.resolve(world.as_ref()))
-        .map(|it| serde_json::to_string(&it).unwrap());
+        .map(|it| to_json(&it, world.sort_keys));
 
     tick!();
     let _ = Box::into_raw(world); // Not to drop the world!
@@ -37,6 +41,121 @@ pub extern "C" fn detached_eval(
     JavaResult::pack(result)
 }
 
+/// Like [`detached_eval`], but also returns everything `print` emitted meanwhile.
+#[no_mangle]
+pub extern "C" fn detached_eval_captured(
+    world_ptr: *mut JavaWorld,
+    source_ptr: ThickBytePtr,
+) -> JavaResult<Captured<Result<String, Vec<ExtendedSourceDiagnostic>>>> {
+    tick!();
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+
+    world.reset();
+    let source = source_ptr.to_str();
+    let (result, printed) = capture_prints(|| eval(world.as_ref(), source.as_str()));
+    let result = result
+        .map_err(|it| it.resolve(world.as_ref()))
+        .map(|it| to_json(&it, world.sort_keys));
+
+    let _ = Box::into_raw(world); // Not to drop the world!
+
+    mem::forget(source);
+    JavaResult::pack(Captured { output: result, printed })
+}
+
+/// A file that is served from memory during [`eval_with_virtual_files`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VirtualFile {
+    pub file: ExtendedFileDescriptor,
+    pub content: String,
+}
+
+/// The file the code passed to [`eval_with_virtual_files`] is attributed to, so
+/// that relative imports are resolved from the root.
+const EVAL_ANCHOR: &str = "/.eval.typ";
+
+/// Like [`detached_eval`], but `import` and `include` resolve to the given files
+/// (a JSON list of [`VirtualFile`]) before falling back to the world's callback.
+/// The files are only visible during this evaluation.
+#[no_mangle]
+pub extern "C" fn eval_with_virtual_files(
+    world_ptr: *mut JavaWorld,
+    files_json: ThickBytePtr,
+    source_ptr: ThickBytePtr,
+) -> JavaResult<Except<Result<String, Vec<ExtendedSourceDiagnostic>>>> {
+    tick!();
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    let files_str = files_json.to_str();
+    let source = source_ptr.to_str();
+
+    let result = serde_json::from_str::<Vec<VirtualFile>>(&files_str)
+        .map_err(|err| {
+            throw!(
+                "java.lang.IllegalArgumentException".to_string(),
+                Some(format!("Malformed virtual files: {err}"))
+            )
+        })
+        .map(|files| {
+            let anchor = FileId::new(None, VirtualPath::new(EVAL_ANCHOR));
+            {
+                let mut virtual_files = world.virtual_files.lock();
+                for VirtualFile { file, content } in files {
+                    virtual_files.insert(file.into(), content.into_bytes());
+                }
+                virtual_files.insert(anchor, source.clone().into_bytes());
+            }
+            world.reset();
+
+            let span = Span::from_range(anchor, 0..source.len());
+            let result = eval_at(world.as_ref(), source.as_str(), span)
+                .map_err(|it| it.resolve(world.as_ref()))
+                .map(|it| to_json(&it, world.sort_keys));
+
+            world.virtual_files.lock().clear();
+            world.reset();
+            result
+        });
+
+    let _ = Box::into_raw(world); // Not to drop the world!
+
+    mem::forget(files_str);
+    mem::forget(source);
+    JavaResult::pack(result)
+}
+
+/// Checks that the JSON can be passed into Typst by deserializing it into a
+/// value the way `sys.inputs` are. Returns the value's repr, or throws an
+/// `IllegalArgumentException` telling where the JSON is malformed.
+#[no_mangle]
+pub extern "C" fn validate_value(value_json: ThickBytePtr) -> JavaResult<Except<String>> {
+    tick!();
+    let value_str = value_json.to_str();
+    let result = serde_json::from_str::<Value>(&value_str)
+        .map(|value| value.repr().to_string())
+        .map_err(|err| {
+            throw!(
+                "java.lang.IllegalArgumentException".to_string(),
+                Some(format!("Malformed value: {err}"))
+            )
+        });
+    mem::forget(value_str);
+    JavaResult::pack(result)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Captured<T> {
+    pub output: T,
+    pub printed: Vec<String>,
+}
+
+fn to_json(value: &Value, sort_keys: bool) -> String {
+    if sort_keys {
+        memory_management::to_json(&crate::sort_keys::sort_keys(value)).unwrap()
+    } else {
+        memory_management::to_json(value).unwrap()
+    }
+}
+
 impl Resolve<Value> for Value {
     fn resolve(self, _world: &dyn World) -> Value {
         self
@@ -44,12 +163,70 @@ impl Resolve<Value> for Value {
 }
 
 fn eval(world: &dyn World, source: &str) -> Result<Value, EcoVec<SourceDiagnostic>> {
+    eval_at(world, source, Span::detached())
+}
+
+/// Evaluates the code as if it were located at the span.
+fn eval_at(
+    world: &dyn World,
+    source: &str,
+    span: Span,
+) -> Result<Value, EcoVec<SourceDiagnostic>> {
     eval_string(
         &typst::ROUTINES,
         world.track(),
         source,
-        Span::detached(),
+        span,
         EvalMode::Code,
         Scope::default(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::java_world::set_sort_keys;
+    use crate::memory_management::free_world;
+    use crate::stdlib::create_stdlib;
+    use crate::test_utils::{json, thick, world, world_with_library};
+
+    #[test]
+    fn test_detached_eval_captures_prints() {
+        let world = world_with_library(create_stdlib(0, thick("(:)")), "");
+        let result = json(detached_eval_captured(world, thick(r#"print("hi"); 1 + 2"#)));
+        assert_eq!(result["output"]["Ok"], "3");
+        let printed = result["printed"].as_array().unwrap();
+        assert_eq!(printed.len(), 1);
+        assert!(printed[0].as_str().unwrap().contains("hi"));
+        free_world(world);
+    }
+
+    #[test]
+    fn test_sort_keys_ignores_construction_order() {
+        let world = world("");
+        // Whitespace is dropped, as another test may make the JSON pretty.
+        let eval = |code| {
+            let result = json(detached_eval(world, thick(code)));
+            result["Ok"].as_str().unwrap().split_whitespace().collect::<String>()
+        };
+        let (first, second) = ("(b: 1, a: (d: 2, c: 3))", "(a: (c: 3, d: 2), b: 1)");
+        assert_ne!(eval(first), eval(second));
+        set_sort_keys(world, 1);
+        assert_eq!(eval(first), r#"{"a":{"c":3,"d":2},"b":1}"#);
+        assert_eq!(eval(second), eval(first));
+        free_world(world);
+    }
+
+    #[test]
+    fn test_validate_valid_dict() {
+        let result = json(validate_value(thick(r#"{"a": 1, "b": [true, "x"]}"#)));
+        assert_eq!(result["Ok"], r#"(a: 1, b: (true, "x"))"#);
+    }
+
+    #[test]
+    fn test_validate_malformed_value() {
+        let result = json(validate_value(thick(r#"{"a": }"#)));
+        assert_eq!(result["Err"]["class"], "java.lang.IllegalArgumentException");
+        assert!(result["Err"]["message"].as_str().unwrap().starts_with("Malformed value"));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::exception::Except;
+use crate::extended_info::{ExtendedSourceDiagnostic, ExtendedSpan};
+use crate::java_world::JavaWorld;
+use crate::memory_management::{JavaResult, ThickBytePtr};
+use crate::throw;
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use codespan_reporting::files::{Error as CodespanError, Files};
+use codespan_reporting::term;
+use codespan_reporting::term::termcolor::NoColor;
+use std::mem;
+use std::ops::Range;
+use typst::diag::{Severity, Tracepoint};
+use typst::syntax::{FileId, Source};
+use typst::utils::tick;
+use typst::World;
+
+/// Renders resolved diagnostics the way `typst compile` prints them: with the
+/// offending source lines, carets under the spans, hints and the trace.
+#[no_mangle]
+pub extern "C" fn format_diagnostics(
+    world_ptr: *mut JavaWorld,
+    diags_json: ThickBytePtr,
+) -> JavaResult<Except<String>> {
+    tick!();
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let diags_str = diags_json.to_str();
+
+    let result = serde_json::from_str::<Vec<ExtendedSourceDiagnostic>>(&diags_str)
+        .map_err(|err| {
+            throw!(
+                "java.lang.IllegalArgumentException".to_string(),
+                Some(err.to_string())
+            )
+        })
+        .and_then(|diags| {
+            render(&WorldFiles(world.as_ref()), &diags).map_err(|err| {
+                throw!("java.lang.RuntimeException".to_string(), Some(err.to_string()))
+            })
+        });
+
+    let _ = Box::into_raw(world); // Not to drop the world!
+    mem::forget(diags_str);
+    JavaResult::pack(result)
+}
+
+fn render(
+    files: &WorldFiles,
+    diags: &[ExtendedSourceDiagnostic],
+) -> Result<String, CodespanError> {
+    let config = term::Config { tab_width: 2, ..Default::default() };
+    let mut out = NoColor::new(vec![]);
+
+    for diagnostic in diags {
+        let diag = match diagnostic.severity {
+            Severity::Error => Diagnostic::error(),
+            Severity::Warning => Diagnostic::warning(),
+        }
+        .with_message(diagnostic.message.clone())
+        .with_notes(diagnostic.hints.iter().map(|hint| format!("hint: {hint}")).collect())
+        .with_labels(label(&diagnostic.span).into_iter().collect());
+
+        term::emit(&mut out, &config, files, &diag)?;
+
+        // Stacktrace-like helper diagnostics.
+        for point in &diagnostic.trace {
+            let help = Diagnostic::help()
+                .with_message(Tracepoint::from(point.v.clone()).to_string())
+                .with_labels(label(&point.span).into_iter().collect());
+
+            term::emit(&mut out, &config, files, &help)?;
+        }
+    }
+
+    Ok(String::from_utf8_lossy(&out.into_inner()).into_owned())
+}
+
+/// Create a label for a span, unless it is detached.
+fn label(span: &ExtendedSpan) -> Option<Label<FileId>> {
+    let file = span.file.clone()?;
+    Some(Label::primary(
+        FileId::from(file),
+        span.start_ind as usize..span.end_ind as usize,
+    ))
+}
+
+/// Looks up the sources the diagnostics refer to in the world.
+struct WorldFiles<'a>(&'a JavaWorld);
+
+impl WorldFiles<'_> {
+    fn lookup(&self, id: FileId) -> Result<Source, CodespanError> {
+        self.0.source(id).map_err(|_| CodespanError::FileMissing)
+    }
+}
+
+impl<'a> Files<'a> for WorldFiles<'_> {
+    type FileId = FileId;
+    type Name = String;
+    type Source = Source;
+
+    fn name(&'a self, id: FileId) -> Result<Self::Name, CodespanError> {
+        let vpath = id.vpath();
+        Ok(if let Some(package) = id.package() {
+            format!("{package}{}", vpath.as_rooted_path().display())
+        } else {
+            vpath.as_rootless_path().to_string_lossy().into()
+        })
+    }
+
+    fn source(&'a self, id: FileId) -> Result<Self::Source, CodespanError> {
+        self.lookup(id)
+    }
+
+    fn line_index(&'a self, id: FileId, given: usize) -> Result<usize, CodespanError> {
+        let source = self.lookup(id)?;
+        source
+            .byte_to_line(given)
+            .ok_or_else(|| CodespanError::IndexTooLarge {
+                given,
+                max: source.len_bytes(),
+            })
+    }
+
+    fn line_range(
+        &'a self,
+        id: FileId,
+        given: usize,
+    ) -> Result<Range<usize>, CodespanError> {
+        let source = self.lookup(id)?;
+        source
+            .line_to_range(given)
+            .ok_or_else(|| CodespanError::LineTooLarge { given, max: source.len_lines() })
+    }
+
+    fn column_number(
+        &'a self,
+        id: FileId,
+        _: usize,
+        given: usize,
+    ) -> Result<usize, CodespanError> {
+        let source = self.lookup(id)?;
+        source.byte_to_column(given).ok_or_else(|| {
+            let max = source.len_bytes();
+            if given <= max {
+                CodespanError::InvalidCharBoundary { given }
+            } else {
+                CodespanError::IndexTooLarge { given, max }
+            }
+        })
+    }
+}
@@ -0,0 +1,93 @@
+use crate::compile::compile_paged;
+use crate::extended_info::{ExtendedSourceDiagnostic, ExtendedWarned};
+use crate::frames::walk_frame;
+use crate::java_world::JavaWorld;
+use crate::memory_management::JavaResult;
+use serde::{Deserialize, Serialize};
+use typst::foundations::Repr;
+use typst::layout::{Dir, FrameItem, PagedDocument, Transform};
+use typst::text::{Lang, TextItem};
+use typst::utils::tick;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocumentInfo {
+    pub title: Option<String>,
+    pub author: Vec<String>,
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+    /// The ISO 639 code of the text language, e.g. `en`.
+    pub lang: String,
+    /// The ISO 3166 code of the text region, e.g. `US`, if set.
+    pub region: Option<String>,
+    /// `ltr` or `rtl`. Follows the language unless the direction is set.
+    pub dir: String,
+}
+
+/// Returns the document's metadata and the text language and direction in
+/// effect at the start of its first page, e.g. as set with `set text(..)` at the
+/// top level of the main file.
+#[no_mangle]
+pub extern "C" fn document_info(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<ExtendedWarned<Result<DocumentInfo, Vec<ExtendedSourceDiagnostic>>>> {
+    tick!();
+    JavaResult::pack(compile_paged(world_ptr, |_, document, _| Ok(info(&document))))
+}
+
+fn info(document: &PagedDocument) -> DocumentInfo {
+    let (lang, region, dir) = first_text(document)
+        .map_or((Lang::ENGLISH, None, Dir::LTR), |it| (it.lang, it.region, it.dir));
+    let info = &document.info;
+    DocumentInfo {
+        title: info.title.as_ref().map(|it| it.to_string()),
+        author: info.author.iter().map(|it| it.to_string()).collect(),
+        description: info.description.as_ref().map(|it| it.to_string()),
+        keywords: info.keywords.iter().map(|it| it.to_string()).collect(),
+        lang: lang.as_str().to_string(),
+        region: region.map(|it| it.as_str().to_string()),
+        dir: dir.repr().to_string(),
+    }
+}
+
+/// The first text on the first page, in layout order.
+fn first_text(document: &PagedDocument) -> Option<TextItem> {
+    let page = document.pages.first()?;
+    let mut first = None;
+    walk_frame(&page.frame, Transform::identity(), &mut |_, _, item| {
+        if let (None, FrameItem::Text(text)) = (&first, item) {
+            first = Some(text.clone());
+        }
+    });
+    first
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, world};
+
+    #[test]
+    fn test_document_info_reads_the_text_direction() {
+        let world = world(
+            "#set document(title: [Notes])\n#set text(lang: \"en\", dir: rtl)\nHello",
+        );
+        let result = json(document_info(world));
+        let info = &result["output"]["Ok"];
+        assert_eq!(info["title"], "Notes");
+        assert_eq!(info["lang"], "en");
+        assert_eq!(info["dir"], "rtl");
+        free_world(world);
+    }
+
+    #[test]
+    fn test_document_info_follows_the_language() {
+        let world = world("#set text(lang: \"ar\", region: \"EG\")\nمرحبا");
+        let result = json(document_info(world));
+        let info = &result["output"]["Ok"];
+        assert_eq!(info["lang"], "ar");
+        assert_eq!(info["region"], "EG");
+        assert_eq!(info["dir"], "rtl");
+        free_world(world);
+    }
+}
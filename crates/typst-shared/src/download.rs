@@ -1,13 +1,20 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::io;
 use std::io::Write;
 use std::path::PathBuf;
+use std::thread;
 use std::time::{Duration, Instant};
 
 use codespan_reporting::term;
 use codespan_reporting::term::termcolor::WriteColor;
+use serde::Deserialize;
+use typst::diag::{PackageError, PackageResult};
+use typst::engine::Route;
+use typst::syntax::package::PackageSpec;
 use typst::utils::format_duration;
 use typst_kit::download::{DownloadState, Downloader, Progress};
+use typst_kit::package::PackageStorage;
 
 use crate::terminal::{self, TermOut};
 
@@ -41,14 +48,129 @@ impl<T: Display> Progress for PrintDownload<T> {
     }
 }
 
+/// Network settings for package downloads, passed from java on world creation.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PackageConfig {
+    /// HTTP/HTTPS proxy URL. If absent, the environment's proxy settings are used.
+    pub proxy: Option<String>,
+    /// Connect and read timeout in milliseconds. If absent, there's no timeout.
+    pub timeout_millis: Option<u64>,
+    /// Path to a PEM file with an additional CA certificate. System roots are
+    /// always trusted.
+    pub cert_path: Option<String>,
+    /// Disables certificate verification. Only meant for testing.
+    pub insecure: bool,
+    /// How many times a failed download is retried. Only timeouts and server
+    /// errors are retried, a missing package is reported right away.
+    pub retries: u32,
+    /// Delay before the first retry in milliseconds, doubled after each attempt.
+    pub backoff_millis: u64,
+    /// Where the packages of each namespace come from. Namespaces not listed
+    /// here use [`PackageConfig::source`]'s defaults.
+    pub namespaces: HashMap<String, PackageSource>,
+}
+
+/// Where the packages of a namespace come from.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PackageSource {
+    /// Downloaded into the package storage if not there yet.
+    Download,
+    /// Read from `{path}/{name}/{version}`, never downloaded.
+    Local { path: String },
+    /// Requested from the java callback, like the files of the project.
+    Callback,
+}
+
+impl PackageConfig {
+    /// Where the packages of the namespace come from. Unless configured
+    /// otherwise, `preview` packages are downloaded and the other ones are
+    /// requested from the java callback.
+    pub fn source(&self, namespace: &str) -> PackageSource {
+        match self.namespaces.get(namespace) {
+            Some(source) => source.clone(),
+            None if namespace == "preview" => PackageSource::Download,
+            None => PackageSource::Callback,
+        }
+    }
+}
+
 /// Returns a new downloader.
-pub fn downloader() -> Downloader {
+pub fn downloader(config: &PackageConfig) -> Downloader {
     let user_agent = concat!("typst/", env!("CARGO_PKG_VERSION"));
-    let cert : Option<PathBuf> = None; // TODO
-    match cert {
+    let cert = config.cert_path.as_ref().map(PathBuf::from);
+    let mut downloader = match cert {
         Some(cert) => Downloader::with_path(user_agent, cert),
         None => Downloader::new(user_agent),
+    };
+    if let Some(proxy) = &config.proxy {
+        downloader = downloader.with_proxy(proxy);
+    }
+    if let Some(timeout) = config.timeout_millis {
+        downloader = downloader.with_timeout(Duration::from_millis(timeout));
+    }
+    if config.insecure {
+        downloader = downloader.danger_accept_invalid_certs(true);
+    }
+    downloader
+}
+
+/// Makes a package available on disk, downloading it if necessary.
+pub fn prepare_package(
+    storage: &PackageStorage,
+    spec: &PackageSpec,
+    config: &PackageConfig,
+) -> PackageResult<PathBuf> {
+    with_retries(config, || storage.prepare_package(spec, &mut PrintDownload(spec)))
+}
+
+/// Runs `attempt` until it succeeds, fails with an error that is not
+/// [transient](is_transient), or runs out of retries. The last error is
+/// returned in the latter case.
+pub fn with_retries<T>(
+    config: &PackageConfig,
+    mut attempt: impl FnMut() -> PackageResult<T>,
+) -> PackageResult<T> {
+    let mut delay = Duration::from_millis(config.backoff_millis);
+    let mut retries = 0;
+    loop {
+        match attempt() {
+            Err(err) if retries < config.retries && is_transient(&err) => {
+                // Waiting doesn't outlast the deadline of the compilation that
+                // needs the package, if any, which is then aborted.
+                let wait = Route::deadline().map_or(delay, |deadline| {
+                    delay.min(deadline.saturating_duration_since(Instant::now()))
+                });
+                thread::sleep(wait);
+                Route::check_deadline();
+                delay = delay.saturating_mul(2);
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether a failed download may succeed when tried again: the server failed
+/// with a 5xx status or the connection timed out. Other failures, like 4xx
+/// statuses or TLS and certificate errors, would fail the same way again.
+fn is_transient(error: &PackageError) -> bool {
+    let (PackageError::NetworkFailed(Some(message))
+    | PackageError::ConnectionFailed(Some(message))) = error
+    else {
+        return false;
+    };
+    // The message is the one of `ureq::Error`, e.g. `{url}: status code 503`
+    // or `{url}: Network Error: timed out reading response`.
+    if let Some((_, status)) = message.rsplit_once("status code ") {
+        return status
+            .get(..3)
+            .and_then(|code| code.parse::<u16>().ok())
+            .is_some_and(|code| (500..600).contains(&code));
     }
+    let message = message.to_lowercase();
+    message.contains("timed out") || message.contains("timeout")
 }
 
 /// Compile and format several download statistics and make and attempt at
@@ -119,3 +241,114 @@ fn as_bytes_unit(size: usize) -> String {
 fn as_throughput_unit(size: usize) -> String {
     as_bytes_unit(size) + "/s"
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(retries: u32) -> PackageConfig {
+        PackageConfig { retries, ..PackageConfig::default() }
+    }
+
+    fn failure(message: &str) -> PackageError {
+        PackageError::NetworkFailed(Some(message.into()))
+    }
+
+    #[test]
+    fn test_retry_after_transient_failure() {
+        let mut attempts = 0;
+        let result = with_retries(&config(3), || {
+            attempts += 1;
+            match attempts {
+                1 => Err(failure("https://packages.typst.org: status code 503")),
+                _ => Ok(attempts),
+            }
+        });
+        assert_eq!(result, Ok(2));
+    }
+
+    #[test]
+    fn test_retry_after_timeout() {
+        let mut attempts = 0;
+        let result = with_retries(&config(3), || {
+            attempts += 1;
+            match attempts {
+                1 | 2 => {
+                    Err(failure("https://packages.typst.org: Network Error: timed out"))
+                }
+                _ => Ok(attempts),
+            }
+        });
+        assert_eq!(result, Ok(3));
+    }
+
+    #[test]
+    fn test_no_retry_after_permanent_failure() {
+        let spec: PackageSpec = "@preview/missing:0.1.0".parse().unwrap();
+        for error in [
+            PackageError::NotFound(spec),
+            failure("https://packages.typst.org: status code 403"),
+            failure(
+                "https://packages.typst.org: Connection Failed: invalid peer certificate",
+            ),
+        ] {
+            let mut attempts = 0;
+            let result = with_retries::<()>(&config(3), || {
+                attempts += 1;
+                Err(error.clone())
+            });
+            assert_eq!(result, Err(error));
+            assert_eq!(attempts, 1);
+        }
+    }
+
+    #[test]
+    fn test_retries_run_out() {
+        let mut attempts = 0;
+        let result = with_retries::<()>(&config(2), || {
+            attempts += 1;
+            Err(failure("https://packages.typst.org: status code 502"))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn test_retries_end_at_the_deadline() {
+        let config = PackageConfig { backoff_millis: 60_000, ..config(3) };
+        let start = Instant::now();
+        let deadline = start + Duration::from_millis(50);
+        let aborted = std::panic::catch_unwind(|| {
+            Route::with_deadline(Some(deadline), || {
+                with_retries::<()>(&config, || {
+                    Err(failure("https://packages.typst.org: status code 503"))
+                })
+            })
+        });
+        assert!(aborted.unwrap_err().is::<typst::engine::DeadlineExceeded>());
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+    #[test]
+    fn test_timeout_fails_instead_of_hanging() {
+        // A proxy that accepts connections, but never answers.
+        let proxy = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = PackageConfig {
+            proxy: Some(format!("http://{}", proxy.local_addr().unwrap())),
+            timeout_millis: Some(200),
+            ..PackageConfig::default()
+        };
+        let dir = std::env::temp_dir()
+            .join(format!("typst-shared-timeout-{}", std::process::id()));
+        let storage = PackageStorage::new(
+            Some(dir.join("cache")),
+            Some(dir.join("packages")),
+            downloader(&config),
+        );
+        let spec = "@preview/example:0.1.0".parse().unwrap();
+        let start = Instant::now();
+        let result = prepare_package(&storage, &spec, &config);
+        assert!(matches!(result, Err(PackageError::NetworkFailed(Some(_)))));
+        assert!(start.elapsed() < Duration::from_secs(10));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
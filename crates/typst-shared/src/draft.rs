@@ -0,0 +1,93 @@
+use crate::java_world::JavaWorld;
+use typst::foundations::{Smart, Style};
+use typst::model::ParElem;
+use typst::text::TextElem;
+
+/// Simplifications of the layout for quick previews.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DraftLayout {
+    pub no_hyphenation: bool,
+    pub no_justification: bool,
+}
+
+impl DraftLayout {
+    /// The set rules for the simplifications.
+    pub fn styles(&self) -> Vec<Style> {
+        let mut styles = vec![];
+        if self.no_hyphenation {
+            styles.push(TextElem::set_hyphenate(Smart::Custom(false)).into());
+        }
+        if self.no_justification {
+            styles.push(ParElem::set_justify(false).into());
+        }
+        styles
+    }
+}
+
+/// Turns off hyphenation and justification for the documents compiled in the
+/// world, as if they started with `#set text(hyphenate: false)` and
+/// `#set par(justify: false)`, which makes layout faster. This changes the
+/// layout: lines break at other places and paragraphs are ragged. Set rules in
+/// the document itself still take precedence.
+#[no_mangle]
+pub extern "C" fn set_draft_layout(
+    world_ptr: *mut JavaWorld,
+    no_hyphenation: i32,   // 1 -- true, 0 -- false
+    no_justification: i32, // 1 -- true, 0 -- false
+) {
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    world.draft = DraftLayout {
+        no_hyphenation: no_hyphenation == 1,
+        no_justification: no_justification == 1,
+    };
+    world.apply_style_overrides();
+    let _ = Box::into_raw(world); // Not to drop the world!
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, world_with_library};
+    use crate::text::extract_text_positions;
+    use typst::Library;
+
+    /// Where the lines of the document end, in points from the page's left edge.
+    fn line_ends(world: *mut JavaWorld) -> Vec<f64> {
+        let result = json(extract_text_positions(world));
+        let mut lines: Vec<(f64, f64)> = vec![];
+        for run in result["output"]["Ok"].as_array().unwrap() {
+            let rect = &run["rect"];
+            let y = rect["y"].as_f64().unwrap();
+            let end = rect["x"].as_f64().unwrap() + rect["width"].as_f64().unwrap();
+            match lines.last_mut() {
+                Some((line_y, line_end)) if (*line_y - y).abs() < 0.01 => {
+                    *line_end = line_end.max(end)
+                }
+                _ => lines.push((y, end)),
+            }
+        }
+        lines.into_iter().map(|(_, end)| end).collect()
+    }
+
+    #[test]
+    fn test_draft_layout_makes_justified_paragraphs_ragged() {
+        // Justified by the library, as the document's own set rules would win.
+        let mut library = Library::builder().build();
+        library.styles.set(ParElem::set_justify(true).into());
+        let world = world_with_library(
+            Box::into_raw(Box::new(library)),
+            "#set page(width: 120pt, height: auto, margin: 10pt)\n#lorem(40)",
+        );
+        let ends = line_ends(world);
+        let (_, lines) = ends.split_last().unwrap();
+        assert!(lines.len() > 3);
+        assert!(lines.iter().all(|end| (end - 110.0).abs() < 0.5));
+        set_draft_layout(world, 1, 1);
+        let ends = line_ends(world);
+        let (_, lines) = ends.split_last().unwrap();
+        assert!(lines.iter().all(|end| *end < 110.5));
+        assert!(lines.iter().any(|end| *end < 105.0));
+        free_world(world);
+    }
+}
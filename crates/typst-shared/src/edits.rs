@@ -0,0 +1,131 @@
+use crate::exception::Except;
+use crate::memory_management::{JavaResult, ThickBytePtr};
+use crate::throw;
+use serde::{Deserialize, Serialize};
+use std::mem;
+use typst::utils::tick;
+
+/// Replaces the bytes `start..end` of a text with `replacement`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextEdit {
+    pub start: i64,
+    pub end: i64,
+    pub replacement: String,
+}
+
+/// Applies the edits, given as a JSON list of [`TextEdit`]s, to the source.
+/// All ranges refer to the original source, in any order. Edits must not
+/// overlap, though an insertion may touch a replaced range; insertions at the
+/// same offset are applied in the order they are given.
+#[no_mangle]
+pub extern "C" fn apply_edits(
+    source: ThickBytePtr,
+    edits_json: ThickBytePtr,
+) -> JavaResult<Except<String>> {
+    tick!();
+    let source_str = source.to_str();
+    let edits_str = edits_json.to_str();
+    let result = serde_json::from_str::<Vec<TextEdit>>(&edits_str)
+        .map_err(|err| {
+            throw!(
+                "java.lang.IllegalArgumentException".to_string(),
+                Some(format!("Malformed edits: {err}"))
+            )
+        })
+        .and_then(|edits| apply(&source_str, edits));
+    mem::forget(source_str);
+    mem::forget(edits_str);
+    JavaResult::pack(result)
+}
+
+/// Applies the edits to the text, see [`apply_edits`].
+pub fn apply(text: &str, mut edits: Vec<TextEdit>) -> Except<String> {
+    for edit in &edits {
+        if edit.start < 0
+            || edit.start > edit.end
+            || edit.end as usize > text.len()
+            || !text.is_char_boundary(edit.start as usize)
+            || !text.is_char_boundary(edit.end as usize)
+        {
+            return Err(throw!(
+                "java.lang.IndexOutOfBoundsException".to_string(),
+                Some(format!(
+                    "Invalid range {}..{} of {} bytes",
+                    edit.start,
+                    edit.end,
+                    text.len()
+                ))
+            ));
+        }
+    }
+
+    // The sort is stable, so insertions at the same offset keep their order.
+    edits.sort_by_key(|edit| (edit.start, edit.end));
+    for pair in edits.windows(2) {
+        if pair[1].start < pair[0].end {
+            return Err(throw!(
+                "java.lang.IllegalArgumentException".to_string(),
+                Some(format!(
+                    "Overlapping edits {}..{} and {}..{}",
+                    pair[0].start, pair[0].end, pair[1].start, pair[1].end
+                ))
+            ));
+        }
+    }
+
+    // Applied from the end, the ranges of the remaining edits stay valid.
+    let mut result = text.to_string();
+    for edit in edits.iter().rev() {
+        result.replace_range(edit.start as usize..edit.end as usize, &edit.replacement);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{json, thick};
+    use serde_json::json;
+
+    fn edit(start: i64, end: i64, replacement: &str) -> TextEdit {
+        TextEdit { start, end, replacement: replacement.to_string() }
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let edits = r#"[
+            {"start": 12, "end": 12, "replacement": "!"},
+            {"start": 0, "end": 5, "replacement": "Goodbye"},
+            {"start": 12, "end": 12, "replacement": "?"}
+        ]"#;
+        let result = json(apply_edits(thick("Hello, world"), thick(edits)));
+        assert_eq!(result, json!({"Ok": "Goodbye, world!?"}));
+    }
+
+    #[test]
+    fn test_insertion_touching_a_replacement() {
+        let edits = vec![edit(4, 7, "BAR"), edit(4, 4, "<"), edit(7, 7, ">")];
+        assert_eq!(apply("foo bar baz", edits).ok().as_deref(), Some("foo <BAR> baz"));
+    }
+
+    #[test]
+    fn test_overlapping_edits_are_rejected() {
+        let edits = r#"[
+            {"start": 0, "end": 5, "replacement": "a"},
+            {"start": 4, "end": 8, "replacement": "b"}
+        ]"#;
+        let result = json(apply_edits(thick("Hello, world"), thick(edits)));
+        assert_eq!(result["Err"]["class"], "java.lang.IllegalArgumentException");
+        assert_eq!(result["Err"]["message"], "Overlapping edits 0..5 and 4..8");
+    }
+
+    #[test]
+    fn test_invalid_ranges_are_rejected() {
+        // Past the end, negative, and inside the two bytes of `ä`.
+        for (start, end) in [(3, 20), (-1, 2), (1, 2)] {
+            let result =
+                apply("äb", vec![edit(start, end, "")]).err().map(|err| err.class);
+            assert_eq!(result.as_deref(), Some("java.lang.IndexOutOfBoundsException"));
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::extended_info::ExtendedSourceDiagnostic;
+use crate::throw;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -23,6 +25,44 @@ pub struct StackTraceElement {
 
 pub type Except<T> = Result<T, Exception>;
 
+/// Fails with the first diagnostic as a `java.lang.RuntimeException`; the
+/// others are attached as suppressed exceptions.
+impl From<Vec<ExtendedSourceDiagnostic>> for Exception {
+    fn from(diagnostics: Vec<ExtendedSourceDiagnostic>) -> Self {
+        let mut exceptions = diagnostics.iter().map(|diagnostic| {
+            throw!("java.lang.RuntimeException".to_string(), Some(describe(diagnostic)))
+        });
+        let mut first = exceptions.next().unwrap_or_else(|| {
+            throw!(
+                "java.lang.RuntimeException".to_string(),
+                Some("failed without diagnostics".to_string())
+            )
+        });
+        first.suppressed = exceptions.map(Arc::new).collect();
+        first
+    }
+}
+
+/// Formats the diagnostic as `path:line:column: message`, followed by its hints.
+fn describe(diagnostic: &ExtendedSourceDiagnostic) -> String {
+    let span = &diagnostic.span;
+    let mut description = match &span.file {
+        Some(file) => format!(
+            "{}:{}:{}: {}",
+            file.path,
+            span.start_line + 1,
+            span.start_col + 1,
+            diagnostic.message
+        ),
+        None => diagnostic.message.clone(),
+    };
+    for hint in &diagnostic.hints {
+        description.push_str("\nhint: ");
+        description.push_str(hint);
+    }
+    description
+}
+
 #[macro_export]
 macro_rules! here {
     () => {{
@@ -98,4 +138,29 @@ macro_rules! or_rethrow {
             }
         }
     }};
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extended_info::Resolve;
+    use crate::memory_management::free_world;
+    use crate::test_utils::world;
+    use typst::diag::SourceDiagnostic;
+    use typst::syntax::Span;
+
+    #[test]
+    fn test_diagnostics_become_suppressed_exceptions() {
+        let world = world("");
+        let diagnostics = vec![
+            SourceDiagnostic::error(Span::detached(), "first").with_hint("try this"),
+            SourceDiagnostic::error(Span::detached(), "second"),
+        ];
+        let resolved = diagnostics.into_iter().map(|it| it.resolve(unsafe { &*world }));
+        let exception = Exception::from(resolved.collect::<Vec<_>>());
+        assert_eq!(exception.class, "java.lang.RuntimeException");
+        assert_eq!(exception.message.as_deref(), Some("first\nhint: try this"));
+        assert_eq!(exception.suppressed.len(), 1);
+        assert_eq!(exception.suppressed[0].message.as_deref(), Some("second"));
+        free_world(world);
+    }
+}
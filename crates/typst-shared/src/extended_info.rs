@@ -1,9 +1,15 @@
+use std::num::NonZeroU64;
 use std::ops::Range;
 
+use crate::exception::Except;
+use crate::java_world::JavaWorld;
+use crate::memory_management::JavaResult;
+use crate::throw;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use typst::diag::{
     EcoString, FileError, PackageError, Severity, SourceDiagnostic, Tracepoint, Warned,
+    WARNING_IDENTIFIERS,
 };
 use typst::ecow::EcoVec;
 use typst::syntax::package::{PackageSpec, PackageVersion};
@@ -59,6 +65,9 @@ pub struct ExtendedSourceDiagnostic {
     pub message: String,
     pub trace: Vec<ExtendedSpanned<ExtendedTracepoint>>,
     pub hints: Vec<String>,
+    /// Identifies the kind of warning, one of `warning_codes()`, or is `timeout`
+    /// for compilations aborted by `set_compile_timeout`.
+    pub code: Option<String>,
 }
 
 impl Resolve<ExtendedSourceDiagnostic> for SourceDiagnostic {
@@ -69,10 +78,17 @@ impl Resolve<ExtendedSourceDiagnostic> for SourceDiagnostic {
             message: self.message.to_string(),
             trace: self.trace.into_iter().map(|s| s.resolve(world)).collect(),
             hints: self.hints.into_iter().map(|e| e.to_string()).collect(),
+            code: self.identifier.map(|it| it.to_string()),
         }
     }
 }
 
+/// All codes that warnings may carry, e.g. to check documentation for completeness.
+#[no_mangle]
+pub extern "C" fn warning_codes() -> JavaResult<Vec<String>> {
+    JavaResult::pack(WARNING_IDENTIFIERS.iter().map(|it| it.to_string()).collect())
+}
+
 // impl From<SourceDiagnostic> for ExtendedSourceDiagnostic {
 //     fn from(diagnostic: SourceDiagnostic) -> Self {
 //         ExtendedSourceDiagnostic {
@@ -168,6 +184,32 @@ fn resolve_range(
     Some((start as i64, end as i64, start_line, start_col, end_line, end_col))
 }
 
+/// Resolves the `native` value of an [`ExtendedSpan`] against the world again,
+/// e.g. after the source was edited. The value is only meaningful within the
+/// process that produced it, as files are numbered in the order they are first
+/// seen. Zero stands for a detached span.
+#[no_mangle]
+pub extern "C" fn decode_span(
+    world_ptr: *mut JavaWorld,
+    native: u64,
+) -> JavaResult<Except<ExtendedSpan>> {
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let span = NonZeroU64::new(native).map_or(Span::detached(), Span::from_raw);
+    let known = span
+        .id()
+        .is_none_or(|id| FileId::try_from_raw(id.into_raw()).is_some());
+    let result = if known {
+        Ok(span.resolve(world.as_ref()))
+    } else {
+        Err(throw!(
+            "java.lang.IllegalArgumentException".to_string(),
+            Some(format!("Span {native} refers to an unknown file"))
+        ))
+    };
+    let _ = Box::into_raw(world); // Not to drop the world!
+    JavaResult::pack(result)
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ExtendedTracepoint {
@@ -290,6 +332,9 @@ pub enum ExtendedPackageError {
     NotFound { package: ExtendedPackageSpec },
     VersionNotFound { package: PackageSpec, version: ExtendedPackageVersion },
     NetworkFailed { message: Option<String> },
+    DnsFailed { message: Option<String> },
+    ConnectionFailed { message: Option<String> },
+    TlsFailed { message: Option<String> },
     MalformedArchive { message: Option<String> },
     Other { message: Option<String> },
 }
@@ -306,6 +351,17 @@ impl From<PackageError> for ExtendedPackageError {
             PackageError::NetworkFailed(message) => ExtendedPackageError::NetworkFailed {
                 message: message.map(|e| e.to_string()),
             },
+            PackageError::DnsFailed(message) => ExtendedPackageError::DnsFailed {
+                message: message.map(|e| e.to_string()),
+            },
+            PackageError::ConnectionFailed(message) => {
+                ExtendedPackageError::ConnectionFailed {
+                    message: message.map(|e| e.to_string()),
+                }
+            }
+            PackageError::TlsFailed(message) => ExtendedPackageError::TlsFailed {
+                message: message.map(|e| e.to_string()),
+            },
             PackageError::MalformedArchive(message) => {
                 ExtendedPackageError::MalformedArchive {
                     message: message.map(|e| e.to_string()),
@@ -330,6 +386,15 @@ impl From<ExtendedPackageError> for PackageError {
             ExtendedPackageError::NetworkFailed { message } => {
                 PackageError::NetworkFailed(message.map(EcoString::from))
             }
+            ExtendedPackageError::DnsFailed { message } => {
+                PackageError::DnsFailed(message.map(EcoString::from))
+            }
+            ExtendedPackageError::ConnectionFailed { message } => {
+                PackageError::ConnectionFailed(message.map(EcoString::from))
+            }
+            ExtendedPackageError::TlsFailed { message } => {
+                PackageError::TlsFailed(message.map(EcoString::from))
+            }
             ExtendedPackageError::MalformedArchive { message } => {
                 PackageError::MalformedArchive(message.map(EcoString::from))
             }
@@ -359,7 +424,6 @@ impl<T2, T: Resolve<T2> + Clone> Resolve<Vec<T2>> for EcoVec<T> {
     }
 }
 
-
 pub type ExtendedFileResult<T> = Result<T, ExtendedFileError>;
 
 /// A package's version.
@@ -436,3 +500,55 @@ impl<T2, T1: Resolve<T2>> Resolve<Option<T2>> for Option<T1> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, world};
+    use typst::syntax::{LinkedNode, Side};
+
+    #[test]
+    fn test_warnings_carry_their_code() {
+        let codes = json(warning_codes());
+        assert!(codes.as_array().unwrap().contains(&"empty-strong".into()));
+        let world = world("Hello **");
+        let result = json(crate::compile::compile(world, 0, 1, 0, 1, 72.0));
+        let warnings = result["Ok"]["warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0]["code"], "empty-strong");
+        free_world(world);
+    }
+
+    #[test]
+    fn test_decode_span_round_trips() {
+        let world = world("Hello\n*World*");
+        let span = {
+            let world = unsafe { &*world };
+            let source = world.source(world.main()).unwrap();
+            let leaf = LinkedNode::new(source.root()).leaf_at(8, Side::After).unwrap();
+            assert_eq!(leaf.text(), "World");
+            leaf.span()
+        };
+        let decoded = json(decode_span(world, span.into_raw().get()));
+        let decoded = &decoded["Ok"];
+        assert_eq!(decoded["native"], span.into_raw().get());
+        assert_eq!(decoded["file"]["path"], "/main.typ");
+        assert_eq!(decoded["start_ind"], 7);
+        assert_eq!(decoded["end_ind"], 12);
+        assert_eq!(decoded["start_line"], 1);
+        assert_eq!(decoded["start_col"], 1);
+        free_world(world);
+    }
+
+    #[test]
+    fn test_decode_span_handles_detached_and_unknown_files() {
+        let world = world("Hello");
+        let detached = json(decode_span(world, 0));
+        assert_eq!(detached["Ok"]["file"], serde_json::Value::Null);
+        assert_eq!(detached["Ok"]["start_ind"], 0);
+        let unknown = json(decode_span(world, (u16::MAX as u64) << 48 | 2));
+        assert_eq!(unknown["Err"]["class"], "java.lang.IllegalArgumentException");
+        free_world(world);
+    }
+}
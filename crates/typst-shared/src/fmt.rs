@@ -1,20 +1,345 @@
 use std::mem;
-use crate::memory_management::ThickBytePtr;
+use crate::exception::Except;
+use crate::memory_management::{JavaResult, ThickBytePtr};
+use crate::syntax::try_parse_in_mode;
+use serde::{Deserialize, Serialize};
 use typstyle_core::{Config, Typstyle};
+use typst::syntax::{parse, parse_code, parse_math, LinkedNode, Source, SyntaxKind};
 use typst::utils::tick;
 
+/// The byte order mark, as it appears at the start of a decoded source.
+const BOM: char = '\u{feff}';
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatError {
+    pub message: String,
+    /// Where the source failed to parse, if that's the reason formatting failed.
+    pub location: Option<FormatErrorLocation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatErrorLocation {
+    pub offset: i64,
+    pub line: i64,
+    pub column: i64,
+}
+
+/// Formats the source. A byte order mark at its start is not formatted, and is
+/// put back in front of the output if `preserve_bom` is set, which is what
+/// callers writing the output back to the file should normally pass.
 #[no_mangle]
-pub extern "C" fn format_source(content: ThickBytePtr, column: i32, tab_width: i32) -> ThickBytePtr {
-    tick!("{:?}, {}, {}", content, column, tab_width);
+pub extern "C" fn format_source(
+    content: ThickBytePtr,
+    column: i32,
+    tab_width: i32,
+    mode: i32,          // Same as for parse_syntax
+    final_newline: i32, // 0 -- as in input, 1 -- ensure, 2 -- strip
+    preserve_bom: i32,  // 1 -- true, 0 -- false
+) -> JavaResult<Result<String, FormatError>> {
+    tick!(
+        "{:?}, {}, {}, {}, {}, {}",
+        content,
+        column,
+        tab_width,
+        mode,
+        final_newline,
+        preserve_bom
+    );
     let str = content.to_str();
-    let result = format(&str, column, tab_width);
+    let (bom, body) = match str.strip_prefix(BOM) {
+        Some(body) => (true, body.to_string()),
+        None => (false, str.clone()),
+    };
+    let result = format(&body, column, tab_width, mode)
+        .map(|it| apply_final_newline(it, str.ends_with('\n'), final_newline))
+        .map(|it| if bom && preserve_bom == 1 { format!("{BOM}{it}") } else { it });
     mem::forget(str);
-    ThickBytePtr::from_str(result)
+    JavaResult::pack(result)
 }
 
-pub fn format(content: &String, column: i32, tab_width: i32) -> String {
+pub fn format(
+    content: &String,
+    column: i32,
+    tab_width: i32,
+    mode: i32,
+) -> Result<String, FormatError> {
     let cfg = Config::new()
         .with_width(column as usize)
         .with_tab_spaces(tab_width as usize);
-    Typstyle::new(cfg).format_content(content).unwrap_or_else(|_err| "".to_string())
+    let typstyle = Typstyle::new(cfg);
+    // Typstyle only formats whole documents, so snippets in other modes are
+    // embedded into a markup document and extracted back afterwards.
+    let result = match mode {
+        0 => typstyle.format_content(content), // Content
+        1 => typstyle
+            .format_content(&format!("#{{\n{content}\n}}"))
+            .map(|it| unwrap_code_block(&it, tab_width as usize)), // Code
+        2 => typstyle
+            .format_content(&format!("${content}$"))
+            .map(|it| unwrap_equation(&it)), // Math
+        _ => {
+            return Err(FormatError {
+                message: format!("Unexpected mode {} for formatting", mode),
+                location: None,
+            })
+        }
+    };
+    result.map_err(|err| {
+        parse_error(content, mode)
+            .unwrap_or_else(|| FormatError { message: err.to_string(), location: None })
+    })
+}
+
+/// Re-emits the source without comments and with whitespace normalized, so that
+/// sources that only differ in these yield the same text, e.g. for cache keys.
+/// Runs of whitespace become a single space, line break or paragraph break.
+/// Indentation is kept in markup, where it nests lists, and dropped elsewhere.
+#[no_mangle]
+pub extern "C" fn canonical_source(
+    content: ThickBytePtr,
+    mode: i32,
+) -> JavaResult<Except<String>> {
+    tick!("{:?}, {}", content, mode);
+    let str = content.to_str();
+    let text = try_parse_in_mode(&str, mode).map(|root| {
+        let mut canonical = Canonical::default();
+        canonical.visit(&LinkedNode::new(&root));
+        canonical.text
+    });
+    mem::forget(str);
+    JavaResult::pack(text)
+}
+
+/// Whitespace that is only written once the next token comes, so that adjacent
+/// whitespace and removed comments merge into the strongest break among them.
+#[derive(Debug, Default, Clone, PartialEq)]
+enum Gap {
+    #[default]
+    None,
+    Space,
+    /// A line break followed by the given indentation.
+    Line(String),
+    Par,
+}
+
+#[derive(Default)]
+struct Canonical {
+    text: String,
+    gap: Gap,
+}
+
+impl Canonical {
+    fn visit(&mut self, node: &LinkedNode) {
+        if node.children().len() > 0 {
+            for child in node.children() {
+                self.visit(&child);
+            }
+            return;
+        }
+        let in_markup = node.parent_kind() == Some(SyntaxKind::Markup);
+        match node.kind() {
+            // In code, a comment may be all that separates two tokens.
+            SyntaxKind::LineComment | SyntaxKind::BlockComment => {
+                if !in_markup {
+                    self.gap(Gap::Space);
+                }
+            }
+            SyntaxKind::Parbreak => self.gap(Gap::Par),
+            SyntaxKind::Space => {
+                let text = node.text();
+                match text.rfind('\n') {
+                    Some(last) if in_markup => {
+                        self.gap(Gap::Line(text[last + 1..].to_string()))
+                    }
+                    Some(_) => self.gap(Gap::Line(String::new())),
+                    None => self.gap(Gap::Space),
+                }
+            }
+            _ if node.text().is_empty() => {}
+            _ => {
+                // Whitespace at the start is dropped, like that at the end.
+                let gap = mem::take(&mut self.gap);
+                if !self.text.is_empty() {
+                    match gap {
+                        Gap::None => {}
+                        Gap::Space => self.text.push(' '),
+                        Gap::Line(indent) => {
+                            self.text.push('\n');
+                            self.text.push_str(&indent);
+                        }
+                        Gap::Par => self.text.push_str("\n\n"),
+                    }
+                }
+                self.text.push_str(node.text());
+            }
+        }
+    }
+
+    fn gap(&mut self, gap: Gap) {
+        let rank = |gap: &Gap| match gap {
+            Gap::None => 0,
+            Gap::Space => 1,
+            Gap::Line(_) => 2,
+            Gap::Par => 3,
+        };
+        if rank(&gap) >= rank(&self.gap) {
+            self.gap = gap;
+        }
+    }
+}
+
+/// Makes the output end with exactly one newline or none, as requested.
+fn apply_final_newline(
+    formatted: String,
+    input_has_newline: bool,
+    final_newline: i32,
+) -> String {
+    let ensure = match final_newline {
+        1 => true,
+        2 => false,
+        _ => input_has_newline,
+    };
+    let body = formatted.trim_end_matches(['\n', '\r']);
+    if ensure {
+        format!("{body}\n")
+    } else {
+        body.to_string()
+    }
+}
+
+/// Extracts the body of a formatted `#{ ... }` block, removing one indentation level.
+fn unwrap_code_block(formatted: &str, tab_width: usize) -> String {
+    let trimmed = formatted.trim();
+    let Some(body) = trimmed.strip_prefix("#{").and_then(|it| it.strip_suffix('}'))
+    else {
+        return trimmed.to_string();
+    };
+    let indent = " ".repeat(tab_width);
+    body.trim_matches('\n')
+        .lines()
+        .map(|line| line.strip_prefix(indent.as_str()).unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Extracts the body of a formatted `$ ... $` equation.
+fn unwrap_equation(formatted: &str) -> String {
+    let trimmed = formatted.trim();
+    trimmed
+        .strip_prefix('$')
+        .and_then(|it| it.strip_suffix('$'))
+        .unwrap_or(trimmed)
+        .trim()
+        .to_string()
+}
+
+/// Locates the first syntax error in the source. Lines and columns are computed
+/// the same way as for diagnostics.
+fn parse_error(content: &str, mode: i32) -> Option<FormatError> {
+    let root = match mode {
+        0 => parse(content),
+        1 => parse_code(content),
+        2 => parse_math(content),
+        _ => return None,
+    };
+    let (offset, message) = first_error(&LinkedNode::new(&root))?;
+    let source = Source::detached(content);
+    Some(FormatError {
+        message,
+        location: Some(FormatErrorLocation {
+            offset: offset as i64,
+            line: source.byte_to_line(offset).map_or(-1, |it| it as i64),
+            column: source.byte_to_column(offset).map_or(-1, |it| it as i64),
+        }),
+    })
+}
+
+fn first_error(node: &LinkedNode) -> Option<(usize, String)> {
+    if !node.erroneous() {
+        return None;
+    }
+    if node.kind() == SyntaxKind::Error {
+        let message = node.errors().first()?.message.to_string();
+        return Some((node.offset(), message));
+    }
+    node.children().find_map(|child| first_error(&child))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{json, thick};
+
+    fn canonical(text: &str, mode: i32) -> serde_json::Value {
+        json(canonical_source(thick(text), mode))
+    }
+
+    #[test]
+    fn test_canonical_source_ignores_comments_and_whitespace() {
+        let variants = [
+            "= Title\n\n- item #f(1, 2)\n  - nested",
+            "  = Title // a comment\n\n\n\n- item   #f(1,   2)\n  - nested  \n",
+            "= Title /* one */\n\n/* two */\n- item #f(1, /* three */ 2)\n  - nested",
+        ];
+        let expected = canonical(variants[0], 0);
+        assert_eq!(expected["Ok"], variants[0]);
+        for variant in variants {
+            assert_eq!(canonical(variant, 0), expected);
+        }
+        // Anything else is kept.
+        assert_ne!(canonical("= Title\n\n- item #f(1, 3)\n  - nested", 0), expected);
+        assert_eq!(canonical("let  x =\n  1 // one", 1)["Ok"], "let x =\n1");
+    }
+
+    #[test]
+    fn test_canonical_source_rejects_unknown_modes() {
+        let result = canonical("x", -1);
+        assert_eq!(result["Err"]["class"], "java.lang.IllegalArgumentException");
+    }
+
+    #[test]
+    fn test_format_source_preserves_bom() {
+        let format = |preserve_bom| {
+            json(format_source(thick("\u{feff}= Title\n"), 80, 2, 0, 0, preserve_bom))
+        };
+        assert_eq!(format(1)["Ok"], "\u{feff}= Title\n");
+        assert_eq!(format(0)["Ok"], "= Title\n");
+        // A BOM is never added.
+        let result = json(format_source(thick("= Title\n"), 80, 2, 0, 0, 1));
+        assert_eq!(result["Ok"], "= Title\n");
+    }
+
+    #[test]
+    fn test_format_error_locates_parse_error() {
+        let error = format(&"= Title\n#\"open".to_string(), 80, 2, 0).unwrap_err();
+        assert_eq!(error.message, "unclosed string");
+        let location = error.location.unwrap();
+        assert_eq!((location.offset, location.line, location.column), (9, 1, 1));
+    }
+
+    #[test]
+    fn test_format_in_each_mode() {
+        let formatted =
+            |text: &str, mode| format(&text.to_string(), 80, 2, mode).unwrap();
+        // A markup document, a code block and a single expression.
+        assert_eq!(formatted("#let x=1\n", 0).trim_end(), "#let x = 1");
+        assert_eq!(formatted("let x=1\nlet y=2", 1), "let x = 1\nlet y = 2");
+        assert_eq!(formatted("f(1,2)", 1), "f(1, 2)");
+    }
+
+    #[test]
+    fn test_format_source_final_newline() {
+        let formatted = |text: &str, final_newline| {
+            json(format_source(thick(text), 80, 2, 0, final_newline, 1))["Ok"].clone()
+        };
+        // The output for an input with and without a trailing newline.
+        for (final_newline, with, without) in [
+            (0, "= Title\n", "= Title"),
+            (1, "= Title\n", "= Title\n"),
+            (2, "= Title", "= Title"),
+        ] {
+            assert_eq!(formatted("= Title\n", final_newline), with);
+            assert_eq!(formatted("= Title", final_newline), without);
+        }
+    }
 }
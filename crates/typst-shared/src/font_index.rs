@@ -0,0 +1,381 @@
+use crate::exception::Except;
+use crate::java_world::JavaWorld;
+use crate::memory_management::{to_json, JavaResult, ThickBytePtr};
+use crate::throw;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use std::{fs, mem};
+use ttf_parser::Permissions;
+use typst::text::{FontBook, FontInfo};
+use typst::utils::tick;
+use typst_kit::fonts::{FontSlot, Fonts};
+use typst_timing::timed;
+
+/// The outcome of a system font search, in a form that can be stored and handed
+/// back to `new_world`, which then skips searching the file system again.
+/// Embedded fonts are not listed, they are always available.
+///
+/// Fonts are loaded lazily, so a font that was removed since the index was built
+/// only surfaces as missing once a document uses it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FontIndex {
+    pub fonts: Vec<FontIndexEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FontIndexEntry {
+    pub path: String,
+    /// Index of the font in its collection, zero if the file is not a collection.
+    pub index: u32,
+    pub info: FontInfo,
+    /// When the file was last modified, in milliseconds since the Unix epoch,
+    /// if known.
+    #[serde(default)]
+    pub modified: Option<u64>,
+}
+
+#[no_mangle]
+pub extern "C" fn build_font_index() -> JavaResult<FontIndex> {
+    tick!();
+    JavaResult::pack(search_index())
+}
+
+/// Searches for fonts like [`build_font_index`] and writes the index to the
+/// file at `path` as JSON, for [`load_fontbook`] in later runs.
+#[no_mangle]
+pub extern "C" fn dump_fontbook(path: ThickBytePtr) -> JavaResult<Except<()>> {
+    tick!();
+    let path_str = path.to_str();
+    let json = to_json(&search_index()).unwrap();
+    let result = fs::write(&*path_str, json)
+        .map_err(|err| throw!("java.io.IOException".to_string(), Some(err.to_string())));
+    mem::forget(path_str);
+    JavaResult::pack(result)
+}
+
+/// Reads an index written by [`dump_fontbook`], to be passed to `new_world`.
+/// `None` if the file doesn't exist or can't be read, or if one of the fonts
+/// was modified or removed since, in which case the index should be built
+/// again. Fonts installed since are not noticed.
+#[no_mangle]
+pub extern "C" fn load_fontbook(path: ThickBytePtr) -> JavaResult<Option<FontIndex>> {
+    tick!();
+    let path_str = path.to_str();
+    let index = fs::read(&*path_str)
+        .ok()
+        .and_then(|data| serde_json::from_slice::<FontIndex>(&data).ok())
+        .filter(|index| {
+            index.fonts.iter().all(|entry| {
+                entry.modified.is_some()
+                    && entry.modified == modified(Path::new(&entry.path))
+            })
+        });
+    mem::forget(path_str);
+    JavaResult::pack(index)
+}
+
+/// Searches the system for fonts.
+fn search_index() -> FontIndex {
+    let Fonts { book, fonts } = Fonts::searcher()
+        .include_system_fonts(true)
+        .search_with(&(vec![] as Vec<PathBuf>));
+    let fonts = fonts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, slot)| {
+            Some(FontIndexEntry {
+                path: slot.path()?.to_string_lossy().into_owned(),
+                index: slot.index(),
+                info: book.info(i)?.clone(),
+                modified: modified(slot.path()?),
+            })
+        })
+        .collect();
+    FontIndex { fonts }
+}
+
+/// When the file was last modified, in milliseconds since the Unix epoch.
+fn modified(path: &Path) -> Option<u64> {
+    let time = fs::metadata(path).and_then(|it| it.modified()).ok()?;
+    Some(time.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
+/// How a world's fonts were found, see `font_load_stats`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FontLoadStats {
+    /// The number of fonts, embedded ones included.
+    pub fonts: i64,
+    pub embedded: i64,
+    /// Whether the fonts were taken from a [`FontIndex`] instead of searched for.
+    pub from_index: bool,
+    /// How long the search or loading the index took.
+    pub millis: f64,
+    /// The directories that fonts were found in, sorted. The directories that
+    /// were scanned without finding anything are not known.
+    pub directories: Vec<String>,
+}
+
+impl FontLoadStats {
+    fn new(fonts: &[FontSlot], from_index: bool, elapsed: Duration) -> Self {
+        let directories: BTreeSet<String> = fonts
+            .iter()
+            .filter_map(|slot| slot.path()?.parent())
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .collect();
+        Self {
+            fonts: fonts.len() as i64,
+            embedded: fonts.iter().filter(|slot| slot.path().is_none()).count() as i64,
+            from_index,
+            millis: elapsed.as_secs_f64() * 1000.0,
+            directories: directories.into_iter().collect(),
+        }
+    }
+}
+
+/// Reports how the world's fonts were found and how long it took.
+#[no_mangle]
+pub extern "C" fn font_load_stats(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<FontLoadStats> {
+    tick!();
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let stats = world.font_stats.clone();
+    let _ = Box::into_raw(world); // Not to drop the world!
+    JavaResult::pack(stats)
+}
+
+/// The fonts found by searching the system, for worlds that are not created by
+/// `new_world` and have no index, like the one of `compile_string`. The search
+/// runs once, on first use, and the fonts are shared afterwards.
+pub fn system_fonts() -> Arc<Fonts> {
+    static FONTS: LazyLock<Arc<Fonts>> = LazyLock::new(|| Arc::new(load_fonts(None).0));
+    FONTS.clone()
+}
+
+/// Searches for fonts, or takes them from the index if there is one.
+pub fn load_fonts(index: Option<FontIndex>) -> (Fonts, FontLoadStats) {
+    let from_index = index.is_some();
+    let start = Instant::now();
+    let fonts = timed!("font discovery", search_or_index(index));
+    let stats = FontLoadStats::new(&fonts.fonts, from_index, start.elapsed());
+    (fonts, stats)
+}
+
+fn search_or_index(index: Option<FontIndex>) -> Fonts {
+    let Some(index) = index else {
+        return Fonts::searcher()
+            .include_system_fonts(true)
+            .search_with(&(vec![] as Vec<PathBuf>));
+    };
+
+    let mut book = FontBook::new();
+    let mut fonts = vec![];
+    for entry in index.fonts {
+        book.push(entry.info);
+        fonts.push(FontSlot::from_path(entry.path.into(), entry.index));
+    }
+
+    // Embedded fonts come last, as in a search.
+    let embedded = Fonts::searcher().include_system_fonts(false).search();
+    for (i, slot) in embedded.fonts.into_iter().enumerate() {
+        if let Some(info) = embedded.book.info(i) {
+            book.push(info.clone());
+            fonts.push(slot);
+        }
+    }
+
+    Fonts { book, fonts }
+}
+
+/// Whether a font may be embedded into a document, as its OS/2 table says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Embedding {
+    /// No restrictions. Also the case for fonts without an OS/2 table.
+    Permitted,
+    /// Must not be embedded without the legal owner's permission.
+    Restricted,
+    /// May be embedded in documents that are only viewed and printed.
+    PreviewAndPrint,
+    /// May be embedded in documents that are viewed, printed and edited.
+    Editable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FontEmbedInfo {
+    pub family: String,
+    /// `None` for embedded fonts.
+    pub path: Option<String>,
+    /// Index of the font in its collection, zero if the file is not a collection.
+    pub index: u32,
+    pub embedding: Embedding,
+    /// Whether a subset of the font may be embedded, as PDF export does.
+    pub subsetting: bool,
+}
+
+/// Reports the embedding permissions of the world's fonts. This loads every
+/// font; fonts that fail to load are left out.
+#[no_mangle]
+pub extern "C" fn font_embedding_status(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<Vec<FontEmbedInfo>> {
+    tick!();
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let infos = world
+        .fonts
+        .iter()
+        .filter_map(|slot| {
+            let font = slot.get()?;
+            let ttf = font.ttf();
+            let embedding = match ttf.permissions() {
+                None | Some(Permissions::Installable) => Embedding::Permitted,
+                Some(Permissions::Restricted) => Embedding::Restricted,
+                Some(Permissions::PreviewAndPrint) => Embedding::PreviewAndPrint,
+                Some(Permissions::Editable) => Embedding::Editable,
+            };
+            Some(FontEmbedInfo {
+                family: font.info().family.clone(),
+                path: slot.path().map(|it| it.to_string_lossy().into_owned()),
+                index: slot.index(),
+                embedding,
+                subsetting: ttf.is_subsetting_allowed(),
+            })
+        })
+        .collect();
+    let _ = Box::into_raw(world); // Not to drop the world!
+    JavaResult::pack(infos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::java_world::new_world;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{java, json, main_file, no_file, raw, thick};
+    use typst::Library;
+
+    /// Writes one of the embedded fonts to a file in the directory and indexes
+    /// it.
+    fn index_font(dir: &Path) -> FontIndex {
+        let embedded = Fonts::searcher().include_system_fonts(false).search();
+        let font = embedded.fonts[0].get().unwrap();
+        fs::create_dir_all(dir).unwrap();
+        let path = dir.join("font.otf");
+        fs::write(&path, font.data()).unwrap();
+        FontIndex {
+            fonts: vec![FontIndexEntry {
+                path: path.to_string_lossy().into_owned(),
+                index: 0,
+                info: font.info().clone(),
+                modified: modified(&path),
+            }],
+        }
+    }
+
+    fn indexed_world(index: &FontIndex) -> *mut JavaWorld {
+        let library = Box::into_raw(Box::new(Library::builder().build()));
+        let result = new_world(
+            library,
+            main_file,
+            no_file,
+            raw("null"),
+            0,
+            raw("{}"),
+            java(&Some(index.clone())),
+        );
+        result.ptr as *mut JavaWorld
+    }
+
+    #[test]
+    fn test_system_fonts_are_searched_once() {
+        assert!(Arc::ptr_eq(&system_fonts(), &system_fonts()));
+    }
+
+    #[test]
+    fn test_new_world_takes_fonts_from_the_index() {
+        let dir = std::env::temp_dir()
+            .join(format!("typst-shared-font-index-{}", std::process::id()));
+        let index = index_font(&dir);
+        let world = indexed_world(&index);
+        let stats = json(font_load_stats(world));
+        assert_eq!(stats["from_index"], true);
+        // The indexed font is the only one from the file system, so no
+        // directory was searched.
+        let directory = dir.to_string_lossy();
+        assert_eq!(stats["directories"], serde_json::json!([directory]));
+        let slots = unsafe { &(*world).fonts };
+        assert_eq!(slots[0].path(), Some(Path::new(&index.fonts[0].path)));
+        assert!(slots[1..].iter().all(|slot| slot.path().is_none()));
+        assert_eq!(slots[0].get().unwrap().info(), &index.fonts[0].info);
+        free_world(world);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_embedding_status_of_an_embeddable_font() {
+        let dir = std::env::temp_dir()
+            .join(format!("typst-shared-font-embedding-{}", std::process::id()));
+        let index = index_font(&dir);
+        let world = indexed_world(&index);
+        let status = json(font_embedding_status(world));
+        let fonts = status.as_array().unwrap();
+        assert_eq!(fonts.len(), unsafe { (*world).fonts.len() });
+        let font = &fonts[0];
+        assert_eq!(font["path"], index.fonts[0].path.as_str());
+        assert_eq!(font["family"], index.fonts[0].info.family.as_str());
+        assert_eq!(font["index"], 0);
+        assert_eq!(font["embedding"], "Permitted");
+        assert_eq!(font["subsetting"], true);
+        free_world(world);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_font_load_stats_count_the_slots() {
+        let world = crate::test_utils::world("");
+        let stats = json(font_load_stats(world));
+        let slots = unsafe { &(*world).fonts };
+        let embedded = slots.iter().filter(|slot| slot.path().is_none()).count();
+        assert_eq!(stats["from_index"], false);
+        assert_eq!(stats["fonts"], slots.len());
+        assert_eq!(stats["embedded"], embedded);
+        free_world(world);
+
+        let dir = std::env::temp_dir()
+            .join(format!("typst-shared-font-stats-{}", std::process::id()));
+        let world = indexed_world(&index_font(&dir));
+        let stats = json(font_load_stats(world));
+        let slots = unsafe { &(*world).fonts };
+        assert_eq!(stats["fonts"], slots.len());
+        assert_eq!(stats["embedded"], slots.len() - 1);
+        free_world(world);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_fontbook_round_trip_and_invalidation() {
+        let dir = std::env::temp_dir()
+            .join(format!("typst-shared-fontbook-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("fontbook.json");
+        let book = || thick(&path.to_string_lossy());
+        let result = json(dump_fontbook(book()));
+        assert_eq!(result, serde_json::json!({"Ok": null}));
+        let dumped: serde_json::Value =
+            serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(json(load_fontbook(book())), dumped);
+
+        let index = index_font(&dir);
+        fs::write(&path, to_json(&index).unwrap()).unwrap();
+        let loaded = json(load_fontbook(book()));
+        assert_eq!(loaded["fonts"][0]["path"], index.fonts[0].path.as_str());
+        assert_eq!(loaded["fonts"][0]["info"]["family"], index.fonts[0].info.family);
+        // Changing the font's modification time invalidates the book.
+        let font = fs::File::options().write(true).open(&index.fonts[0].path).unwrap();
+        font.set_modified(UNIX_EPOCH + Duration::from_secs(1)).unwrap();
+        assert!(json(load_fontbook(book())).is_null());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,91 @@
+use crate::compile::compile_paged;
+use crate::extended_info::{
+    ExtendedSourceDiagnostic, ExtendedSpan, ExtendedWarned, Resolve,
+};
+use crate::java_world::JavaWorld;
+use crate::memory_management::JavaResult;
+use serde::{Deserialize, Serialize};
+use typst::comemo::Track;
+use typst::engine::{Engine, Route, Sink, Traced};
+use typst::foundations::NativeElement;
+use typst::introspection::Counter;
+use typst::model::FootnoteElem;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FootnoteEntry {
+    /// The footnote's number, as counted by the footnote counter.
+    pub number: i64,
+    /// The footnote's body as plain text.
+    pub text: String,
+    /// Zero-based index of the page the footnote's marker is on.
+    pub page: i64,
+    /// The span of the `footnote` call in the text.
+    pub span: ExtendedSpan,
+}
+
+/// Lists the footnotes of the document in order. Footnotes that only refer to
+/// another footnote by its label, like `#footnote(<note>)`, are left out, as
+/// they have no body of their own.
+#[no_mangle]
+pub extern "C" fn list_footnotes(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<ExtendedWarned<Result<Vec<FootnoteEntry>, Vec<ExtendedSourceDiagnostic>>>>
+{
+    JavaResult::pack(compile_paged(world_ptr, |world, document, _| {
+        let introspector = &document.introspector;
+        let traced = Traced::default();
+        let mut sink = Sink::new();
+        let mut engine = Engine {
+            routines: &typst::ROUTINES,
+            world: world.track(),
+            introspector: introspector.track(),
+            traced: traced.track(),
+            sink: sink.track_mut(),
+            route: Route::default(),
+        };
+        let counter = Counter::of(FootnoteElem::elem());
+        let mut footnotes = vec![];
+        for elem in introspector.query(&FootnoteElem::elem().select()) {
+            let footnote = elem.to_packed::<FootnoteElem>().unwrap();
+            let (Some(body), Some(location)) = (footnote.body_content(), elem.location())
+            else {
+                continue;
+            };
+            let state = counter.at_loc(&mut engine, location)?;
+            footnotes.push(FootnoteEntry {
+                number: state.first() as i64,
+                text: body.plain_text().to_string(),
+                page: introspector.page(location).get() as i64 - 1,
+                span: elem.span().resolve(world),
+            });
+        }
+        Ok(footnotes)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, world};
+
+    #[test]
+    fn test_list_footnotes_numbers_them() {
+        let text = "A#footnote[First note] B#footnote[Second *note*] <n>\n\
+                    #pagebreak()\nC#footnote(<n>) D#footnote[Third]";
+        let world = world(text);
+        let result = json(list_footnotes(world));
+        let footnotes = result["output"]["Ok"].as_array().unwrap();
+        let entries: Vec<_> = footnotes
+            .iter()
+            .map(|it| (it["number"].as_i64().unwrap(), it["text"].as_str().unwrap()))
+            .collect();
+        // The reference to the second footnote is left out and not counted.
+        assert_eq!(entries, [(1, "First note"), (2, "Second note"), (3, "Third")]);
+        let pages: Vec<_> = footnotes.iter().map(|it| it["page"].as_i64()).collect();
+        assert_eq!(pages, [Some(0), Some(0), Some(1)]);
+        let start = text.find("footnote[First").unwrap();
+        assert_eq!(footnotes[0]["span"]["start_ind"], start);
+        free_world(world);
+    }
+}
@@ -0,0 +1,281 @@
+use crate::compile::compile_paged;
+use crate::extended_info::{ExtendedSourceDiagnostic, ExtendedWarned};
+use crate::java_world::JavaWorld;
+use crate::memory_management::JavaResult;
+use serde::{Deserialize, Serialize};
+use typst::introspection::Tag;
+use typst::layout::{Abs, Frame, FrameItem, Point, Size, Transform};
+use typst::model::Destination;
+use typst::visualize::Geometry;
+
+/// An axis-aligned rectangle on a page, in points from the top left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExtendedRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl ExtendedRect {
+    /// The bounding box of a `size`-sized box at `pos`, after applying `ts`.
+    pub fn bounding(ts: Transform, pos: Point, size: Size) -> Self {
+        let mut min = Point::splat(Abs::inf());
+        let mut max = Point::splat(-Abs::inf());
+        for point in [
+            pos,
+            pos + Point::with_x(size.x),
+            pos + Point::with_y(size.y),
+            pos + size.to_point(),
+        ] {
+            let t = point.transform(ts);
+            min = min.min(t);
+            max = max.max(t);
+        }
+        ExtendedRect {
+            x: min.x.to_pt(),
+            y: min.y.to_pt(),
+            width: (max.x - min.x).to_pt(),
+            height: (max.y - min.y).to_pt(),
+        }
+    }
+}
+
+/// Visits every item of the frame and its groups in order, together with the
+/// transform from the item's frame to the page and its position in that frame.
+pub fn walk_frame(
+    frame: &Frame,
+    ts: Transform,
+    f: &mut impl FnMut(Transform, Point, &FrameItem),
+) {
+    for (pos, item) in frame.items() {
+        f(ts, *pos, item);
+        if let FrameItem::Group(group) = item {
+            let ts = ts
+                .pre_concat(Transform::translate(pos.x, pos.y))
+                .pre_concat(group.transform);
+            walk_frame(&group.frame, ts, f);
+        }
+    }
+}
+
+/// Returns a copy of the frame without the items `keep` rejects, looking into
+/// groups recursively.
+pub fn filter_frame(frame: &Frame, keep: &impl Fn(&FrameItem) -> bool) -> Frame {
+    let mut result = Frame::new(frame.size(), frame.kind());
+    if frame.has_baseline() {
+        result.set_baseline(frame.baseline());
+    }
+    for (pos, item) in frame.items() {
+        if !keep(item) {
+            continue;
+        }
+        let item = match item {
+            FrameItem::Group(group) => {
+                let mut group = group.clone();
+                group.frame = filter_frame(&group.frame, keep);
+                FrameItem::Group(group)
+            }
+            other => other.clone(),
+        };
+        result.push(*pos, item);
+    }
+    result
+}
+
+/// An item of a frame, as laid out. Positions are relative to the frame the
+/// item is in, in points.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FrameNode {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    #[serde(flatten)]
+    pub kind: FrameNodeKind,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum FrameNodeKind {
+    Group {
+        /// The group's transform as `[sx, ky, kx, sy, tx, ty]`, translations in
+        /// points.
+        transform: [f64; 6],
+        clipped: bool,
+        children: Vec<FrameNode>,
+    },
+    /// A run of text. The height is the font size.
+    Text {
+        text: String,
+        font: String,
+    },
+    Shape {
+        geometry: String,
+    },
+    Image {
+        format: String,
+    },
+    /// A link area to a URL, or to a position as `page:x:y` with a zero-based
+    /// page.
+    Link {
+        destination: String,
+    },
+    /// The start or end of an element; the size is zero.
+    Tag {
+        element: Option<String>,
+        start: bool,
+    },
+}
+
+/// Returns the items of a page's frame as a tree of groups, in the order they
+/// are drawn. `None` if the document has no page with the zero-based index.
+#[no_mangle]
+pub extern "C" fn page_frame(
+    world_ptr: *mut JavaWorld,
+    page: i32,
+) -> JavaResult<ExtendedWarned<Result<Option<FrameNode>, Vec<ExtendedSourceDiagnostic>>>>
+{
+    JavaResult::pack(compile_paged(world_ptr, |_, document, _| {
+        let page = usize::try_from(page).ok().and_then(|it| document.pages.get(it));
+        Ok(page.map(|page| FrameNode {
+            x: 0.0,
+            y: 0.0,
+            width: page.frame.width().to_pt(),
+            height: page.frame.height().to_pt(),
+            kind: FrameNodeKind::Group {
+                transform: transform(Transform::identity()),
+                clipped: false,
+                children: frame_nodes(&page.frame),
+            },
+        }))
+    }))
+}
+
+fn frame_nodes(frame: &Frame) -> Vec<FrameNode> {
+    frame
+        .items()
+        .map(|(pos, item)| {
+            let (size, kind) = match item {
+                FrameItem::Group(group) => (
+                    group.frame.size(),
+                    FrameNodeKind::Group {
+                        transform: transform(group.transform),
+                        clipped: group.clip.is_some(),
+                        children: frame_nodes(&group.frame),
+                    },
+                ),
+                FrameItem::Text(text) => (
+                    Size::new(text.width(), text.size),
+                    FrameNodeKind::Text {
+                        text: text.text.to_string(),
+                        font: text.font.info().family.clone(),
+                    },
+                ),
+                FrameItem::Shape(shape, _) => {
+                    let geometry = match shape.geometry {
+                        Geometry::Line(_) => "line",
+                        Geometry::Rect(_) => "rect",
+                        Geometry::Curve(_) => "curve",
+                    };
+                    (
+                        shape.geometry.bbox_size(),
+                        FrameNodeKind::Shape { geometry: geometry.to_string() },
+                    )
+                }
+                FrameItem::Image(image, size, _) => (
+                    *size,
+                    FrameNodeKind::Image { format: format!("{:?}", image.format()) },
+                ),
+                FrameItem::Link(dest, size) => {
+                    let destination = match dest {
+                        Destination::Url(url) => url.to_string(),
+                        Destination::Position(pos) => format!(
+                            "{}:{}:{}",
+                            pos.page.get() - 1,
+                            pos.point.x.to_pt(),
+                            pos.point.y.to_pt()
+                        ),
+                        Destination::Location(_) => "location".to_string(),
+                    };
+                    (*size, FrameNodeKind::Link { destination })
+                }
+                FrameItem::Tag(tag) => (
+                    Size::zero(),
+                    match tag {
+                        Tag::Start(elem) => FrameNodeKind::Tag {
+                            element: Some(elem.elem().name().to_string()),
+                            start: true,
+                        },
+                        Tag::End(..) => {
+                            FrameNodeKind::Tag { element: None, start: false }
+                        }
+                    },
+                ),
+            };
+            FrameNode {
+                x: pos.x.to_pt(),
+                y: pos.y.to_pt(),
+                width: size.x.to_pt(),
+                height: size.y.to_pt(),
+                kind,
+            }
+        })
+        .collect()
+}
+
+fn transform(ts: Transform) -> [f64; 6] {
+    [ts.sx.get(), ts.ky.get(), ts.kx.get(), ts.sy.get(), ts.tx.to_pt(), ts.ty.to_pt()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, world};
+    use serde_json::Value;
+
+    /// The nodes from `node` down to the first that matches, depth-first.
+    fn path_to<'a>(node: &'a Value, matches: &impl Fn(&Value) -> bool) -> Vec<&'a Value> {
+        if matches(node) {
+            return vec![node];
+        }
+        let children = node["children"].as_array().into_iter().flatten();
+        for child in children {
+            let mut path = path_to(child, matches);
+            if !path.is_empty() {
+                path.insert(0, node);
+                return path;
+            }
+        }
+        vec![]
+    }
+
+    #[test]
+    fn test_page_frame_nests_groups() {
+        let world = world(
+            "#set page(width: 100pt, height: 100pt, margin: 10pt)\n\
+             Hi\n#rotate(90deg, rect(width: 20pt, height: 10pt))",
+        );
+        let result = json(page_frame(world, 0));
+        let root = &result["output"]["Ok"];
+        assert_eq!(root["type"], "Group");
+        assert_eq!(root["width"], 100.0);
+        assert_eq!(root["transform"], serde_json::json!([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]));
+
+        let text = path_to(root, &|node| node["type"] == "Text");
+        assert_eq!(text.last().unwrap()["text"], "Hi");
+
+        // The rectangle is nested in the group that rotates it.
+        let rect = path_to(root, &|node| node["geometry"] == "rect");
+        assert!(rect.len() > 2);
+        let rotated = rect.iter().find(|node| {
+            node["type"] == "Group" && node["transform"][1].as_f64() == Some(1.0)
+        });
+        assert!(rotated.is_some());
+
+        let result = json(page_frame(world, 1));
+        assert!(result["output"]["Ok"].is_null());
+        free_world(world);
+    }
+}
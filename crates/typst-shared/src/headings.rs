@@ -0,0 +1,91 @@
+use crate::compile::compile_structure;
+use crate::extended_info::{
+    ExtendedSourceDiagnostic, ExtendedSpan, ExtendedWarned, Resolve,
+};
+use crate::java_world::JavaWorld;
+use crate::memory_management::JavaResult;
+use serde::{Deserialize, Serialize};
+use std::iter::Peekable;
+use typst::foundations::{NativeElement, StyleChain};
+use typst::model::HeadingElem;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeadingNode {
+    pub text: String,
+    pub level: i64,
+    /// Zero-based index of the page the heading is on.
+    pub page: i64,
+    pub span: ExtendedSpan,
+    /// The headings up to the next one of the same or a lower level.
+    pub children: Vec<HeadingNode>,
+}
+
+/// Lists the document's headings as a tree. A heading belongs to the closest
+/// preceding heading of a lower level, even if levels in between are skipped.
+/// With `structural`, show rules are skipped, see `compile_structure`, so
+/// pages may differ from the ones of the full document.
+#[no_mangle]
+pub extern "C" fn heading_tree(
+    world_ptr: *mut JavaWorld,
+    structural: i32, // 1 -- true, 0 -- false
+) -> JavaResult<ExtendedWarned<Result<Vec<HeadingNode>, Vec<ExtendedSourceDiagnostic>>>> {
+    let skip = structural == 1;
+    JavaResult::pack(compile_structure(world_ptr, skip, |world, document, _| {
+        let introspector = &document.introspector;
+        let mut headings = introspector
+            .query(&HeadingElem::elem().select())
+            .into_iter()
+            .filter_map(|elem| {
+                let heading = elem.to_packed::<HeadingElem>()?;
+                Some(HeadingNode {
+                    text: heading.body.plain_text().to_string(),
+                    level: heading.resolve_level(StyleChain::default()).get() as i64,
+                    page: introspector.page(elem.location()?).get() as i64 - 1,
+                    span: elem.span().resolve(world),
+                    children: vec![],
+                })
+            })
+            .peekable();
+        Ok(nest(&mut headings, 0))
+    }))
+}
+
+/// Takes the headings of a level above `level` until one at or below it comes
+/// and nests their successors into them.
+fn nest(
+    headings: &mut Peekable<impl Iterator<Item = HeadingNode>>,
+    level: i64,
+) -> Vec<HeadingNode> {
+    let mut nodes = vec![];
+    while let Some(mut node) = headings.next_if(|it| it.level > level) {
+        node.children = nest(headings, node.level);
+        nodes.push(node);
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, world};
+
+    #[test]
+    fn test_structural_outline_matches_the_full_one() {
+        let world = world(
+            "#show heading: it => it\n#show heading: set text(blue)\n\
+             = Intro\n== Scope\n#pagebreak()\n= Method\n=== Detail\n== Data",
+        );
+        let full = json(heading_tree(world, 0));
+        let structural = json(heading_tree(world, 1));
+        let outline = full["output"]["Ok"].as_array().unwrap();
+        assert_eq!(outline.len(), 2);
+        assert_eq!(outline[1]["text"], "Method");
+        assert_eq!(outline[1]["page"], 1);
+        // Skipped levels nest all the same.
+        assert_eq!(outline[1]["children"][0]["text"], "Detail");
+        assert_eq!(outline[1]["children"][1]["text"], "Data");
+        assert_eq!(structural["output"], full["output"]);
+        free_world(world);
+    }
+}
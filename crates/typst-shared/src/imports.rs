@@ -0,0 +1,111 @@
+use crate::exception::Except;
+use crate::extended_info::ExtendedFileDescriptor;
+use crate::java_world::JavaWorld;
+use crate::memory_management::{JavaResult, ThickBytePtr};
+use crate::throw;
+use std::mem;
+use typst::syntax::package::{PackageManifest, PackageSpec};
+use typst::syntax::{FileId, VirtualPath};
+use typst::utils::tick;
+use typst::World;
+
+/// Resolves the path of an `import` or `include` in the file `from` the way
+/// Typst does: relative paths are relative to `from`'s directory, absolute ones
+/// to the root of `from`'s project or package, and a package spec such as
+/// `@preview/z:1.0.0` refers to the entrypoint named in the package's manifest.
+/// Reading the manifest downloads the package if needed.
+#[no_mangle]
+pub extern "C" fn resolve_import(
+    world_ptr: *mut JavaWorld,
+    from: JavaResult<ExtendedFileDescriptor>,
+    import_path: ThickBytePtr,
+) -> JavaResult<Except<ExtendedFileDescriptor>> {
+    tick!();
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let path = import_path.to_str();
+    let result = from.try_unpack().and_then(|from| {
+        if path.starts_with('@') {
+            resolve_package(&world, &path)
+        } else {
+            Ok(FileId::from(from).join(&path))
+        }
+    });
+    let _ = Box::into_raw(world); // Not to drop the world!
+    mem::forget(path);
+    JavaResult::pack(result.map(Into::into))
+}
+
+/// Finds the entrypoint of the package, as in `typst_eval::import`.
+fn resolve_package(world: &JavaWorld, spec: &str) -> Except<FileId> {
+    let (manifest_id, manifest) = package_manifest(world, spec)?;
+    Ok(manifest_id.join(&manifest.package.entrypoint))
+}
+
+/// Reads and validates the manifest of the package, returning it together with
+/// its file.
+pub(crate) fn package_manifest(
+    world: &JavaWorld,
+    spec: &str,
+) -> Except<(FileId, PackageManifest)> {
+    let illegal = |message: String| {
+        throw!("java.lang.IllegalArgumentException".to_string(), Some(message))
+    };
+    let spec = spec.parse::<PackageSpec>().map_err(|err| illegal(err.to_string()))?;
+    let manifest_id = FileId::new(Some(spec.clone()), VirtualPath::new("typst.toml"));
+    let bytes = world.file(manifest_id).map_err(|err| {
+        throw!("java.io.IOException".to_string(), Some(err.to_string()))
+    })?;
+    let manifest: PackageManifest = bytes
+        .as_str()
+        .map_err(|err| err.to_string())
+        .and_then(|string| toml::from_str(string).map_err(|err| err.to_string()))
+        .map_err(|err| illegal(format!("package manifest is malformed ({err})")))?;
+    manifest.validate(&spec).map_err(|err| illegal(err.to_string()))?;
+    Ok((manifest_id, manifest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{java, json, thick, world};
+
+    fn resolve(world: *mut JavaWorld, from: FileId, path: &str) -> serde_json::Value {
+        let from = ExtendedFileDescriptor::from(from);
+        json(resolve_import(world, java(&from), thick(path)))
+    }
+
+    #[test]
+    fn test_resolve_relative_imports() {
+        let world = world("");
+        let from = FileId::new(None, VirtualPath::new("/dir/main.typ"));
+        assert_eq!(resolve(world, from, "./x.typ")["Ok"]["path"], "/dir/x.typ");
+        assert_eq!(resolve(world, from, "../y.typ")["Ok"]["path"], "/y.typ");
+        assert_eq!(resolve(world, from, "/z.typ")["Ok"]["path"], "/z.typ");
+        free_world(world);
+    }
+
+    #[test]
+    fn test_resolve_package_imports() {
+        let world = world("");
+        let spec: PackageSpec = "@preview/z:1.0.0".parse().unwrap();
+        let manifest = FileId::new(Some(spec.clone()), VirtualPath::new("typst.toml"));
+        unsafe { &*world }.virtual_files.lock().insert(
+            manifest,
+            b"[package]\nname = \"z\"\nversion = \"1.0.0\"\nentrypoint = \"src/lib.typ\"\n"
+                .to_vec(),
+        );
+        let from = FileId::new(None, VirtualPath::new("/main.typ"));
+        let result = resolve(world, from, "@preview/z:1.0.0");
+        assert_eq!(result["Ok"]["path"], "/src/lib.typ");
+        assert_eq!(result["Ok"]["pack"]["name"], "z");
+        // Within the package, paths stay in it.
+        let entrypoint = FileId::new(Some(spec), VirtualPath::new("/src/lib.typ"));
+        let result = resolve(world, entrypoint, "../util.typ");
+        assert_eq!(result["Ok"]["path"], "/util.typ");
+        assert_eq!(result["Ok"]["pack"]["name"], "z");
+        let result = resolve(world, from, "@preview/z");
+        assert_eq!(result["Err"]["class"], "java.lang.IllegalArgumentException");
+        free_world(world);
+    }
+}
@@ -2,36 +2,53 @@ use chrono::{DateTime, Datelike, FixedOffset, Local, TimeZone, Timelike, Utc};
 
 use crate::cache_cell::CacheCell;
 use crate::download;
-use crate::download::PrintDownload;
+use crate::exception::Except;
+use crate::download::{PackageConfig, PackageSource};
+use crate::draft::DraftLayout;
+use crate::font_index;
+use crate::font_index::{FontIndex, FontLoadStats};
 use crate::extended_info::{
-    ExtendedFileDescriptor, ExtendedFileResult,
+    ExtendedFileDescriptor, ExtendedFileResult, ExtendedSourceDiagnostic,
 };
 use crate::memory_management::{
-    Base16ByteArray, JavaExceptPtrResult, JavaResult, ThickBytePtr,
+    to_json, Base16ByteArray, JavaExceptPtrResult, JavaResult, ThickBytePtr,
 };
+use crate::page_size::PageSize;
+use crate::throw;
+use crate::watermark::Watermark;
 use parking_lot::Mutex;
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::mem;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
-use std::sync::OnceLock;
+use std::sync::{LazyLock, OnceLock};
+use std::time::Duration;
 use typst::diag::FileResult;
 use typst::foundations::{
-    Bytes, Datetime,
+    Bytes, Datetime, Dict, Value,
 };
-use typst::syntax::package::PackageSpec;
 use typst::syntax::{FileId, Source};
 use typst::text::{Font, FontBook};
 use typst::utils::{tick, LazyHash};
 use typst::{Library, World};
-use typst_kit::fonts::{FontSlot, Fonts};
+use typst_kit::fonts::FontSlot;
 use typst_kit::package::PackageStorage;
-use typst_library::diag::FileError;
+use typst_library::diag::{FileError, PackageError};
 
 pub type MainCallback = extern "C" fn() -> JavaResult<ExtendedFileDescriptor>;
 pub type FileCallback =
     extern "C" fn(ThickBytePtr) -> JavaResult<ExtendedFileResult<Base16ByteArray>>;
+/// Accepts a single warning, serialized as `ExtendedSourceDiagnostic`.
+pub type WarningCallback = extern "C" fn(ThickBytePtr);
+
+/// Passes a warning to a warning callback.
+pub fn report_warning(callback: WarningCallback, warning: &ExtendedSourceDiagnostic) {
+    let json: ThickBytePtr = to_json(warning).unwrap().into();
+    callback(json);
+    json.release();
+}
 
 /// JavaWorld keeps anything that is needed to impl World from java code with JNA.
 /// It is not directly representable with JNA, therefore no #[repr(C)],
@@ -52,11 +69,100 @@ pub struct JavaWorld {
     pub fonts: Vec<FontSlot>,
     /// File cache
     pub files: Mutex<HashMap<FileId, FileCache>>,
+    /// Contents of files that are served from memory instead of the java callback.
+    pub virtual_files: Mutex<HashMap<FileId, Vec<u8>>>,
     /// Now, handled as in SystemWorld
     pub now: Option<Now>,
     /// Package storage, handled as in SystemWorld
     pub package_storage: Option<PackageStorage>,
+    /// Network settings for package downloads
+    pub package_config: PackageConfig,
     pub auto_load_central: bool,
+    /// Whether query and eval results are serialized with sorted dictionary keys.
+    pub sort_keys: bool,
+    /// Replacements for link targets in HTML output, keyed by the original `href`.
+    pub link_map: HashMap<String, String>,
+    /// Stamped onto the pages exported by `compile_svg` and `compile_png`.
+    pub watermark: Option<Watermark>,
+    /// Overrides the page size of compiled documents, see `set_page_size`.
+    pub page_size: Option<PageSize>,
+    /// Simplifies the layout of compiled documents, see `set_draft_layout`.
+    pub draft: DraftLayout,
+    /// How many styles `apply_style_overrides` put onto the library's styles.
+    pub style_overrides: usize,
+    /// How the fonts were found.
+    pub font_stats: FontLoadStats,
+    /// How long a compilation may take, see `set_compile_timeout`.
+    pub timeout: Option<Duration>,
+    /// Receives the warnings of each compilation, see `set_warning_callback`.
+    pub warning_callback: Option<WarningCallback>,
+}
+
+/// Sources of downloaded packages, shared between all worlds.
+///
+/// Published package versions never change, so every world can be handed the very
+/// same `Source`. This skips reading and parsing them again for each new world and,
+/// since comemo memoizes module evaluation by the source's hash, lets a package
+/// imported by many documents be evaluated once for all of them.
+static SHARED_PACKAGE_SOURCES: LazyLock<Mutex<SharedSources>> =
+    LazyLock::new(|| Mutex::new(SharedSources::new(SHARED_SOURCES_LIMIT)));
+
+/// How many bytes of text the shared package sources may hold.
+const SHARED_SOURCES_LIMIT: usize = 64 * 1024 * 1024;
+
+#[no_mangle]
+pub extern "C" fn clear_shared_package_sources() {
+    SHARED_PACKAGE_SOURCES.lock().clear();
+}
+
+/// Sources bounded by the total size of their text. The least recently used
+/// ones are dropped first when the limit is exceeded.
+struct SharedSources {
+    sources: HashMap<FileId, (Source, u64)>,
+    /// Counts accesses, the second element of the entries is the last one.
+    clock: u64,
+    size: usize,
+    limit: usize,
+}
+
+impl SharedSources {
+    fn new(limit: usize) -> Self {
+        Self { sources: HashMap::new(), clock: 0, size: 0, limit }
+    }
+
+    fn get(&mut self, id: FileId) -> Option<Source> {
+        self.clock += 1;
+        let (source, used) = self.sources.get_mut(&id)?;
+        *used = self.clock;
+        Some(source.clone())
+    }
+
+    fn insert(&mut self, source: Source) {
+        self.clock += 1;
+        self.size += source.text().len();
+        if let Some((prev, _)) = self.sources.insert(source.id(), (source, self.clock)) {
+            self.size -= prev.text().len();
+        }
+        // The source just inserted is kept even if it alone exceeds the limit.
+        while self.size > self.limit && self.sources.len() > 1 {
+            let Some(oldest) = self
+                .sources
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(id, _)| *id)
+            else {
+                break;
+            };
+            if let Some((source, _)) = self.sources.remove(&oldest) {
+                self.size -= source.text().len();
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.sources.clear();
+        self.size = 0;
+    }
 }
 
 pub enum Now {
@@ -112,14 +218,24 @@ pub extern "C" fn new_world(
     file_callback: FileCallback,
     now: JavaResult<Option<Now>>,
     auto_load_central: i32, // 1 -- true, 0 -- false
+    package_config: JavaResult<PackageConfig>,
+    font_index: JavaResult<Option<FontIndex>>, // None -- search for fonts
 ) -> JavaExceptPtrResult<JavaWorld> {
     tick!();
     let library = unsafe { Box::from_raw(library) }.deref().clone();
     tick!();
 
-    let fonts = Fonts::searcher()
-        .include_system_fonts(true)
-        .search_with(&(vec![] as Vec<PathBuf>));
+    // Each value is unpacked even if another one fails, so that Java releases
+    // all of them.
+    let (now, package_config, font_index) =
+        (now.try_unpack(), package_config.try_unpack(), font_index.try_unpack());
+    let unpacked = now.and_then(|now| Ok((now, package_config?, font_index?)));
+    let (now, package_config, font_index) = match unpacked {
+        Ok(it) => it,
+        Err(err) => return JavaExceptPtrResult::pack(Err(err)),
+    };
+
+    let (fonts, font_stats) = font_index::load_fonts(font_index);
     tick!();
 
     let package_cache_path: Option<PathBuf> = None;
@@ -132,13 +248,24 @@ pub extern "C" fn new_world(
         file_callback,
         fonts: fonts.fonts,
         files: Mutex::new(HashMap::new()),
-        now: now.unpack().into(),
+        virtual_files: Mutex::new(HashMap::new()),
+        now: now.into(),
         package_storage: Some(PackageStorage::new(
             package_cache_path.clone(),
             package_path.clone(),
-            download::downloader(),
+            download::downloader(&package_config),
         )),
+        package_config,
         auto_load_central: auto_load_central == 1,
+        sort_keys: false,
+        link_map: HashMap::new(),
+        watermark: None,
+        page_size: None,
+        draft: DraftLayout::default(),
+        style_overrides: 0,
+        font_stats,
+        timeout: None,
+        warning_callback: None,
     };
     tick!();
     JavaExceptPtrResult::pack(Ok(Box::into_raw(Box::new(java_world))))
@@ -164,6 +291,47 @@ impl FileCache {
 }
 
 impl JavaWorld {
+    /// Replaces the styles set for the previous `page_size` and `draft` with
+    /// the ones for their current values.
+    pub fn apply_style_overrides(&mut self) {
+        let overrides = self
+            .page_size
+            .iter()
+            .flat_map(PageSize::styles)
+            .chain(self.draft.styles())
+            .collect::<Vec<_>>();
+        let styles = &mut self.library.styles;
+        for _ in 0..self.style_overrides {
+            styles.unset();
+        }
+        self.style_overrides = overrides.len();
+        for style in overrides {
+            styles.set(style);
+        }
+    }
+
+    /// Passes the warnings to the warning callback, if there is one.
+    pub fn report_warnings(&self, warnings: &[ExtendedSourceDiagnostic]) {
+        let Some(callback) = self.warning_callback else { return };
+        for warning in warnings {
+            report_warning(callback, warning);
+        }
+    }
+
+    /// The project's sources read since the last [`reset`](Self::reset),
+    /// sorted by path. Sources of packages are left out.
+    pub fn dependencies(&self) -> Vec<FileId> {
+        let mut ids = self
+            .files
+            .lock()
+            .values()
+            .filter(|it| it.source.accessed() && it.id.package().is_none())
+            .map(|it| it.id)
+            .collect::<Vec<_>>();
+        ids.sort_by(|a, b| a.vpath().cmp(b.vpath()));
+        ids
+    }
+
     fn cell<F, T>(&self, id: FileId, f: F) -> T
     where
         F: FnOnce(&mut FileCache) -> T,
@@ -182,40 +350,55 @@ impl JavaWorld {
     }
 
     pub fn obtain_file(&self, id: FileId) -> FileResult<Vec<u8>> {
-        let custom: bool;
-
-        if let Some(pack) = id.package() {
-            let PackageSpec { namespace, .. } = pack;
-            custom = !namespace.to_string().eq(&"preview".to_string());
-        } else {
-            custom = true
+        if let Some(data) = self.virtual_files.lock().get(&id) {
+            return Ok(data.clone());
         }
+        match self.package_source(id) {
+            PackageSource::Callback => {
+                let descriptor: ThickBytePtr =
+                    to_json(&ExtendedFileDescriptor::from(id)).unwrap().into();
+                tick!();
+                let jr = (self.file_callback)(descriptor);
+                tick!("{:?}", jr);
+                let result = jr
+                    .try_unpack()
+                    .map_err(|err| FileError::Other(err.message.map(Into::into)))
+                    .and_then(|it| it.map(|it| it.into()).map_err(|it| it.into()));
+                tick!();
+                descriptor.release();
+                tick!();
+                result
+            }
+            PackageSource::Local { path } => {
+                let spec = id.package().unwrap();
+                let root = Path::new(&path)
+                    .join(spec.name.as_str())
+                    .join(spec.version.to_string());
+                if !root.exists() {
+                    return Err(PackageError::NotFound(spec.clone()).into());
+                }
+                let path = id.vpath().resolve(&root).ok_or(FileError::AccessDenied);
+                read_from_disk(&path?)
+            }
+            PackageSource::Download => {
+                let spec = id.package().unwrap();
+                let storage = self.package_storage.as_ref().ok_or_else(|| {
+                    PackageError::Other(Some("package storage unavailable".into()))
+                })?;
+                let buf = download::prepare_package(storage, spec, &self.package_config)?;
+                let root = &buf;
+                let path = id.vpath().resolve(root).ok_or(FileError::AccessDenied);
+                read_from_disk(&path?)
+            }
+        }
+    }
 
-        if custom {
-            let descriptor: ThickBytePtr =
-                serde_json::to_string(&ExtendedFileDescriptor::from(id))
-                    .unwrap()
-                    .into();
-            tick!();
-            let jr = (self.file_callback)(descriptor);
-            tick!("{:?}", jr);
-            let result =
-                jr.unpack().map(|it| it.into()).map_err(|it| it.into());
-            tick!();
-            descriptor.release();
-            tick!();
-            result
-        } else {
-            let spec = id.package().unwrap();
-            let buf = self
-                .package_storage
-                .as_ref()
-                .unwrap()
-                .prepare_package(spec, &mut PrintDownload(&spec))?;
-            let root = &buf;
-            let path =
-                id.vpath().resolve(root).ok_or(FileError::AccessDenied);
-            read_from_disk(&path?)
+    /// Where the file comes from, according to the namespace of its package.
+    /// Files outside packages are requested from the java callback.
+    fn package_source(&self, id: FileId) -> PackageSource {
+        match id.package() {
+            Some(spec) => self.package_config.source(&spec.namespace),
+            None => PackageSource::Callback,
         }
     }
 }
@@ -227,6 +410,207 @@ pub extern "C" fn reset_world(world_ptr: *mut JavaWorld) {
     let _ = Box::into_raw(world); // Not to drop the world!
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// Files in the file cache, including ones not needed by the last
+    /// compilation.
+    pub files: i64,
+    /// Files accessed during the last compilation.
+    pub accessed_files: i64,
+    /// Text held by the cached sources.
+    pub source_bytes: i64,
+    /// Raw contents held by the cached files. Files loaded as sources are
+    /// usually held in both forms.
+    pub file_bytes: i64,
+    /// Contents of the files served from memory, see `eval_with_virtual_files`.
+    pub virtual_file_bytes: i64,
+    /// Fonts loaded into memory.
+    pub fonts_loaded: i64,
+    /// Fonts not loaded yet.
+    pub fonts_lazy: i64,
+}
+
+/// Estimates how much memory the world's caches hold, to decide when to reset
+/// or drop it. The sizes only count file contents, not the data structures
+/// built from them.
+#[no_mangle]
+pub extern "C" fn world_cache_stats(world_ptr: *mut JavaWorld) -> JavaResult<CacheStats> {
+    tick!();
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let files = world.files.lock();
+    let fonts_loaded = world.fonts.iter().filter(|slot| slot.is_loaded()).count();
+    let stats = CacheStats {
+        files: files.len() as i64,
+        accessed_files: files.values().filter(|it| it.accessed()).count() as i64,
+        source_bytes: files
+            .values()
+            .filter_map(|it| it.source.data())
+            .map(|source| source.text().len() as i64)
+            .sum(),
+        file_bytes: files
+            .values()
+            .filter_map(|it| it.file.data())
+            .map(|bytes| bytes.len() as i64)
+            .sum(),
+        virtual_file_bytes: world
+            .virtual_files
+            .lock()
+            .values()
+            .map(|data| data.len() as i64)
+            .sum(),
+        fonts_loaded: fonts_loaded as i64,
+        fonts_lazy: (world.fonts.len() - fonts_loaded) as i64,
+    };
+    drop(files);
+    let _ = Box::into_raw(world); // Not to drop the world!
+    JavaResult::pack(stats)
+}
+
+/// Replaces the world's `sys.inputs` with the given JSON object. The next
+/// compilation sees the new inputs; anything that depends on them is
+/// evaluated again.
+#[no_mangle]
+pub extern "C" fn set_inputs(
+    world_ptr: *mut JavaWorld,
+    inputs_json: ThickBytePtr,
+) -> JavaResult<Except<()>> {
+    tick!();
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    let inputs_str = inputs_json.to_str();
+    let result = serde_json::from_str::<Dict>(&inputs_str)
+        .map(|inputs| {
+            world.library.set_inputs(inputs);
+            world.reset();
+        })
+        .map_err(|err| {
+            throw!(
+                "java.lang.IllegalArgumentException".to_string(),
+                Some(format!("Malformed inputs: {err}"))
+            )
+        });
+    let _ = Box::into_raw(world); // Not to drop the world!
+    mem::forget(inputs_str);
+    JavaResult::pack(result)
+}
+
+/// Replaces the inputs the document reads by key with `sys.context-input`
+/// with the given JSON object, e.g. a dataset per chapter. Like `set_inputs`,
+/// the next compilation sees the new inputs, so the same sources may compile
+/// to different documents depending on what was set here.
+#[no_mangle]
+pub extern "C" fn set_context_inputs(
+    world_ptr: *mut JavaWorld,
+    inputs_json: ThickBytePtr,
+) -> JavaResult<Except<()>> {
+    tick!();
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    let inputs_str = inputs_json.to_str();
+    let result = serde_json::from_str::<Dict>(&inputs_str)
+        .map(|inputs| {
+            world.library.context_inputs = inputs;
+            world.reset();
+        })
+        .map_err(|err| {
+            throw!(
+                "java.lang.IllegalArgumentException".to_string(),
+                Some(format!("Malformed inputs: {err}"))
+            )
+        });
+    let _ = Box::into_raw(world); // Not to drop the world!
+    mem::forget(inputs_str);
+    JavaResult::pack(result)
+}
+
+/// Adds a binary input to `sys.inputs`, e.g. a dataset the document reads with
+/// `csv(sys.inputs.data)`. The buffer is kept in memory and never goes through
+/// the file callback. Replaces any input of the same name; `set_inputs` removes
+/// it again.
+#[no_mangle]
+pub extern "C" fn set_input_bytes(
+    world_ptr: *mut JavaWorld,
+    name: ThickBytePtr,
+    data: JavaResult<Base16ByteArray>,
+) -> JavaResult<Except<()>> {
+    tick!();
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    let name_str = name.to_str();
+    let result = data.try_unpack().map(|data| {
+        let mut inputs = world.library.inputs();
+        inputs.insert(name_str.as_str().into(), Value::Bytes(Bytes::new(data.0)));
+        world.library.set_inputs(inputs);
+        world.reset();
+    });
+    let _ = Box::into_raw(world); // Not to drop the world!
+    mem::forget(name_str);
+    JavaResult::pack(result)
+}
+
+/// Sets how links are rewritten in HTML output: the JSON object maps `href`s as
+/// Typst emits them to the ones to use instead, e.g. `"#intro"` to
+/// `"/docs/start#intro"`. Links to labels are emitted as `#label`. Links that are
+/// not in the map are kept.
+#[no_mangle]
+pub extern "C" fn set_link_map(
+    world_ptr: *mut JavaWorld,
+    map_json: ThickBytePtr,
+) -> JavaResult<Except<()>> {
+    tick!();
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    let map_str = map_json.to_str();
+    let result = serde_json::from_str::<HashMap<String, String>>(&map_str)
+        .map(|link_map| world.link_map = link_map)
+        .map_err(|err| {
+            throw!(
+                "java.lang.IllegalArgumentException".to_string(),
+                Some(format!("Malformed link map: {err}"))
+            )
+        });
+    let _ = Box::into_raw(world); // Not to drop the world!
+    mem::forget(map_str);
+    JavaResult::pack(result)
+}
+
+/// Makes query and eval results list dictionary keys in sorted rather than
+/// insertion order, so that equal dictionaries always serialize identically.
+/// See [`sort_keys`](crate::sort_keys::sort_keys).
+#[no_mangle]
+pub extern "C" fn set_sort_keys(
+    world_ptr: *mut JavaWorld,
+    sort_keys: i32, // 1 -- true, 0 -- false
+) {
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    world.sort_keys = sort_keys == 1;
+    let _ = Box::into_raw(world); // Not to drop the world!
+}
+
+/// Aborts compilations that take longer than `timeout_ms` milliseconds, which
+/// then fail with a "compilation timed out" error with the `timeout` code.
+/// Non-positive values remove the limit, which is the default.
+#[no_mangle]
+pub extern "C" fn set_compile_timeout(world_ptr: *mut JavaWorld, timeout_ms: i64) {
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    world.timeout = u64::try_from(timeout_ms)
+        .ok()
+        .filter(|&ms| ms > 0)
+        .map(Duration::from_millis);
+    let _ = Box::into_raw(world); // Not to drop the world!
+}
+
+/// Makes compilations hand their warnings to `callback` one by one while they
+/// run, as soon as each warning is final: the ones of evaluation before layout
+/// starts, the ones of layout once it converged, and the ones of the export at
+/// last. Each warning of the result is reported once, and the result still has
+/// all of them. `null` removes the callback, which is the default.
+#[no_mangle]
+pub extern "C" fn set_warning_callback(
+    world_ptr: *mut JavaWorld,
+    callback: Option<WarningCallback>,
+) {
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    world.warning_callback = callback;
+    let _ = Box::into_raw(world); // Not to drop the world!
+}
+
 impl World for JavaWorld {
     fn library(&self) -> &LazyHash<Library> {
         &self.library
@@ -243,7 +627,14 @@ impl World for JavaWorld {
     }
 
     fn source(&self, id: FileId) -> FileResult<Source> {
-        self.cell(id, |it| {
+        let shared = self.package_source(id) == PackageSource::Download
+            && !self.virtual_files.lock().contains_key(&id);
+        if shared {
+            if let Some(source) = SHARED_PACKAGE_SOURCES.lock().get(id) {
+                return Ok(source);
+            }
+        }
+        let result = self.cell(id, |it| {
             it.source.get_or_init(
                 || {
                     self.obtain_file(id)
@@ -258,7 +649,11 @@ impl World for JavaWorld {
                     }
                 },
             )
-        })
+        });
+        if let (true, Ok(source)) = (shared, &result) {
+            SHARED_PACKAGE_SOURCES.lock().insert(source.clone());
+        }
+        result
     }
 
     fn file(&self, id: FileId) -> FileResult<Bytes> {
@@ -316,3 +711,210 @@ fn read_from_disk(path: &Path) -> FileResult<Vec<u8>> {
         fs::read(path).map_err(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::{free_world, set_freer};
+    use crate::test_utils::{
+        add_file, java, json, main_file, no_file, raw, thick, world,
+    };
+    use typst::syntax::VirtualPath;
+
+    fn source(path: &str, text: &str) -> Source {
+        Source::new(FileId::new(None, VirtualPath::new(path)), text.into())
+    }
+
+    #[test]
+    fn test_shared_sources_drop_least_recently_used() {
+        let mut shared = SharedSources::new(10);
+        let a = source("a.typ", "aaaa");
+        let b = source("b.typ", "bbbb");
+        let c = source("c.typ", "cccc");
+        shared.insert(a.clone());
+        shared.insert(b.clone());
+        assert!(shared.get(a.id()).is_some());
+        shared.insert(c.clone());
+        assert!(shared.get(b.id()).is_none());
+        assert!(shared.get(a.id()).is_some());
+        assert!(shared.get(c.id()).is_some());
+        assert_eq!(shared.size, 8);
+    }
+
+    #[test]
+    fn test_shared_sources_keep_oversized_source() {
+        let mut shared = SharedSources::new(2);
+        let big = source("big.typ", "too large");
+        shared.insert(source("small.typ", "s"));
+        shared.insert(big.clone());
+        assert_eq!(shared.sources.len(), 1);
+        assert!(shared.get(big.id()).is_some());
+    }
+
+    static RELEASED: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+
+    extern "C" fn release(ticket: i64) {
+        RELEASED.lock().push(ticket);
+    }
+
+    /// A value that Java has to release.
+    fn value<T>(ticket: i64, json: &str) -> JavaResult<T> {
+        JavaResult { ticket, ..raw(json) }
+    }
+
+    #[test]
+    fn test_new_world_releases_all_values_on_failure() {
+        json(set_freer(release));
+        let library = Box::into_raw(Box::new(Library::builder().build()));
+        let result = new_world(
+            library,
+            main_file,
+            no_file,
+            value(101, "[1, 2"),
+            0,
+            value(102, "{}"),
+            value(103, "null"),
+        );
+        assert!(result.ptr.is_null());
+        let err: serde_json::Value =
+            serde_json::from_str(&result.comment.to_str()).unwrap();
+        assert_eq!(err["class"], "java.lang.IllegalArgumentException");
+        let released = RELEASED.lock();
+        assert!([101, 102, 103].iter().all(|ticket| released.contains(ticket)));
+    }
+
+    #[test]
+    fn test_set_input_bytes_rejects_malformed_data() {
+        let world = world("");
+        let result = json(set_input_bytes(world, thick("data"), raw("\"zz\"")));
+        assert_eq!(result["Err"]["class"], "java.lang.IllegalArgumentException");
+        free_world(world);
+    }
+
+    #[test]
+    fn test_set_input_bytes_is_read_by_the_document() {
+        let world = world("#metadata(csv(sys.inputs.data)) <data>");
+        let data = java(&Base16ByteArray(b"a,b\n1,2".to_vec()));
+        let result = json(set_input_bytes(world, thick("data"), data));
+        assert_eq!(result, serde_json::json!({"Ok": null}));
+        let result = json(crate::query::query(world, thick("<data>"), 0, 0));
+        let output = &result["Ok"]["output"]["Ok"];
+        let matches: serde_json::Value =
+            serde_json::from_str(output["result"].as_str().unwrap()).unwrap();
+        assert_eq!(matches[0]["value"], serde_json::json!([["a", "b"], ["1", "2"]]));
+        free_world(world);
+    }
+
+    #[test]
+    fn test_cache_stats_count_the_accessed_files() {
+        let main = "#include \"chapter.typ\"\n#read(\"data.txt\")";
+        let world = world(main);
+        add_file(world, "/chapter.typ", "Chapter");
+        add_file(world, "/data.txt", "Data");
+        json(crate::compile::compile_png(world, 0, 1, 1.0, 0));
+        let stats = json(world_cache_stats(world));
+        assert_eq!(stats["files"], 3);
+        assert_eq!(stats["accessed_files"], 3);
+        assert_eq!(stats["source_bytes"], main.len() + "Chapter".len());
+        assert_eq!(stats["file_bytes"], "Data".len());
+        let total = main.len() + "Chapter".len() + "Data".len();
+        assert_eq!(stats["virtual_file_bytes"], total);
+        reset_world(world);
+        let stats = json(world_cache_stats(world));
+        assert_eq!(stats["files"], 3);
+        assert_eq!(stats["accessed_files"], 0);
+        free_world(world);
+    }
+
+    #[test]
+    fn test_local_namespace_never_downloads() {
+        let dir = std::env::temp_dir()
+            .join(format!("typst-shared-company-{}", std::process::id()));
+        let package = dir.join("lib/0.1.0");
+        std::fs::create_dir_all(&package).unwrap();
+        std::fs::write(
+            package.join("typst.toml"),
+            "[package]\nname = \"lib\"\nversion = \"0.1.0\"\nentrypoint = \"lib.typ\"",
+        )
+        .unwrap();
+        std::fs::write(package.join("lib.typ"), "#let greeting = [Local]").unwrap();
+
+        let world = world("#import \"@company/lib:0.1.0\": greeting\n#greeting");
+        {
+            let world = unsafe { &mut *world };
+            let local = PackageSource::Local { path: dir.to_string_lossy().into() };
+            world.package_config.namespaces.insert("company".into(), local);
+            // Without a storage, a download fails, and so does the file callback
+            // of the test world.
+            world.package_storage = None;
+        }
+        let result = json(crate::compile::compile_png(world, 0, 1, 1.0, 0));
+        assert_eq!(result["output"]["Ok"].as_array().unwrap().len(), 1);
+        // The default routes are kept for the other namespaces.
+        let config = unsafe { &(*world).package_config };
+        assert_eq!(config.source("preview"), PackageSource::Download);
+        assert_eq!(config.source("local"), PackageSource::Callback);
+        free_world(world);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_download_without_package_storage_fails() {
+        let world = world("#import \"@preview/no-storage:0.1.0\": item");
+        unsafe { (*world).package_storage = None };
+        let spec = "@preview/no-storage:0.1.0".parse().unwrap();
+        let id = FileId::new(Some(spec), VirtualPath::new("/lib.typ"));
+        let error = unsafe { &*world }.source(id).unwrap_err();
+        let unavailable = PackageError::Other(Some("package storage unavailable".into()));
+        assert_eq!(error, FileError::Package(unavailable));
+        // A compilation reports it as an error instead of crashing.
+        let result = json(crate::compile::compile_png(world, 0, 1, 1.0, 0));
+        let errors = result["output"]["Err"].as_array().unwrap();
+        assert_eq!(
+            errors[0]["message"],
+            "failed to load package (package storage unavailable)"
+        );
+        free_world(world);
+    }
+
+    #[test]
+    fn test_set_inputs_between_compilations() {
+        let world = world("#metadata(sys.inputs.build) <build>");
+        for build in ["1", "2"] {
+            let inputs = format!(r#"{{"build": "{build}"}}"#);
+            let result = json(set_inputs(world, thick(&inputs)));
+            assert_eq!(result, serde_json::json!({"Ok": null}));
+            let result = json(crate::query::query(world, thick("<build>"), 0, 0));
+            let output = &result["Ok"]["output"]["Ok"];
+            let matches: serde_json::Value =
+                serde_json::from_str(output["result"].as_str().unwrap()).unwrap();
+            assert_eq!(matches[0]["value"], build);
+        }
+        free_world(world);
+    }
+
+    #[test]
+    fn test_context_inputs_are_read_by_key() {
+        let world = world(
+            "#metadata(sys.context-input(\"chapter-1\"))\n\
+             #metadata(sys.context-input(\"chapter-2\"))\n\
+             #metadata(sys.context-input(\"chapter-3\", default: 0))",
+        );
+        let values = || {
+            let result =
+                json(crate::metadata::list_metadata(world, java(&None::<String>)));
+            let entries = result["Ok"]["output"]["Ok"].as_array().unwrap().clone();
+            entries.into_iter().map(|it| it["value"].clone()).collect::<Vec<_>>()
+        };
+        let inputs = r#"{"chapter-1": {"rows": 3}, "chapter-2": "b"}"#;
+        let result = json(set_context_inputs(world, thick(inputs)));
+        assert_eq!(result, serde_json::json!({"Ok": null}));
+        assert_eq!(values(), [serde_json::json!({"rows": 3}), "b".into(), 0.into()]);
+        // The next compilation sees the new inputs.
+        let result = json(set_context_inputs(world, thick(r#"{"chapter-2": "c"}"#)));
+        assert_eq!(result, serde_json::json!({"Ok": null}));
+        let none = serde_json::Value::Null;
+        assert_eq!(values(), [none, "c".into(), 0.into()]);
+        free_world(world);
+    }
+}
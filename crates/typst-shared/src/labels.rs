@@ -0,0 +1,182 @@
+use crate::compile::{
+    compile_partial_reporting, compile_structure, run_compilation, PartialOutput,
+};
+use crate::exception::Except;
+use crate::extended_info::{
+    ExtendedSourceDiagnostic, ExtendedSpan, ExtendedWarned, Resolve,
+};
+use crate::java_world::JavaWorld;
+use crate::links::linked_labels;
+use crate::memory_management::{evict_after_compile, JavaResult};
+use serde::{Deserialize, Serialize};
+use typst::comemo::Track;
+use typst::diag::Warned;
+use typst::foundations::Selector;
+use typst::layout::PagedDocument;
+use typst::model::{BibliographyElem, RefElem};
+use typst::utils::tick;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefInfo {
+    /// The referenced label's name, without angle brackets.
+    pub label: String,
+    pub span: ExtendedSpan,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelInfo {
+    /// The label's name, without angle brackets.
+    pub label: String,
+    /// Zero-based index of the page the labelled element is on.
+    pub page: i64,
+    pub span: ExtendedSpan,
+}
+
+/// Lists the labels that are attached to an element, but that no reference
+/// (`@label`) or link (`#link(<label>)`) points to. Labels in `ignored`, given
+/// without angle brackets, are left out, e.g. ones that are only queried.
+/// Each label is listed once, at its first element. With `structural`, show
+/// rules are skipped, see `compile_structure`, so pages may differ from the
+/// ones of the full document.
+#[no_mangle]
+pub extern "C" fn unused_labels(
+    world_ptr: *mut JavaWorld,
+    ignored: JavaResult<Vec<String>>,
+    structural: i32, // 1 -- true, 0 -- false
+) -> JavaResult<
+    Except<ExtendedWarned<Result<Vec<LabelInfo>, Vec<ExtendedSourceDiagnostic>>>>,
+> {
+    let ignored = match ignored.try_unpack() {
+        Ok(ignored) => ignored,
+        Err(err) => return JavaResult::pack(Err(err)),
+    };
+    let skip = structural == 1;
+    JavaResult::pack(Ok(compile_structure(world_ptr, skip, |world, document, _| {
+        let introspector = &document.introspector;
+        let mut used = linked_labels(world, &document);
+        used.extend(
+            introspector
+                .all()
+                .filter_map(|elem| Some(elem.to_packed::<RefElem>()?.target)),
+        );
+        Ok(introspector
+            .all()
+            .filter_map(|elem| {
+                let label = elem.label()?;
+                let name = label.resolve().as_str().to_string();
+                // Inserting marks the label as listed, for its other elements.
+                if ignored.contains(&name) || !used.insert(label) {
+                    return None;
+                }
+                Some(LabelInfo {
+                    label: name,
+                    page: introspector.page(elem.location()?).get() as i64 - 1,
+                    span: elem.span().resolve(world),
+                })
+            })
+            .collect())
+    })))
+}
+
+/// Lists the references (`@label` or `#ref(<label>)`) to labels that are
+/// neither attached to an element nor keys of a bibliography. Compilation fails
+/// at these, so the document is compiled partially to collect all of them at
+/// once. Their errors are left out of the other errors; the references are
+/// missing only if compilation fails fatally.
+#[no_mangle]
+pub extern "C" fn dangling_refs(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<PartialOutput<Vec<RefInfo>>> {
+    tick!();
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let Warned { output: (document, mut errors), warnings } =
+        run_compilation(world.timeout, || {
+            compile_partial_reporting::<PagedDocument>(
+                world.as_ref(),
+                world.warning_callback,
+            )
+        });
+    evict_after_compile();
+    let dangling = document.map(|document| {
+        let introspector = &document.introspector;
+        let keys = BibliographyElem::keys(introspector.track());
+        introspector
+            .all()
+            .filter_map(|elem| {
+                let target = elem.to_packed::<RefElem>()?.target;
+                let defined = !introspector.query(&Selector::Label(target)).is_empty()
+                    || keys.iter().any(|(key, _)| *key == target);
+                (!defined).then(|| (target, elem.span()))
+            })
+            .collect::<Vec<_>>()
+    });
+    if let Some(dangling) = &dangling {
+        errors.retain(|error| dangling.iter().all(|(_, span)| *span != error.span));
+    }
+    let result = PartialOutput {
+        output: dangling.map(|dangling| {
+            dangling
+                .into_iter()
+                .map(|(target, span)| RefInfo {
+                    label: target.resolve().as_str().to_string(),
+                    span: span.resolve(world.as_ref()),
+                })
+                .collect()
+        }),
+        errors: errors.resolve(world.as_ref()),
+        warnings: warnings.resolve(world.as_ref()),
+    };
+    let _ = Box::into_raw(world); // Not to drop the world!
+    JavaResult::pack(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{java, json, world};
+
+    /// The names of the unused labels.
+    fn unused(world: *mut JavaWorld, ignored: &[&str], structural: i32) -> Vec<String> {
+        let ignored = ignored.iter().map(|it| it.to_string()).collect::<Vec<_>>();
+        let result = json(unused_labels(world, java(&ignored), structural));
+        let labels = result["Ok"]["output"]["Ok"].as_array().unwrap();
+        labels
+            .iter()
+            .map(|it| it["label"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_unused_labels_leave_out_referenced_ones() {
+        let world = world(
+            "#set heading(numbering: \"1.\")\n= Intro <intro>\n= Orphan <orphan>\n\
+             #metadata(1) <queried>\nSee @intro.",
+        );
+        assert_eq!(unused(world, &["queried"], 0), ["orphan"]);
+        assert_eq!(unused(world, &["queried"], 1), ["orphan"]);
+        assert_eq!(unused(world, &[], 0), ["orphan", "queried"]);
+        let result = json(unused_labels(world, java(&Vec::<String>::new()), 0));
+        let orphan = &result["Ok"]["output"]["Ok"][0];
+        assert_eq!(orphan["page"], 0);
+        assert!(!orphan["span"]["file"].is_null());
+        free_world(world);
+    }
+
+    #[test]
+    fn test_dangling_refs_skips_resolved_references() {
+        let text =
+            "#set heading(numbering: \"1.\")\n= Intro <intro>\nSee @intro, @missing";
+        let world = world(text);
+        let result = json(dangling_refs(world));
+        let dangling = result["output"].as_array().unwrap();
+        assert_eq!(dangling.len(), 1);
+        assert_eq!(dangling[0]["label"], "missing");
+        let start = text.find("@missing").unwrap();
+        assert_eq!(dangling[0]["span"]["start_ind"], start);
+        assert_eq!(dangling[0]["span"]["end_ind"], start + "@missing".len());
+        // Its error is reported as the reference instead.
+        assert!(result["errors"].as_array().unwrap().is_empty());
+        free_world(world);
+    }
+}
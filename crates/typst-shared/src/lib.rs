@@ -14,3 +14,37 @@ pub mod syntax;
 pub mod fmt;
 pub mod download;
 pub mod terminal;
+pub mod memory_world;
+pub mod overlay_world;
+pub mod self_test;
+pub mod package_cache;
+pub mod frames;
+pub mod pdf;
+pub mod font_index;
+pub mod sort_keys;
+pub mod compiled_doc;
+pub mod text;
+pub mod math_glyphs;
+pub mod headings;
+pub mod diagnostics;
+pub mod syntax_diff;
+pub mod watermark;
+pub mod page_size;
+pub mod math_export;
+pub mod imports;
+pub mod links;
+pub mod edits;
+pub mod resolved_style;
+pub mod document_info;
+pub mod render_diff;
+pub mod tables;
+pub mod draft;
+pub mod alt_text;
+pub mod labels;
+pub mod counters;
+pub mod metadata;
+pub mod footnotes;
+pub mod compat;
+
+#[cfg(test)]
+mod test_utils;
@@ -0,0 +1,249 @@
+use crate::compile::compile_paged;
+use crate::extended_info::{
+    ExtendedSourceDiagnostic, ExtendedSpan, ExtendedWarned, Resolve,
+};
+use crate::java_world::JavaWorld;
+use crate::memory_management::JavaResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use typst::foundations::{Label, Selector};
+use typst::introspection::{Introspector, Location};
+use typst::layout::{Frame, FrameItem, PagedDocument};
+use typst::model::Destination;
+use typst::syntax::{ast, Span, SyntaxKind};
+use typst::World;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkInfo {
+    pub target: LinkDestination,
+    /// The text of the link.
+    pub text: String,
+    /// Zero-based index of the page the link is on.
+    pub page: i64,
+    pub span: ExtendedSpan,
+}
+
+/// Where a link points to. Pages are zero-based; `None` for internal links
+/// whose target is not in the document.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum LinkDestination {
+    /// An external link.
+    Url { url: String },
+    /// A link to a labelled element, e.g. `#link(<intro>)` or `@intro`.
+    Label { label: String, page: Option<i64> },
+    /// A link to an element's location, e.g. `#link(here())`.
+    Location { page: Option<i64> },
+    /// A link to a point on a page, in points from the page's top left.
+    Position { page: i64, x: f64, y: f64 },
+}
+
+/// Lists the document's links in order, external as well as internal ones,
+/// like references and outline entries.
+///
+/// Links are found in the laid out pages, so the ones in hidden content are
+/// not listed. A link broken across lines is listed once, with its parts'
+/// text joined by spaces.
+#[no_mangle]
+pub extern "C" fn list_links(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<ExtendedWarned<Result<Vec<LinkInfo>, Vec<ExtendedSourceDiagnostic>>>> {
+    JavaResult::pack(compile_paged(world_ptr, |world, document, _| {
+        let mut collector = Collector {
+            world,
+            introspector: &document.introspector,
+            links: vec![],
+            last: None,
+        };
+        for (i, page) in document.pages.iter().enumerate() {
+            for_each_link(&page.frame, &mut |dest, frame| collector.link(dest, frame, i));
+        }
+        Ok(collector.links)
+    }))
+}
+
+/// Collects the links of the frames in order.
+struct Collector<'a> {
+    world: &'a JavaWorld,
+    introspector: &'a Introspector,
+    links: Vec<LinkInfo>,
+    /// The destination and origin of the last link, to join its parts.
+    last: Option<(Destination, Span)>,
+}
+
+impl Collector<'_> {
+    fn link(&mut self, dest: &Destination, frame: &Frame, page: usize) {
+        let mut text = String::new();
+        let mut span = Span::detached();
+        collect_text(frame, &mut text, &mut span);
+        let origin = link_origin(self.world, span).map_or(span, |(origin, _)| origin);
+
+        if let (Some((last, last_origin)), Some(info)) =
+            (&self.last, self.links.last_mut())
+        {
+            if last == dest && *last_origin == origin && !origin.is_detached() {
+                info.text.push(' ');
+                info.text.push_str(&text);
+                return;
+            }
+        }
+
+        self.links.push(LinkInfo {
+            target: destination(self.introspector, dest),
+            text,
+            page: page as i64,
+            span: origin.resolve(self.world),
+        });
+        self.last = Some((dest.clone(), origin));
+    }
+}
+
+/// The labels of the elements that `link` calls point to, e.g. `#link(<intro>)`.
+/// Links without text are missed, since the call is found by its text's span.
+pub(crate) fn linked_labels(
+    world: &JavaWorld,
+    document: &PagedDocument,
+) -> HashSet<Label> {
+    let introspector = &document.introspector;
+    let mut labels = HashSet::new();
+    for page in &document.pages {
+        for_each_link(&page.frame, &mut |dest, frame| {
+            let Destination::Location(loc) = dest else { return };
+            let mut span = Span::detached();
+            collect_text(frame, &mut String::new(), &mut span);
+            if !matches!(link_origin(world, span), Some((_, SyntaxKind::FuncCall))) {
+                return;
+            }
+            if let Some(label) = introspector
+                .query_first(&Selector::Location(*loc))
+                .and_then(|elem| elem.label())
+            {
+                labels.insert(label);
+            }
+        });
+    }
+    labels
+}
+
+/// Calls `f` with each link in the frame and the frame it covers, in order.
+fn for_each_link(frame: &Frame, f: &mut impl FnMut(&Destination, &Frame)) {
+    for (_, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => for_each_link(&group.frame, f),
+            // The link covers the frame it is in, so its text is the one of
+            // that frame.
+            FrameItem::Link(dest, _) => f(dest, frame),
+            _ => {}
+        }
+    }
+}
+
+/// Appends the text of the frame to `text`, and sets `span` to the one of its
+/// first glyph from a source file.
+fn collect_text(frame: &Frame, text: &mut String, span: &mut Span) {
+    for (_, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => collect_text(&group.frame, text, span),
+            FrameItem::Text(item) => {
+                text.push_str(&item.text);
+                if span.is_detached() {
+                    if let Some(glyph) =
+                        item.glyphs.iter().find(|glyph| !glyph.span.0.is_detached())
+                    {
+                        *span = glyph.span.0;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The span and kind of the `link` call, automatic link or reference the text at
+/// `span` is part of, if any.
+fn link_origin(world: &JavaWorld, span: Span) -> Option<(Span, SyntaxKind)> {
+    let source = world.source(span.id()?).ok()?;
+    let mut node = source.find(span)?;
+    loop {
+        match node.kind() {
+            SyntaxKind::Link | SyntaxKind::Ref => {
+                return Some((node.span(), node.kind()))
+            }
+            SyntaxKind::FuncCall => {
+                let callee = node.cast::<ast::FuncCall>().map(|call| call.callee());
+                if let Some(ast::Expr::Ident(ident)) = callee {
+                    if ident.as_str() == "link" {
+                        return Some((node.span(), node.kind()));
+                    }
+                }
+            }
+            _ => {}
+        }
+        node = node.parent()?.clone();
+    }
+}
+
+fn destination(introspector: &Introspector, dest: &Destination) -> LinkDestination {
+    match dest {
+        Destination::Url(url) => LinkDestination::Url { url: url.to_string() },
+        Destination::Position(pos) => LinkDestination::Position {
+            page: pos.page.get() as i64 - 1,
+            x: pos.point.x.to_pt(),
+            y: pos.point.y.to_pt(),
+        },
+        Destination::Location(loc) => {
+            let page = page(introspector, *loc);
+            match introspector
+                .query_first(&Selector::Location(*loc))
+                .and_then(|elem| elem.label())
+            {
+                Some(label) => LinkDestination::Label {
+                    label: label.resolve().as_str().to_string(),
+                    page,
+                },
+                None => LinkDestination::Location { page },
+            }
+        }
+    }
+}
+
+fn page(introspector: &Introspector, loc: Location) -> Option<i64> {
+    introspector
+        .query_first(&Selector::Location(loc))
+        .map(|_| introspector.page(loc).get() as i64 - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, world};
+    use serde_json::json;
+
+    #[test]
+    fn test_list_links() {
+        let main = "#set heading(numbering: \"1.\")\n\
+                    = Intro <intro>\n\
+                    #link(\"https://typst.app\")[Typst] and #link(<intro>)[back] and @intro\n\
+                    #pagebreak()\n\
+                    https://example.com";
+        let world = world(main);
+        let links = json(list_links(world));
+        let links = links["output"]["Ok"].as_array().unwrap();
+        let targets: Vec<_> = links.iter().map(|link| link["target"].clone()).collect();
+        assert_eq!(
+            targets,
+            vec![
+                json!({ "type": "Url", "url": "https://typst.app" }),
+                json!({ "type": "Label", "label": "intro", "page": 0 }),
+                json!({ "type": "Label", "label": "intro", "page": 0 }),
+                json!({ "type": "Url", "url": "https://example.com" }),
+            ]
+        );
+        assert_eq!(links[0]["text"], "Typst");
+        assert_eq!(links[1]["text"], "back");
+        assert_eq!(links[3]["page"], 1);
+        assert_eq!(links[0]["span"]["start_ind"], main.find("link(").unwrap());
+        free_world(world);
+    }
+}
@@ -18,19 +18,53 @@ use typst_library::routines::EvalMode;
 use typst_library::{Library, World};
 use typst_library::text::{Font, FontBook};
 
+pub mod alt_text;
 pub mod cache_cell;
+pub mod compat;
 pub mod compile;
+pub mod compiled_doc;
+pub mod counters;
 pub mod detached_eval;
+pub mod diagnostics;
+pub mod document_info;
 pub mod download;
+pub mod draft;
+pub mod edits;
 pub mod exception;
 pub mod extended_info;
 pub mod fmt;
+pub mod font_index;
+pub mod footnotes;
+pub mod frames;
+pub mod headings;
+pub mod imports;
 pub mod java_world;
+pub mod labels;
+pub mod links;
+pub mod math_export;
+pub mod math_glyphs;
 pub mod memory_management;
+pub mod memory_world;
+pub mod overlay_world;
+pub mod metadata;
+pub mod package_cache;
+pub mod page_size;
+pub mod pdf;
 pub mod query;
+pub mod render_diff;
+pub mod resolved_style;
+pub mod self_test;
+pub mod sort_keys;
 pub mod stdlib;
 pub mod syntax;
+pub mod syntax_diff;
+pub mod tables;
 pub mod terminal;
+pub mod text;
+pub mod watermark;
+
+#[cfg(test)]
+mod test_utils;
 
 // pub extern "C" fn main_nop() -> JavaResult<ExtendedFileDescriptor> {
 //     panic!()
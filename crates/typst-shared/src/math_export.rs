@@ -0,0 +1,634 @@
+use crate::exception::Except;
+use crate::memory_management::{JavaResult, ThickBytePtr};
+use crate::throw;
+use std::fmt::Write;
+use std::mem;
+use std::sync::LazyLock;
+use typst::foundations::{Module, Value};
+use typst::syntax::ast::{self, AstNode, Expr, MathTextKind};
+use typst::syntax::parse_math;
+use typst::utils::tick;
+
+/// The math module, to resolve symbols such as `pi` or `arrow.r`.
+static MATH: LazyLock<Module> = LazyLock::new(typst::math::module);
+
+/// Converts a math expression, without the enclosing dollars, to a `<math>`
+/// element. See [`Node`] for what is supported.
+#[no_mangle]
+pub extern "C" fn math_to_mathml(source_ptr: ThickBytePtr) -> JavaResult<Except<String>> {
+    tick!();
+    let source = source_ptr.to_str();
+    let result = convert(&source).map(|node| {
+        let mut out =
+            String::from(r#"<math xmlns="http://www.w3.org/1998/Math/MathML">"#);
+        node.write_mathml(&mut out);
+        out.push_str("</math>");
+        out
+    });
+    mem::forget(source);
+    JavaResult::pack(result)
+}
+
+/// Converts a math expression, without the enclosing dollars, to LaTeX math
+/// mode, also without delimiters. Symbols without a common LaTeX command are
+/// kept as Unicode characters, as `unicode-math` understands them.
+#[no_mangle]
+pub extern "C" fn math_to_latex(source_ptr: ThickBytePtr) -> JavaResult<Except<String>> {
+    tick!();
+    let source = source_ptr.to_str();
+    let result = convert(&source).map(|node| {
+        let mut out = String::new();
+        node.write_latex(&mut out);
+        out.trim().to_string()
+    });
+    mem::forget(source);
+    JavaResult::pack(result)
+}
+
+fn convert(source: &str) -> Except<Node> {
+    let root = parse_math(source);
+    if let Some(error) = root.errors().into_iter().next() {
+        return Err(throw!(
+            "java.lang.IllegalArgumentException".to_string(),
+            Some(error.message.to_string())
+        ));
+    }
+    let math = root.cast::<ast::Math>().expect("math is parsed into a math node");
+    Ok(row(math))
+}
+
+/// The structure of a formula, as far as it can be told from the syntax alone.
+///
+/// Supported are text, numbers, symbols and shorthands, fractions, roots,
+/// attachments and primes, matched delimiters, strings and calls to `frac`,
+/// `sqrt`, `root`, `binom`, `abs`, `norm`, `floor`, `ceil` and `vec`. Other calls
+/// are written as the function's name followed by the arguments in
+/// parentheses, which is right for `f(x)` but not for, e.g., `mat` or `cases`.
+/// Named arguments are ignored. Embedded code such as `#x` is unsupported and
+/// becomes `<merror>` or `\text{..}` with the source text. Alignment points and
+/// line breaks have no MathML counterpart outside of tables and are dropped.
+enum Node {
+    Row(Vec<Node>),
+    /// A variable or a multi-letter identifier.
+    Ident(String),
+    Number(String),
+    Op(String),
+    /// An operator written as a word, e.g. `sin`.
+    OpName(String),
+    Text(String),
+    Frac(Box<Node>, Box<Node>),
+    Binom(Box<Node>, Box<Node>),
+    Root(Option<Box<Node>>, Box<Node>),
+    Attach {
+        base: Box<Node>,
+        bottom: Option<Box<Node>>,
+        top: Option<Box<Node>>,
+    },
+    Fenced {
+        open: char,
+        close: char,
+        body: Box<Node>,
+    },
+    Vector(Vec<Node>),
+    Space,
+    Linebreak,
+    AlignPoint,
+    Unsupported(String),
+}
+
+fn row(math: ast::Math) -> Node {
+    Node::Row(math.exprs().map(node).collect())
+}
+
+fn node(expr: Expr) -> Node {
+    match expr {
+        // The parser turns parentheses that only group the parts of fractions,
+        // attachments and roots, as in `(a+b)/c`, into plain math. They aren't
+        // expressions, so they are dropped here, like Typst doesn't show them.
+        Expr::Math(math) => row(math),
+        Expr::MathText(text) => match text.get() {
+            MathTextKind::Character(c) => char_node(c),
+            MathTextKind::Number(number) => Node::Number(number.to_string()),
+        },
+        Expr::MathIdent(ident) => ident_node(&ident),
+        Expr::MathShorthand(shorthand) => char_node(shorthand.get()),
+        Expr::Escape(escape) => char_node(escape.get()),
+        Expr::FieldAccess(access) => match resolve_symbol(Expr::FieldAccess(access)) {
+            Some(c) => char_node(c),
+            None => unsupported(access.to_untyped().clone().into_text().as_str()),
+        },
+        Expr::Str(string) => Node::Text(string.get().to_string()),
+        Expr::MathAlignPoint(_) => Node::AlignPoint,
+        Expr::Linebreak(_) => Node::Linebreak,
+        Expr::Space(_) => Node::Space,
+        Expr::MathPrimes(primes) => primes_node(primes),
+        Expr::MathFrac(frac) => {
+            Node::Frac(Box::new(node(frac.num())), Box::new(node(frac.denom())))
+        }
+        Expr::MathRoot(root) => Node::Root(
+            root.index().map(|index| Box::new(Node::Number(index.to_string()))),
+            Box::new(node(root.radicand())),
+        ),
+        Expr::MathAttach(attach) => {
+            let top = match (attach.primes(), attach.top()) {
+                (Some(primes), Some(top)) => {
+                    Some(Node::Row(vec![primes_node(primes), node(top)]))
+                }
+                (Some(primes), None) => Some(primes_node(primes)),
+                (None, top) => top.map(node),
+            };
+            Node::Attach {
+                base: Box::new(node(attach.base())),
+                bottom: attach.bottom().map(|it| Box::new(node(it))),
+                top: top.map(Box::new),
+            }
+        }
+        Expr::MathDelimited(delimited) => {
+            match (delimiter(delimited.open()), delimiter(delimited.close())) {
+                (Some(open), Some(close)) => {
+                    Node::Fenced { open, close, body: Box::new(row(delimited.body())) }
+                }
+                _ => unsupported(delimited.to_untyped().clone().into_text().as_str()),
+            }
+        }
+        Expr::FuncCall(call) => call_node(call),
+        other => unsupported(other.to_untyped().clone().into_text().as_str()),
+    }
+}
+
+fn delimiter(expr: Expr) -> Option<char> {
+    match node(expr) {
+        Node::Op(op) | Node::Ident(op) => {
+            let mut chars = op.chars();
+            chars.next().filter(|_| chars.next().is_none())
+        }
+        _ => None,
+    }
+}
+
+fn primes_node(primes: ast::MathPrimes) -> Node {
+    Node::Op("′".repeat(primes.count()))
+}
+
+fn char_node(c: char) -> Node {
+    if c.is_alphabetic() {
+        Node::Ident(c.to_string())
+    } else if c.is_numeric() {
+        Node::Number(c.to_string())
+    } else {
+        Node::Op(c.to_string())
+    }
+}
+
+fn ident_node(ident: &str) -> Node {
+    match MATH.scope().get(ident).map(|it| it.read()) {
+        Some(Value::Symbol(symbol)) => char_node(symbol.get()),
+        Some(Value::Content(_)) => Node::OpName(ident.to_string()),
+        _ => Node::Ident(ident.to_string()),
+    }
+}
+
+/// Resolves `pi` or `arrow.r.long` to its character.
+fn resolve_symbol(expr: Expr) -> Option<char> {
+    match resolve_value(expr)? {
+        Value::Symbol(symbol) => Some(symbol.get()),
+        _ => None,
+    }
+}
+
+fn resolve_value(expr: Expr) -> Option<Value> {
+    match expr {
+        Expr::MathIdent(ident) => Some(MATH.scope().get(&ident)?.read().clone()),
+        Expr::FieldAccess(access) => {
+            let Value::Symbol(symbol) = resolve_value(access.target())? else {
+                return None;
+            };
+            symbol.modified(access.field().as_str()).ok().map(Value::Symbol)
+        }
+        _ => None,
+    }
+}
+
+fn call_node(call: ast::FuncCall) -> Node {
+    let mut args: Vec<Node> = call
+        .args()
+        .items()
+        .filter_map(|arg| match arg {
+            ast::Arg::Pos(arg) => Some(node(arg)),
+            _ => None,
+        })
+        .collect();
+    let name = match call.callee() {
+        Expr::MathIdent(ident) => ident.get().to_string(),
+        _ => String::new(),
+    };
+    let fenced = |open, close, body| Node::Fenced { open, close, body: Box::new(body) };
+    match (name.as_str(), args.len()) {
+        ("frac", 2) => {
+            let denom = args.pop().unwrap();
+            Node::Frac(Box::new(args.pop().unwrap()), Box::new(denom))
+        }
+        ("binom", 2) => {
+            let k = args.pop().unwrap();
+            Node::Binom(Box::new(args.pop().unwrap()), Box::new(k))
+        }
+        ("sqrt", 1) => Node::Root(None, Box::new(args.pop().unwrap())),
+        ("root", 2) => {
+            let radicand = args.pop().unwrap();
+            Node::Root(Some(Box::new(args.pop().unwrap())), Box::new(radicand))
+        }
+        ("abs", 1) => fenced('|', '|', args.pop().unwrap()),
+        ("norm", 1) => fenced('‖', '‖', args.pop().unwrap()),
+        ("floor", 1) => fenced('⌊', '⌋', args.pop().unwrap()),
+        ("ceil", 1) => fenced('⌈', '⌉', args.pop().unwrap()),
+        ("vec", _) => Node::Vector(args),
+        _ => {
+            let mut body = vec![];
+            for (i, arg) in args.into_iter().enumerate() {
+                if i > 0 {
+                    body.push(Node::Op(",".into()));
+                }
+                body.push(arg);
+            }
+            Node::Row(vec![node(call.callee()), fenced('(', ')', Node::Row(body))])
+        }
+    }
+}
+
+fn unsupported(source: &str) -> Node {
+    Node::Unsupported(source.to_string())
+}
+
+impl Node {
+    fn write_mathml(&self, out: &mut String) {
+        match self {
+            Node::Row(nodes) => {
+                out.push_str("<mrow>");
+                nodes.iter().for_each(|node| node.write_mathml(out));
+                out.push_str("</mrow>");
+            }
+            Node::Ident(text) => element(out, "mi", text),
+            Node::Number(text) => element(out, "mn", text),
+            Node::Op(text) => element(out, "mo", text),
+            Node::OpName(text) => {
+                write!(out, r#"<mi mathvariant="normal">{}</mi>"#, escape_xml(text))
+                    .unwrap()
+            }
+            Node::Text(text) => element(out, "mtext", text),
+            Node::Frac(num, denom) => {
+                out.push_str("<mfrac>");
+                num.write_mathml(out);
+                denom.write_mathml(out);
+                out.push_str("</mfrac>");
+            }
+            Node::Binom(n, k) => {
+                out.push_str(r#"<mrow><mo>(</mo><mfrac linethickness="0">"#);
+                n.write_mathml(out);
+                k.write_mathml(out);
+                out.push_str("</mfrac><mo>)</mo></mrow>");
+            }
+            Node::Root(None, radicand) => {
+                out.push_str("<msqrt>");
+                radicand.write_mathml(out);
+                out.push_str("</msqrt>");
+            }
+            Node::Root(Some(index), radicand) => {
+                out.push_str("<mroot>");
+                radicand.write_mathml(out);
+                index.write_mathml(out);
+                out.push_str("</mroot>");
+            }
+            Node::Attach { base, bottom, top } => {
+                let tag = match (bottom, top) {
+                    (Some(_), Some(_)) => "msubsup",
+                    (Some(_), None) => "msub",
+                    (None, Some(_)) => "msup",
+                    (None, None) => return base.write_mathml(out),
+                };
+                write!(out, "<{tag}>").unwrap();
+                base.write_mathml(out);
+                bottom.iter().chain(top).for_each(|node| node.write_mathml(out));
+                write!(out, "</{tag}>").unwrap();
+            }
+            Node::Fenced { open, close, body } => {
+                out.push_str("<mrow>");
+                fence(out, *open);
+                body.write_mathml(out);
+                fence(out, *close);
+                out.push_str("</mrow>");
+            }
+            Node::Vector(nodes) => {
+                out.push_str("<mrow><mo>(</mo><mtable>");
+                for node in nodes {
+                    out.push_str("<mtr><mtd>");
+                    node.write_mathml(out);
+                    out.push_str("</mtd></mtr>");
+                }
+                out.push_str("</mtable><mo>)</mo></mrow>");
+            }
+            Node::Space | Node::Linebreak | Node::AlignPoint => {}
+            Node::Unsupported(source) => {
+                write!(out, "<merror><mtext>{}</mtext></merror>", escape_xml(source))
+                    .unwrap()
+            }
+        }
+    }
+
+    fn write_latex(&self, out: &mut String) {
+        match self {
+            Node::Row(nodes) => nodes.iter().for_each(|node| node.write_latex(out)),
+            Node::Ident(text) if text.chars().count() > 1 => {
+                write!(out, r"\mathrm{{{}}}", escape_latex(text)).unwrap()
+            }
+            Node::Ident(text) | Node::Number(text) | Node::Op(text) => {
+                text.chars().for_each(|c| latex_char(out, c))
+            }
+            Node::OpName(name) => {
+                if LATEX_OPERATORS.contains(&name.as_str()) {
+                    write!(out, r"\{name} ").unwrap()
+                } else {
+                    write!(out, r"\operatorname{{{name}}}").unwrap()
+                }
+            }
+            Node::Text(text) => write!(out, r"\text{{{}}}", escape_latex(text)).unwrap(),
+            Node::Frac(num, denom) => {
+                out.push_str(r"\frac");
+                braced(out, num);
+                braced(out, denom);
+            }
+            Node::Binom(n, k) => {
+                out.push_str(r"\binom");
+                braced(out, n);
+                braced(out, k);
+            }
+            Node::Root(index, radicand) => {
+                out.push_str(r"\sqrt");
+                if let Some(index) = index {
+                    out.push('[');
+                    index.write_latex(out);
+                    out.push(']');
+                }
+                braced(out, radicand);
+            }
+            Node::Attach { base, bottom, top } => {
+                group(out, base);
+                if let Some(bottom) = bottom {
+                    out.push('_');
+                    group(out, bottom);
+                }
+                if let Some(top) = top {
+                    out.push('^');
+                    group(out, top);
+                }
+            }
+            Node::Fenced { open, close, body } => {
+                out.push_str(r"\left");
+                latex_delimiter(out, *open);
+                body.write_latex(out);
+                out.push_str(r"\right");
+                latex_delimiter(out, *close);
+            }
+            Node::Vector(nodes) => {
+                out.push_str(r"\begin{pmatrix}");
+                for (i, node) in nodes.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(r" \\ ");
+                    }
+                    node.write_latex(out);
+                }
+                out.push_str(r"\end{pmatrix}");
+            }
+            Node::Space => out.push(' '),
+            Node::Linebreak => out.push_str(r" \\ "),
+            Node::AlignPoint => out.push('&'),
+            Node::Unsupported(source) => {
+                write!(out, r"\text{{{}}}", escape_latex(source)).unwrap()
+            }
+        }
+    }
+}
+
+fn element(out: &mut String, tag: &str, text: &str) {
+    write!(out, "<{tag}>{}</{tag}>", escape_xml(text)).unwrap();
+}
+
+fn fence(out: &mut String, c: char) {
+    write!(out, r#"<mo fence="true">{}</mo>"#, escape_xml(&c.to_string())).unwrap();
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Writes the node in braces, unless it is a single character.
+fn group(out: &mut String, node: &Node) {
+    let mut inner = String::new();
+    node.write_latex(&mut inner);
+    if inner.chars().count() == 1 {
+        out.push_str(&inner);
+    } else {
+        write!(out, "{{{}}}", inner.trim()).unwrap();
+    }
+}
+
+/// Writes the node in braces, as an argument of a command like `\frac`.
+fn braced(out: &mut String, node: &Node) {
+    let mut inner = String::new();
+    node.write_latex(&mut inner);
+    write!(out, "{{{}}}", inner.trim()).unwrap();
+}
+
+fn escape_latex(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn latex_char(out: &mut String, c: char) {
+    match LATEX_SYMBOLS.iter().find(|(symbol, _)| *symbol == c) {
+        // A space keeps the command apart from a following letter.
+        Some((_, command)) => write!(out, r"\{command} ").unwrap(),
+        None => match c {
+            '−' => out.push('-'),
+            '{' | '}' | '#' | '%' | '&' | '_' | '$' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        },
+    }
+}
+
+fn latex_delimiter(out: &mut String, c: char) {
+    match c {
+        '{' | '}' => {
+            out.push('\\');
+            out.push(c);
+        }
+        '⟨' => out.push_str(r"\langle "),
+        '⟩' => out.push_str(r"\rangle "),
+        '⌊' => out.push_str(r"\lfloor "),
+        '⌋' => out.push_str(r"\rfloor "),
+        '⌈' => out.push_str(r"\lceil "),
+        '⌉' => out.push_str(r"\rceil "),
+        '‖' => out.push_str(r"\| "),
+        _ => out.push(c),
+    }
+}
+
+/// Operator names that LaTeX has a command for.
+const LATEX_OPERATORS: &[&str] = &[
+    "arccos", "arcsin", "arctan", "arg", "cos", "cosh", "cot", "coth", "csc", "deg",
+    "det", "dim", "exp", "gcd", "hom", "inf", "ker", "lg", "lim", "liminf", "limsup",
+    "ln", "log", "max", "min", "sec", "sin", "sinh", "sup", "tan", "tanh",
+];
+
+/// Characters that LaTeX has a command for.
+const LATEX_SYMBOLS: &[(char, &str)] = &[
+    ('α', "alpha"),
+    ('β', "beta"),
+    ('γ', "gamma"),
+    ('δ', "delta"),
+    ('ε', "varepsilon"),
+    ('ϵ', "epsilon"),
+    ('ζ', "zeta"),
+    ('η', "eta"),
+    ('θ', "theta"),
+    ('ϑ', "vartheta"),
+    ('ι', "iota"),
+    ('κ', "kappa"),
+    ('λ', "lambda"),
+    ('μ', "mu"),
+    ('ν', "nu"),
+    ('ξ', "xi"),
+    ('π', "pi"),
+    ('ρ', "rho"),
+    ('σ', "sigma"),
+    ('τ', "tau"),
+    ('υ', "upsilon"),
+    ('φ', "varphi"),
+    ('ϕ', "phi"),
+    ('χ', "chi"),
+    ('ψ', "psi"),
+    ('ω', "omega"),
+    ('Γ', "Gamma"),
+    ('Δ', "Delta"),
+    ('Θ', "Theta"),
+    ('Λ', "Lambda"),
+    ('Ξ', "Xi"),
+    ('Π', "Pi"),
+    ('Σ', "Sigma"),
+    ('Υ', "Upsilon"),
+    ('Φ', "Phi"),
+    ('Ψ', "Psi"),
+    ('Ω', "Omega"),
+    ('∞', "infty"),
+    ('∑', "sum"),
+    ('∏', "prod"),
+    ('∫', "int"),
+    ('∮', "oint"),
+    ('∂', "partial"),
+    ('∇', "nabla"),
+    ('±', "pm"),
+    ('∓', "mp"),
+    ('×', "times"),
+    ('⋅', "cdot"),
+    ('·', "cdot"),
+    ('÷', "div"),
+    ('≤', "leq"),
+    ('≥', "geq"),
+    ('≠', "neq"),
+    ('≈', "approx"),
+    ('≡', "equiv"),
+    ('∼', "sim"),
+    ('≪', "ll"),
+    ('≫', "gg"),
+    ('∈', "in"),
+    ('∉', "notin"),
+    ('⊂', "subset"),
+    ('⊆', "subseteq"),
+    ('⊃', "supset"),
+    ('⊇', "supseteq"),
+    ('∪', "cup"),
+    ('∩', "cap"),
+    ('∅', "emptyset"),
+    ('∀', "forall"),
+    ('∃', "exists"),
+    ('¬', "neg"),
+    ('∧', "wedge"),
+    ('∨', "vee"),
+    ('→', "to"),
+    ('←', "leftarrow"),
+    ('↔', "leftrightarrow"),
+    ('⇒', "Rightarrow"),
+    ('⇐', "Leftarrow"),
+    ('⇔', "Leftrightarrow"),
+    ('↦', "mapsto"),
+    ('…', "dots"),
+    ('⋯', "cdots"),
+    ('∗', "ast"),
+    ('∘', "circ"),
+    ('′', "prime"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{json, thick};
+
+    fn latex(source: &str) -> serde_json::Value {
+        json(math_to_latex(thick(source)))["Ok"].clone()
+    }
+
+    fn mathml(source: &str) -> serde_json::Value {
+        json(math_to_mathml(thick(source)))["Ok"].clone()
+    }
+
+    #[test]
+    fn test_fraction_and_superscript() {
+        assert_eq!(latex("a/b + x^2"), r"\frac{a}{b} + x^2");
+        assert_eq!(
+            mathml("a/b"),
+            concat!(
+                r#"<math xmlns="http://www.w3.org/1998/Math/MathML"><mrow>"#,
+                "<mfrac><mi>a</mi><mi>b</mi></mfrac>",
+                "</mrow></math>",
+            )
+        );
+        assert_eq!(
+            mathml("x^2"),
+            concat!(
+                r#"<math xmlns="http://www.w3.org/1998/Math/MathML"><mrow>"#,
+                "<msup><mi>x</mi><mn>2</mn></msup>",
+                "</mrow></math>",
+            )
+        );
+    }
+
+    #[test]
+    fn test_grouping_parens_are_stripped() {
+        assert_eq!(latex("(a+b)/c"), r"\frac{a+b}{c}");
+        assert_eq!(latex("1/(a+b)"), r"\frac{1}{a+b}");
+        assert_eq!(latex("x^(n+1)_(i j)"), r"x_{i j}^{n+1}");
+        assert_eq!(latex("sqrt(x) + root(3, y)"), r"\sqrt{x} + \sqrt[3]{y}");
+        // Elsewhere, parentheses are shown.
+        assert_eq!(latex("(a+b)^2"), r"{\left(a+b\right)}^2");
+        assert_eq!(
+            mathml("(a+b)/c"),
+            concat!(
+                r#"<math xmlns="http://www.w3.org/1998/Math/MathML"><mrow><mfrac>"#,
+                "<mrow><mi>a</mi><mo>+</mo><mi>b</mi></mrow><mi>c</mi>",
+                "</mfrac></mrow></math>",
+            )
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use crate::compile::{compile_reporting, run_compilation};
+use crate::extended_info::{ExtendedSourceDiagnostic, ExtendedWarned, Resolve};
+use crate::frames::walk_frame;
+use crate::java_world::JavaWorld;
+use crate::memory_management::{evict_after_compile, JavaResult, ThickBytePtr};
+use crate::overlay_world::OverlayWorld;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::mem;
+use ttf_parser::{GlyphId, OutlineBuilder};
+use typst::diag::Warned;
+use typst::layout::{Abs, FrameItem, PagedDocument, Point, Ratio, Transform};
+use typst::syntax::{FileId, Source, VirtualPath};
+use typst::utils::tick;
+
+/// The file the formula is laid out in.
+const MATH_FILE: &str = "/.math.typ";
+
+/// Set before the formula, so that the page fits it tightly.
+const MATH_PREAMBLE: &str = "#set page(width: auto, height: auto, margin: 0pt)\n";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GlyphPath {
+    /// The text the glyph represents.
+    pub text: String,
+    /// The glyph's origin on the baseline, in points from the formula's top left.
+    pub x: f64,
+    pub y: f64,
+    /// The font size in points.
+    pub size: f64,
+    /// The outline as SVG path data, in the same coordinates as `x` and `y`.
+    /// Empty for glyphs without an outline, such as spaces.
+    pub path: String,
+}
+
+/// Lays out the math expression as an inline formula and returns the outlines
+/// of its glyphs. The source is math markup without the enclosing dollars.
+#[no_mangle]
+pub extern "C" fn render_math_glyphs(
+    world_ptr: *mut JavaWorld,
+    source_ptr: ThickBytePtr,
+) -> JavaResult<ExtendedWarned<Result<Vec<GlyphPath>, Vec<ExtendedSourceDiagnostic>>>> {
+    tick!();
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let source = source_ptr.to_str();
+
+    let id = FileId::new(None, VirtualPath::new(MATH_FILE));
+    let math_world =
+        OverlayWorld::new(&world, Source::new(id, format!("{MATH_PREAMBLE}${source}$")));
+    let Warned { output, warnings } = run_compilation(world.timeout, || {
+        compile_reporting::<PagedDocument>(&math_world, world.warning_callback)
+    });
+    evict_after_compile();
+    let result = ExtendedWarned {
+        output: output
+            .map(|document| collect_glyphs(&document))
+            .map_err(|it| it.resolve(&math_world)),
+        warnings: warnings.resolve(&math_world),
+    };
+
+    let _ = Box::into_raw(world); // Not to drop the world!
+
+    mem::forget(source);
+    JavaResult::pack(result)
+}
+
+fn collect_glyphs(document: &PagedDocument) -> Vec<GlyphPath> {
+    let mut glyphs = vec![];
+    let Some(page) = document.pages.first() else { return glyphs };
+    walk_frame(&page.frame, Transform::identity(), &mut |ts, pos, item| {
+        let FrameItem::Text(text) = item else { return };
+        let mut x = pos.x;
+        for glyph in &text.glyphs {
+            let origin = Point::new(x + glyph.x_offset.at(text.size), pos.y);
+            // Font units are scaled to points and the y-axis points upwards.
+            let scale = Ratio::new(text.size.to_pt() / text.font.units_per_em());
+            let glyph_ts = ts
+                .pre_concat(Transform::translate(origin.x, origin.y))
+                .pre_concat(Transform::scale(scale, -scale));
+            let page_origin = origin.transform(ts);
+            glyphs.push(GlyphPath {
+                text: text.text[glyph.range()].to_string(),
+                x: page_origin.x.to_pt(),
+                y: page_origin.y.to_pt(),
+                size: text.size.to_pt(),
+                path: outline(&text.font, glyph.id, glyph_ts),
+            });
+            x += glyph.x_advance.at(text.size);
+        }
+    });
+    glyphs
+}
+
+/// The glyph's outline as SVG path data, with points mapped through `ts` from
+/// font units.
+fn outline(font: &Font, id: u16, ts: Transform) -> String {
+    let mut builder = PathBuilder { path: String::new(), ts };
+    font.ttf().outline_glyph(GlyphId(id), &mut builder);
+    builder.path.trim_end().to_string()
+}
+
+struct PathBuilder {
+    path: String,
+    ts: Transform,
+}
+
+impl PathBuilder {
+    fn point(&mut self, x: f32, y: f32) {
+        let point = Point::new(Abs::pt(x.into()), Abs::pt(y.into())).transform(self.ts);
+        write!(&mut self.path, "{} {} ", point.x.to_pt(), point.y.to_pt()).unwrap();
+    }
+}
+
+impl OutlineBuilder for PathBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.path.push_str("M ");
+        self.point(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.path.push_str("L ");
+        self.point(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.path.push_str("Q ");
+        self.point(x1, y1);
+        self.point(x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.path.push_str("C ");
+        self.point(x1, y1);
+        self.point(x2, y2);
+        self.point(x, y);
+    }
+
+    fn close(&mut self) {
+        self.path.push_str("Z ");
+    }
+}
@@ -1,14 +1,19 @@
+use crate::compiled_doc::CompiledDoc;
 use crate::java_world::JavaWorld;
+use crate::syntax::SyntaxSession;
 use std::ffi::CString;
 use std::marker::PhantomData;
 use std::mem;
-use std::os::raw::c_char;
+use std::os::raw::{c_char, c_void};
 use std::ptr::{null, null_mut};
+use std::slice;
 use typst::{comemo, Library};
 
 use crate::exception::Except;
+use crate::throw;
 use hex::{decode, encode};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::OnceLock;
 use typst::utils::tick;
 
@@ -24,10 +29,44 @@ macro_rules! free_fn {
             unsafe { drop(Box::from_raw(__ptr)) };
         }
     };
+    // Refuses to free pointers that `$is_valid` rejects, e.g. ones already freed,
+    // see `free_checked`.
+    ($fn_name:ident, $type_name:ty, $is_valid:path) => {
+        #[no_mangle]
+        pub extern "C" fn $fn_name(
+            __ptr: *mut $type_name,
+        ) -> $crate::memory_management::JavaResult<$crate::exception::Except<()>> {
+            tick!();
+            $crate::memory_management::JavaResult::pack(
+                $crate::memory_management::free_checked(__ptr, $is_valid),
+            )
+        }
+    };
 }
 
 free_fn!(free_library, Library);
 free_fn!(free_world, JavaWorld);
+free_fn!(free_syntax_session, SyntaxSession);
+free_fn!(free_compiled_doc, CompiledDoc, CompiledDoc::is_valid);
+
+/// Frees the handle unless `is_valid` rejects it, e.g. because it was already
+/// freed, which throws an `IllegalStateException` instead.
+pub fn free_checked<T>(ptr: *mut T, is_valid: fn(*const T) -> bool) -> Except<()> {
+    if ptr.is_null() {
+        return Ok(());
+    }
+    if !is_valid(ptr) {
+        return Err(throw!(
+            "java.lang.IllegalStateException".to_string(),
+            Some(format!(
+                "Refusing to free an invalid {} handle, was it freed twice?",
+                std::any::type_name::<T>().rsplit("::").next().unwrap_or_default()
+            ))
+        ));
+    }
+    unsafe { drop(Box::from_raw(ptr)) };
+    Ok(())
+}
 
 #[no_mangle]
 pub extern "C" fn free_str(ptr: *mut c_char) {
@@ -38,18 +77,97 @@ pub extern "C" fn free_str(ptr: *mut c_char) {
     unsafe { drop(CString::from_raw(ptr)) };
 }
 
+/// What the pointers passed to [`free_many`] point to, given by its `type_tag`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum HandleKind {
+    Library = 0,
+    World = 1,
+    SyntaxSession = 2,
+    CompiledDoc = 3,
+    /// Strings freed by `free_str`.
+    Str = 4,
+}
+
+impl HandleKind {
+    fn from_tag(tag: i32) -> Option<Self> {
+        Some(match tag {
+            0 => Self::Library,
+            1 => Self::World,
+            2 => Self::SyntaxSession,
+            3 => Self::CompiledDoc,
+            4 => Self::Str,
+            _ => return None,
+        })
+    }
+
+    fn free(self, ptr: *mut c_void) -> Except<()> {
+        match self {
+            Self::Library => free_library(ptr.cast()),
+            Self::World => free_world(ptr.cast()),
+            Self::SyntaxSession => free_syntax_session(ptr.cast()),
+            Self::CompiledDoc => return free_checked(ptr.cast(), CompiledDoc::is_valid),
+            Self::Str => free_str(ptr.cast()),
+        }
+        Ok(())
+    }
+}
+
+/// Frees all the pointers at once, to spare a call per pointer. `type_tag` tells
+/// what they point to, see [`HandleKind`]. The array itself is owned by the
+/// caller.
+#[no_mangle]
+pub extern "C" fn free_many(
+    ptrs: CVec<*mut c_void>,
+    type_tag: i32,
+) -> JavaResult<Except<()>> {
+    tick!();
+    let Some(kind) = HandleKind::from_tag(type_tag) else {
+        return JavaResult::pack(Err(throw!(
+            "java.lang.IllegalArgumentException".to_string(),
+            Some(format!("Unknown type tag {type_tag}"))
+        )));
+    };
+    if ptrs.ptr.is_null() {
+        return JavaResult::pack(Ok(()));
+    }
+    let ptrs = unsafe { slice::from_raw_parts(ptrs.ptr, ptrs.len.max(0) as usize) };
+    // Frees the valid ones even if some are not, and throws for the first of those.
+    let results = ptrs.iter().map(|&ptr| kind.free(ptr)).collect::<Vec<_>>();
+    JavaResult::pack(results.into_iter().collect())
+}
+
 static FREER: OnceLock<extern "C" fn(ticket: i64)> = OnceLock::new();
 
+/// Sets the function that releases the values Java passes in as
+/// [`JavaResult`]s. Must be called once, before anything else; fails if a freer
+/// is already set.
 #[no_mangle]
-pub extern "C" fn set_freer(f: extern "C" fn(i64)) -> i32 {
-    match FREER.set(f) {
-        // Ok(_) => Ok(()),
-        // Err(_) => throw!(
-        //     "java.lang.IllegalStateException".to_string(),
-        //     Some("Can't reset freer function")
-        // ),
-        Ok(_) => 1,
-        Err(_) => 0,
+pub extern "C" fn set_freer(f: extern "C" fn(i64)) -> JavaResult<Except<()>> {
+    JavaResult::pack(FREER.set(f).map_err(|_| {
+        throw!(
+            "java.lang.IllegalStateException".to_string(),
+            Some("Can't reset freer function".to_string())
+        )
+    }))
+}
+
+/// Whether the JSON handed to Java is indented.
+static PRETTY_JSON: AtomicBool = AtomicBool::new(false);
+
+/// Makes all JSON handed to Java pretty-printed (1) or compact (0, the default),
+/// including the JSON nested in results, like that of `query` and `detached_eval`.
+#[no_mangle]
+pub extern "C" fn set_pretty_json(pretty: i32) {
+    PRETTY_JSON.store(pretty == 1, Ordering::Relaxed);
+}
+
+/// Serializes a value for Java, as set with `set_pretty_json`.
+pub fn to_json<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    if PRETTY_JSON.load(Ordering::Relaxed) {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
     }
 }
 
@@ -62,19 +180,36 @@ pub struct JavaResult<T: Sized> {
 }
 
 impl<T: for<'a> Deserialize<'a>> JavaResult<T> {
-    pub fn unpack(self) -> T {
+    /// Releases the value on the Java side and deserializes it. Fails if no
+    /// freer was set with `set_freer` or the value is malformed.
+    pub fn try_unpack(self) -> Except<T> {
+        self.try_unpack_with(FREER.get().copied())
+    }
+
+    fn try_unpack_with(self, freer: Option<extern "C" fn(i64)>) -> Except<T> {
         tick!();
         let Self { ticket, value, phantom: _phantom } = self;
 
         tick!();
         if ticket >= 0 {
-            FREER.get().unwrap()(ticket);
+            let Some(freer) = freer else {
+                return Err(throw!(
+                    "java.lang.IllegalStateException".to_string(),
+                    Some("freer not initialized, call set_freer first".to_string())
+                ));
+            };
+            freer(ticket);
         }
         tick!();
 
         let str = value.to_str();
         tick!("{}", str);
-        let result = serde_json::from_str::<T>(str.as_str()).unwrap();
+        let result = serde_json::from_str::<T>(str.as_str()).map_err(|err| {
+            throw!(
+                "java.lang.IllegalArgumentException".to_string(),
+                Some(format!("Malformed value: {err}"))
+            )
+        });
         mem::forget(str);
         result
     }
@@ -82,7 +217,7 @@ impl<T: for<'a> Deserialize<'a>> JavaResult<T> {
 
 impl<T: Serialize> JavaResult<T> {
     pub fn pack(value: T) -> JavaResult<T> {
-        let str = serde_json::to_string(&value).expect("FATAL: error serializing value");
+        let str = to_json(&value).expect("FATAL: error serializing value");
         JavaResult {
             ticket: 0,
             value: ThickBytePtr::from_str(str),
@@ -102,8 +237,7 @@ impl<T> JavaExceptPtrResult<T> {
         match value {
             Ok(v) => JavaExceptPtrResult { comment: ThickBytePtr::null(), ptr: v },
             Err(e) => {
-                let str =
-                    serde_json::to_string(&e).expect("FATAL: error serializing value");
+                let str = to_json(&e).expect("FATAL: error serializing value");
 
                 JavaExceptPtrResult { comment: ThickBytePtr::from_str(str), ptr: null() }
             }
@@ -222,7 +356,140 @@ extern "C" fn free_thick_byte_ptr(ptr: ThickBytePtr) {
     ptr.release()
 }
 
+#[no_mangle]
+extern "C" fn free_i64_array(array: CVec<i64>) {
+    if !array.ptr.is_null() {
+        drop(Vec::from(array));
+    }
+}
+
 #[no_mangle]
 extern "C" fn evict_cache(max_age: i64) {
     comemo::evict(max_age as usize)
 }
+
+/// How many compilations memoized results survive unused, 0 if they are kept
+/// until `evict_cache` is called.
+static CACHE_MAX_AGE: AtomicUsize = AtomicUsize::new(0);
+
+/// Makes every compilation evict the memoized results that were not used in the
+/// last `compilations` compilations. This bounds the cache by age rather than by
+/// entries or bytes: comemo tracks neither the number nor the size of its
+/// entries, only their age, which each eviction increases by one. Evicting once
+/// after every compilation makes that age a count of compilations.
+/// Non-positive values turn automatic eviction off.
+#[no_mangle]
+extern "C" fn set_cache_max_age(compilations: i64) {
+    CACHE_MAX_AGE.store(compilations.max(0) as usize, Ordering::Relaxed);
+}
+
+/// Enforces the age set with `set_cache_max_age`. Runs after the compilation on
+/// the same thread, so that none is skipped; dropping the old entries is cheap
+/// compared to the compilation.
+pub fn evict_after_compile() {
+    let max_age = CACHE_MAX_AGE.load(Ordering::Relaxed);
+    if max_age != 0 {
+        comemo::evict(max_age);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_free_checked_rejects_invalid_handles() {
+        let ptr = Box::into_raw(Box::new(1));
+        let err = free_checked(ptr, |_| false).unwrap_err();
+        assert_eq!(err.class, "java.lang.IllegalStateException");
+        assert!(free_checked(ptr, |_| true).is_ok());
+        assert!(free_checked(std::ptr::null_mut::<i32>(), |_| false).is_ok());
+    }
+
+    #[test]
+    fn test_free_many_releases_every_handle() {
+        use crate::compiled_doc::{compile_and_cache, live_compiled_docs};
+        use crate::test_utils::{json, world, COMPILED_DOCS};
+        let _docs = COMPILED_DOCS.lock();
+        let world = world("Page");
+        let before = live_compiled_docs();
+        let docs = (0..64)
+            .map(|_| {
+                let result = json(compile_and_cache(world));
+                result["output"]["Ok"]["handle"].as_i64().unwrap() as *mut c_void
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(live_compiled_docs(), before + 64);
+        let ptrs = CVec::from(docs);
+        let result = json(free_many(ptrs, HandleKind::CompiledDoc as i32));
+        assert_eq!(result, serde_json::json!({"Ok": null}));
+        assert_eq!(live_compiled_docs(), before);
+        drop(Vec::from(ptrs));
+        let empty = CVec { ptr: null_mut(), len: 0, cap: 0 };
+        let result = json(free_many(empty, 5));
+        assert_eq!(result["Err"]["class"], "java.lang.IllegalArgumentException");
+        free_world(world);
+    }
+
+    #[test]
+    fn test_unpack_without_freer_fails() {
+        // Not through `try_unpack`, as other tests set the global freer.
+        let result = JavaResult::<i32> {
+            ticket: 5,
+            value: ThickBytePtr::from_str("1".to_string()),
+            phantom: PhantomData,
+        };
+        let err = result.try_unpack_with(None).unwrap_err();
+        assert_eq!(err.class, "java.lang.IllegalStateException");
+        let result = JavaResult::<i32> {
+            ticket: -1,
+            value: ThickBytePtr::from_str("1".to_string()),
+            phantom: PhantomData,
+        };
+        assert_eq!(result.try_unpack_with(None).unwrap(), 1);
+    }
+
+    static PROBE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    #[comemo::memoize]
+    fn probe(key: &str) -> usize {
+        PROBE_CALLS.fetch_add(1, Ordering::SeqCst);
+        key.len()
+    }
+
+    #[test]
+    fn test_cache_max_age_bounds_unused_results() {
+        set_cache_max_age(3);
+        let key = "test_cache_max_age_bounds_unused_results";
+        probe(key);
+        let calls = PROBE_CALLS.load(Ordering::SeqCst);
+        for i in 0..10 {
+            let world = crate::test_utils::world(&format!("Document {i}"));
+            crate::test_utils::json(crate::compile::compile_png(world, 0, 1, 1.0, 0));
+            free_world(world);
+        }
+        // The result was not used for more compilations than the maximum age.
+        probe(key);
+        assert_eq!(PROBE_CALLS.load(Ordering::SeqCst), calls + 1);
+        set_cache_max_age(0);
+    }
+
+    #[test]
+    fn test_pretty_json_covers_nested_json() {
+        use crate::test_utils::{json, thick, world};
+        let world = world("= Intro <intro>");
+        set_pretty_json(1);
+        let compiled = crate::compile::compile_png(world, 0, 1, 1.0, 0);
+        let evaluated = crate::detached_eval::detached_eval(world, thick("(a: 1)"));
+        let queried = crate::query::query(world, thick("<intro>"), 0, 0);
+        set_pretty_json(0);
+        assert!(compiled.value.to_str().contains("\n  "));
+        let evaluated = json(evaluated);
+        assert!(evaluated["Ok"].as_str().unwrap().contains("\n  \"a\": 1"));
+        let queried = &json(queried)["Ok"]["output"]["Ok"]["result"];
+        assert!(queried.as_str().unwrap().contains("\n  "));
+        let compact = crate::detached_eval::detached_eval(world, thick("(a: 1)"));
+        assert_eq!(json(compact)["Ok"], r#"{"a":1}"#);
+        free_world(world);
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use typst::diag::{FileError, FileResult};
+use typst::foundations::{Bytes, Datetime};
+use typst::syntax::{FileId, Source, VirtualPath};
+use typst::text::{Font, FontBook};
+use typst::utils::LazyHash;
+use typst::{Library, World};
+use typst_kit::fonts::Fonts;
+
+/// MemoryWorld serves a single in-memory main file.
+/// Unlike JavaWorld, it needs neither callbacks nor disk access,
+/// so it can be used for self-contained, one-shot compilations.
+pub struct MemoryWorld {
+    /// Typst's standard library.
+    pub library: LazyHash<Library>,
+    /// Metadata about discovered fonts.
+    pub book: LazyHash<FontBook>,
+    /// Fonts, handled as in SystemWorld. Shared, so that worlds created for a
+    /// single compilation don't search for fonts again.
+    pub fonts: Arc<Fonts>,
+    /// The only source file available in this world.
+    pub main: Source,
+}
+
+impl MemoryWorld {
+    pub fn new(library: Library, fonts: Arc<Fonts>, text: String) -> Self {
+        let id = FileId::new(None, VirtualPath::new("main.typ"));
+        MemoryWorld {
+            library: LazyHash::new(library),
+            book: LazyHash::new(fonts.book.clone()),
+            fonts,
+            main: Source::new(id, text),
+        }
+    }
+}
+
+impl World for MemoryWorld {
+    fn library(&self) -> &LazyHash<Library> {
+        &self.library
+    }
+
+    fn book(&self) -> &LazyHash<FontBook> {
+        &self.book
+    }
+
+    fn main(&self) -> FileId {
+        self.main.id()
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        if id == self.main.id() {
+            Ok(self.main.clone())
+        } else {
+            Err(FileError::NotFound(id.vpath().as_rootless_path().into()))
+        }
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        if id == self.main.id() {
+            Ok(Bytes::from_string(self.main.text().to_string()))
+        } else {
+            Err(FileError::NotFound(id.vpath().as_rootless_path().into()))
+        }
+    }
+
+    fn font(&self, index: usize) -> Option<Font> {
+        self.fonts.fonts.get(index)?.get()
+    }
+
+    fn today(&self, _offset: Option<i64>) -> Option<Datetime> {
+        None
+    }
+}
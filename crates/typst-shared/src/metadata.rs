@@ -0,0 +1,101 @@
+use crate::compile::compile_paged;
+use crate::exception::Except;
+use crate::extended_info::{
+    ExtendedSourceDiagnostic, ExtendedSpan, ExtendedWarned, Resolve,
+};
+use crate::java_world::JavaWorld;
+use crate::memory_management::JavaResult;
+use crate::sort_keys::sort_keys;
+use serde::Serialize;
+use typst::foundations::{Label, NativeElement, Selector};
+use typst::introspection::MetadataElem;
+use typst::utils::PicoStr;
+
+#[derive(Debug, Serialize)]
+pub struct MetadataEntry {
+    /// The element's label, without angle brackets.
+    pub label: Option<String>,
+    /// The value, serialized like query results.
+    pub value: serde_json::Value,
+    /// Zero-based index of the page the element is on.
+    pub page: i64,
+    pub span: ExtendedSpan,
+}
+
+/// Lists the values of the document's `metadata` elements in order, only the
+/// ones with the given label if there is one. The label may be given with or
+/// without angle brackets.
+#[no_mangle]
+pub extern "C" fn list_metadata(
+    world_ptr: *mut JavaWorld,
+    label: JavaResult<Option<String>>,
+) -> JavaResult<
+    Except<ExtendedWarned<Result<Vec<MetadataEntry>, Vec<ExtendedSourceDiagnostic>>>>,
+> {
+    let label = match label.try_unpack() {
+        Ok(label) => label,
+        Err(err) => return JavaResult::pack(Err(err)),
+    };
+    let selector = match label {
+        Some(name) => {
+            let name = name
+                .strip_prefix('<')
+                .and_then(|it| it.strip_suffix('>'))
+                .unwrap_or(&name);
+            // Other labelled elements are skipped below.
+            Selector::Label(Label::new(PicoStr::intern(name)))
+        }
+        None => MetadataElem::elem().select(),
+    };
+    JavaResult::pack(Ok(compile_paged(world_ptr, |world, document, _| {
+        let introspector = &document.introspector;
+        Ok(introspector
+            .query(&selector)
+            .into_iter()
+            .filter_map(|elem| {
+                let metadata = elem.to_packed::<MetadataElem>()?;
+                let value = if world.sort_keys {
+                    sort_keys(&metadata.value)
+                } else {
+                    serde_json::to_value(&metadata.value).ok()?
+                };
+                Some(MetadataEntry {
+                    label: elem.label().map(|it| it.resolve().as_str().to_string()),
+                    value,
+                    page: introspector.page(elem.location()?).get() as i64 - 1,
+                    span: elem.span().resolve(world),
+                })
+            })
+            .collect())
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{java, json, world};
+
+    #[test]
+    fn test_list_metadata_returns_every_value() {
+        let world = world(
+            "#metadata((version: 2)) <config>\n#pagebreak()\n\
+             #metadata(\"second\")\n#heading[Other] <config>",
+        );
+        let result = json(list_metadata(world, java(&None::<String>)));
+        let entries = result["Ok"]["output"]["Ok"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["value"], serde_json::json!({"version": 2}));
+        assert_eq!(entries[0]["label"], "config");
+        assert_eq!(entries[0]["page"], 0);
+        assert_eq!(entries[1]["value"], "second");
+        assert!(entries[1]["label"].is_null());
+        assert_eq!(entries[1]["page"], 1);
+        // Filtering by the label skips the heading that has it as well.
+        let result = json(list_metadata(world, java(&Some("<config>".to_string()))));
+        let entries = result["Ok"]["output"]["Ok"].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["value"]["version"], 2);
+        free_world(world);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::java_world::JavaWorld;
+use std::collections::HashMap;
+use typst::diag::FileResult;
+use typst::foundations::{Bytes, Datetime};
+use typst::syntax::{FileId, Source};
+use typst::text::{Font, FontBook};
+use typst::utils::LazyHash;
+use typst::{Library, World};
+
+/// Compiles a made-up main file, like a formula or a watermark, against a
+/// world. The main file may replace one of the world's, and further files can
+/// be served from memory; everything else comes from the world.
+pub struct OverlayWorld<'a> {
+    pub world: &'a JavaWorld,
+    main: Source,
+    files: HashMap<FileId, Bytes>,
+}
+
+impl<'a> OverlayWorld<'a> {
+    pub fn new(world: &'a JavaWorld, main: Source) -> Self {
+        OverlayWorld { world, main, files: HashMap::new() }
+    }
+
+    /// Serves `data` as the file `id`, in place of the world's.
+    pub fn with_file(mut self, id: FileId, data: Bytes) -> Self {
+        self.files.insert(id, data);
+        self
+    }
+}
+
+impl World for OverlayWorld<'_> {
+    fn library(&self) -> &LazyHash<Library> {
+        self.world.library()
+    }
+
+    fn book(&self) -> &LazyHash<FontBook> {
+        self.world.book()
+    }
+
+    fn main(&self) -> FileId {
+        self.main.id()
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        if id == self.main.id() {
+            Ok(self.main.clone())
+        } else {
+            self.world.source(id)
+        }
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        match self.files.get(&id) {
+            Some(data) => Ok(data.clone()),
+            None => self.world.file(id),
+        }
+    }
+
+    fn font(&self, index: usize) -> Option<Font> {
+        self.world.font(index)
+    }
+
+    fn today(&self, offset: Option<i64>) -> Option<Datetime> {
+        self.world.today(offset)
+    }
+}
@@ -0,0 +1,179 @@
+use crate::exception::Except;
+use crate::extended_info::ExtendedPackageSpec;
+use crate::java_world::JavaWorld;
+use crate::memory_management::JavaResult;
+use crate::throw;
+use std::fs;
+use std::path::{Path, PathBuf};
+use typst::syntax::package::{PackageSpec, PackageVersion};
+use typst::utils::tick;
+
+#[no_mangle]
+pub extern "C" fn list_cached_packages(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<Vec<ExtendedPackageSpec>> {
+    tick!();
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let packages = match cache_dir(&world) {
+        Some(dir) => scan_cache(dir),
+        None => vec![],
+    };
+    let _ = Box::into_raw(world); // Not to drop the world!
+    JavaResult::pack(packages.into_iter().map(|it| it.into()).collect())
+}
+
+/// Deletes the given package from the cache. If `spec` is `None`, deletes the
+/// whole cache directory, which is shared with every other world and process
+/// using the same package cache path.
+#[no_mangle]
+pub extern "C" fn clear_package_cache(
+    world_ptr: *mut JavaWorld,
+    spec: JavaResult<Option<ExtendedPackageSpec>>,
+) -> JavaResult<Except<()>> {
+    tick!();
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let result = spec.try_unpack().and_then(|spec| {
+        let spec: Option<PackageSpec> = spec.map(|it| it.into());
+        match cache_dir(&world) {
+            Some(dir) => clear_cache(dir, spec.as_ref()),
+            None => Ok(()),
+        }
+    });
+    let _ = Box::into_raw(world); // Not to drop the world!
+    JavaResult::pack(result)
+}
+
+fn cache_dir(world: &JavaWorld) -> Option<&Path> {
+    world.package_storage.as_ref()?.package_cache_path()
+}
+
+/// Collects all packages stored as `{namespace}/{name}/{version}` in `dir`.
+fn scan_cache(dir: &Path) -> Vec<PackageSpec> {
+    let mut packages = vec![];
+    for namespace in subdirs(dir) {
+        for name in subdirs(&namespace) {
+            for version in subdirs(&name) {
+                let Some(version) =
+                    file_name(&version).and_then(|it| it.parse::<PackageVersion>().ok())
+                else {
+                    continue;
+                };
+                let (Some(namespace), Some(name)) =
+                    (file_name(&namespace), file_name(&name))
+                else {
+                    continue;
+                };
+                packages.push(PackageSpec {
+                    namespace: namespace.into(),
+                    name: name.into(),
+                    version,
+                });
+            }
+        }
+    }
+    packages.sort_by(|a, b| {
+        (&a.namespace, &a.name, a.version).cmp(&(&b.namespace, &b.name, b.version))
+    });
+    packages
+}
+
+fn clear_cache(dir: &Path, spec: Option<&PackageSpec>) -> Except<()> {
+    let target = match spec {
+        // Parsing checks that the namespace and the name are identifiers, so
+        // that something like `..` can't point outside of the cache.
+        Some(spec) => match spec.to_string().parse::<PackageSpec>() {
+            Ok(spec) => {
+                dir.join(format!("{}/{}/{}", spec.namespace, spec.name, spec.version))
+            }
+            Err(err) => {
+                return Err(throw!(
+                    "java.lang.IllegalArgumentException".to_string(),
+                    Some(format!("Invalid package {spec}: {err}"))
+                ))
+            }
+        },
+        None => dir.to_path_buf(),
+    };
+    if !target.exists() {
+        return Ok(());
+    }
+    let io_error = |err: std::io::Error| {
+        throw!(
+            "java.io.IOException".to_string(),
+            Some(format!("Failed to delete {}: {err}", target.display()))
+        )
+    };
+    let canonical = target.canonicalize().map_err(io_error)?;
+    if !canonical.starts_with(dir.canonicalize().map_err(io_error)?) {
+        return Err(throw!(
+            "java.lang.IllegalArgumentException".to_string(),
+            Some(format!("{} is outside of the package cache", canonical.display()))
+        ));
+    }
+    fs::remove_dir_all(&canonical).map_err(io_error)
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+fn file_name(path: &Path) -> Option<&str> {
+    path.file_name()?.to_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("typst-shared-cache-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for package in ["preview/foo/0.1.0", "preview/foo/0.2.0", "local/bar/1.0.0"] {
+            fs::create_dir_all(dir.join(package)).unwrap();
+        }
+        dir
+    }
+
+    fn spec(text: &str) -> PackageSpec {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_clear_one_package() {
+        let dir = cache("one");
+        assert!(clear_cache(&dir, Some(&spec("@preview/foo:0.1.0"))).is_ok());
+        assert_eq!(
+            scan_cache(&dir),
+            vec![spec("@local/bar:1.0.0"), spec("@preview/foo:0.2.0")]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_clear_rejects_paths_outside_of_cache() {
+        let dir = cache("outside");
+        let escaping = PackageSpec {
+            namespace: "..".into(),
+            name: "..".into(),
+            version: "1.0.0".parse().unwrap(),
+        };
+        let err = clear_cache(&dir.join("preview/foo"), Some(&escaping)).unwrap_err();
+        assert_eq!(err.class, "java.lang.IllegalArgumentException");
+        assert_eq!(scan_cache(&dir).len(), 3);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_clear_whole_cache() {
+        let dir = cache("whole");
+        assert!(clear_cache(&dir, None).is_ok());
+        assert!(!dir.exists());
+    }
+}
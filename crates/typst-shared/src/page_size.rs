@@ -0,0 +1,111 @@
+use crate::exception::Except;
+use crate::java_world::JavaWorld;
+use crate::memory_management::{JavaResult, ThickBytePtr};
+use crate::throw;
+use serde::{Deserialize, Serialize};
+use std::mem;
+use typst::foundations::{Smart, Style};
+use typst::layout::{Abs, Length, PageElem};
+use typst::utils::tick;
+
+/// Width and height of the pages, as if the document started with
+/// `#set page(width: .., height: ..)`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PageSize {
+    pub width: Extent,
+    pub height: Extent,
+}
+
+/// A length in points, or `"auto"` to fit the content.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Extent {
+    Auto(AutoKeyword),
+    Points(f64),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoKeyword {
+    Auto,
+}
+
+impl Extent {
+    fn to_smart(self) -> Smart<Length> {
+        match self {
+            Extent::Auto(_) => Smart::Auto,
+            Extent::Points(pt) => Smart::Custom(Abs::pt(pt).into()),
+        }
+    }
+}
+
+impl PageSize {
+    /// The set rules for the size.
+    pub fn styles(&self) -> Vec<Style> {
+        vec![
+            PageElem::set_width(self.width.to_smart()).into(),
+            PageElem::set_height(self.height.to_smart()).into(),
+        ]
+    }
+}
+
+/// Overrides the page size of the documents compiled in the world, see
+/// [`PageSize`]; `null` removes the override. This changes the layout, content
+/// reflows to the new size instead of being scaled. Page rules in the document
+/// itself still take precedence.
+#[no_mangle]
+pub extern "C" fn set_page_size(
+    world_ptr: *mut JavaWorld,
+    page_size_json: ThickBytePtr,
+) -> JavaResult<Except<()>> {
+    tick!();
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    let page_size_str = page_size_json.to_str();
+    let result = serde_json::from_str::<Option<PageSize>>(&page_size_str)
+        .map(|page_size| {
+            world.page_size = page_size;
+            world.apply_style_overrides();
+        })
+        .map_err(|err| {
+            throw!(
+                "java.lang.IllegalArgumentException".to_string(),
+                Some(format!("Malformed page size: {err}"))
+            )
+        });
+    let _ = Box::into_raw(world); // Not to drop the world!
+    mem::forget(page_size_str);
+    JavaResult::pack(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frames::page_frame;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, thick, world};
+
+    fn page_dimensions(world: *mut JavaWorld) -> (f64, f64) {
+        let result = json(page_frame(world, 0));
+        let page = &result["output"]["Ok"];
+        (page["width"].as_f64().unwrap(), page["height"].as_f64().unwrap())
+    }
+
+    #[test]
+    fn test_page_size_changes_the_pages() {
+        let world = world("Hello");
+        let (width, _) = page_dimensions(world);
+        assert!((width - 595.28).abs() < 0.01);
+
+        let size = r#"{"width": 200, "height": "auto"}"#;
+        let result = json(set_page_size(world, thick(size)));
+        assert_eq!(result, serde_json::json!({"Ok": null}));
+        let (width, height) = page_dimensions(world);
+        assert_eq!(width, 200.0);
+        assert!(height < 200.0);
+
+        json(set_page_size(world, thick("null")));
+        let (width, _) = page_dimensions(world);
+        assert!((width - 595.28).abs() < 0.01);
+        free_world(world);
+    }
+}
@@ -0,0 +1,303 @@
+use crate::compile::compile_paged;
+use crate::exception::Except;
+use crate::extended_info::{ExtendedSourceDiagnostic, ExtendedWarned};
+use crate::frames::{filter_frame, walk_frame, ExtendedRect};
+use crate::java_world::{reset_world, JavaWorld};
+use crate::memory_management::{Base16ByteArray, JavaResult};
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroUsize;
+use typst::diag::{At, SourceDiagnostic, SourceResult};
+use typst::ecow::{eco_vec, EcoString, EcoVec};
+use typst::foundations::{Derived, Packed, Smart};
+use typst::layout::{FrameItem, PageRanges, PagedDocument, Position, Transform};
+use typst::model::Destination;
+use typst::pdf::{EmbedElem, EmbeddedFileRelationship};
+use typst::syntax::Span;
+use typst::World;
+use typst_pdf::{PdfOptions, PdfStandard, PdfStandards};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PdfExportOptions {
+    /// Omits link annotations, for consumers that don't support them.
+    pub disable_links: bool,
+    /// Standards to conform to, named as in the CLI: `"a-2b"`, `"ua-1"`, etc.
+    /// PDF/UA produces a tagged PDF and warns about content it cannot describe.
+    pub standards: Vec<PdfStandard>,
+    /// Leaves out the authors, the creation date and the creating software.
+    /// The title, description and keywords are kept, they are part of the content.
+    pub strip_metadata: bool,
+    /// Attaches the main file, so that recipients can compile the document again.
+    pub embed_source: bool,
+    /// With `embed_source`, also attaches the project files the main file uses,
+    /// but not those of packages.
+    pub embed_dependencies: bool,
+}
+
+#[no_mangle]
+pub extern "C" fn compile_pdf(
+    world_ptr: *mut JavaWorld,
+    options: JavaResult<PdfExportOptions>,
+) -> JavaResult<
+    Except<ExtendedWarned<Result<Base16ByteArray, Vec<ExtendedSourceDiagnostic>>>>,
+> {
+    let options = match options.try_unpack() {
+        Ok(options) => options,
+        Err(err) => return JavaResult::pack(Err(err)),
+    };
+    if options.embed_source {
+        // Only the sources this compilation reads are attached.
+        reset_world(world_ptr);
+    }
+    JavaResult::pack(Ok(compile_paged(world_ptr, |world, document, warnings| {
+        let attachments = if options.embed_source {
+            source_attachments(world, &options)?
+        } else {
+            vec![]
+        };
+        export_pdf(document, &options, &attachments, warnings)
+    })))
+}
+
+/// The main file and, with `embed_dependencies`, the other project files the
+/// compilation read, to be attached as the sources of the PDF.
+fn source_attachments(
+    world: &JavaWorld,
+    options: &PdfExportOptions,
+) -> SourceResult<Vec<Packed<EmbedElem>>> {
+    let ids = if options.embed_dependencies {
+        world.dependencies()
+    } else {
+        vec![world.main()]
+    };
+    ids.into_iter()
+        .map(|id| {
+            let data = world.file(id).at(Span::detached())?;
+            let path = id.vpath().as_rootless_path().to_string_lossy().replace('\\', "/");
+            let path = EcoString::from(path);
+            let embed = EmbedElem::new(Derived::new(path.clone(), path), data)
+                .with_relationship(Some(EmbeddedFileRelationship::Source))
+                .with_mime_type(Some("text/vnd.typst".into()));
+            Ok(Packed::new(embed))
+        })
+        .collect()
+}
+
+/// Exports every page in `from..to` as a PDF of its own, which embeds only the
+/// fonts and images of that page. Links to other pages are dropped.
+#[no_mangle]
+pub extern "C" fn compile_pdf_pages(
+    world_ptr: *mut JavaWorld,
+    from: i32,
+    to: i32,
+) -> JavaResult<ExtendedWarned<Result<Vec<Base16ByteArray>, Vec<ExtendedSourceDiagnostic>>>>
+{
+    let options = PdfExportOptions::default();
+    JavaResult::pack(compile_paged(world_ptr, |_, document, warnings| {
+        let start = (from.max(0) as usize).min(document.pages.len());
+        let end = (to.max(0) as usize).min(document.pages.len());
+        (start..end)
+            .map(|index| {
+                let page = NonZeroUsize::new(index + 1);
+                let ranges = PageRanges::new(vec![page..=page]);
+                export_range(&document, &options, Some(ranges), &[], warnings)
+            })
+            .collect()
+    }))
+}
+
+pub fn export_pdf(
+    mut document: PagedDocument,
+    options: &PdfExportOptions,
+    attachments: &[Packed<EmbedElem>],
+    warnings: &mut EcoVec<SourceDiagnostic>,
+) -> SourceResult<Base16ByteArray> {
+    if options.disable_links {
+        for page in &mut document.pages {
+            page.frame =
+                filter_frame(&page.frame, &|item| !matches!(item, FrameItem::Link(..)));
+        }
+    }
+    if options.strip_metadata {
+        document.info.author.clear();
+        document.info.date = Smart::Custom(None);
+    }
+    export_range(&document, options, None, attachments, warnings)
+}
+
+fn export_range(
+    document: &PagedDocument,
+    options: &PdfExportOptions,
+    page_ranges: Option<PageRanges>,
+    attachments: &[Packed<EmbedElem>],
+    warnings: &mut EcoVec<SourceDiagnostic>,
+) -> SourceResult<Base16ByteArray> {
+    let standards = PdfStandards::new(&options.standards)
+        .map_err(|err| eco_vec![SourceDiagnostic::error(Span::detached(), err)])?;
+    let pdf_options = PdfOptions {
+        standards,
+        page_ranges,
+        omit_creator: options.strip_metadata,
+        attachments,
+        ..PdfOptions::default()
+    };
+    let exported = typst_pdf::pdf_with_warnings(document, &pdf_options);
+    warnings.extend(exported.warnings);
+    exported.output.map(Base16ByteArray)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkAnnotation {
+    /// Zero-based index of the page the link is on.
+    pub page: i64,
+    pub rect: ExtendedRect,
+    pub target: LinkTarget,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum LinkTarget {
+    Url {
+        url: String,
+    },
+    /// A point in the document, `page` is zero-based.
+    Internal {
+        page: i64,
+        x: f64,
+        y: f64,
+    },
+}
+
+/// Lists the link annotations the PDF export emits, in page order.
+#[no_mangle]
+pub extern "C" fn pdf_links(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<ExtendedWarned<Result<Vec<LinkAnnotation>, Vec<ExtendedSourceDiagnostic>>>>
+{
+    JavaResult::pack(compile_paged(world_ptr, |_, document, _| {
+        Ok(collect_links(&document))
+    }))
+}
+
+fn collect_links(document: &PagedDocument) -> Vec<LinkAnnotation> {
+    let mut links = vec![];
+    for (index, page) in document.pages.iter().enumerate() {
+        walk_frame(&page.frame, Transform::identity(), &mut |ts, pos, item| {
+            let FrameItem::Link(dest, size) = item else { return };
+            let target = match dest {
+                Destination::Url(url) => LinkTarget::Url { url: url.to_string() },
+                Destination::Position(position) => internal_target(*position),
+                Destination::Location(location) => {
+                    internal_target(document.introspector.position(*location))
+                }
+            };
+            links.push(LinkAnnotation {
+                page: index as i64,
+                rect: ExtendedRect::bounding(ts, pos, *size),
+                target,
+            });
+        });
+    }
+    links
+}
+
+fn internal_target(position: Position) -> LinkTarget {
+    LinkTarget::Internal {
+        page: position.page.get() as i64 - 1,
+        x: position.point.x.to_pt(),
+        y: position.point.y.to_pt(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{add_file, json, raw, world};
+
+    /// The exported PDF, whose file specifications are not compressed.
+    fn pdf_text(result: &serde_json::Value) -> String {
+        let pdf = hex::decode(result["Ok"]["output"]["Ok"].as_str().unwrap()).unwrap();
+        String::from_utf8_lossy(&pdf).into_owned()
+    }
+
+    #[test]
+    fn test_compile_pdf_pages_exports_one_pdf_per_page() {
+        let world = world(
+            "#set page(width: 100pt, height: 200pt)\nA\n\
+             #set page(width: 300pt)\nB\n#pagebreak()\nC",
+        );
+        let result = json(compile_pdf_pages(world, 0, 3));
+        let pdfs = result["output"]["Ok"].as_array().unwrap();
+        assert_eq!(pdfs.len(), 3);
+        for (pdf, width) in pdfs.iter().zip([100, 300, 300]) {
+            let bytes = hex::decode(pdf.as_str().unwrap()).unwrap();
+            let pdf = String::from_utf8_lossy(&bytes);
+            assert!(pdf.starts_with("%PDF-"));
+            assert!(pdf.trim_end().ends_with("%%EOF"));
+            assert_eq!(pdf.matches("/Type /Page\n").count(), 1);
+            assert!(pdf.contains("/Count 1\n"));
+            assert!(pdf.contains(&format!("/MediaBox [0 0 {width} 200]")));
+        }
+        // Only the pages in the range are exported.
+        let result = json(compile_pdf_pages(world, 1, 2));
+        assert_eq!(result["output"]["Ok"].as_array().unwrap().len(), 1);
+        free_world(world);
+    }
+
+    #[test]
+    fn test_strip_metadata_leaves_no_identifying_fields() {
+        let world = world(
+            "#set document(title: \"Report\", author: \"Jane Doe\", \
+             date: datetime(year: 2020, month: 1, day: 1))\nHi",
+        );
+        let identifying = [
+            "Jane Doe",
+            "/Author",
+            "/Creator",
+            "CreatorTool",
+            "/CreationDate",
+            "/ModDate",
+        ];
+        let pdf = pdf_text(&json(compile_pdf(world, raw("{}"))));
+        assert!(identifying.iter().all(|field| pdf.contains(field)));
+        let pdf = pdf_text(&json(compile_pdf(world, raw(r#"{"strip_metadata": true}"#))));
+        for field in identifying {
+            assert!(!pdf.contains(field), "{field} is left");
+        }
+        // The title is part of the content.
+        assert!(pdf.contains("/Title (Report)"));
+        free_world(world);
+    }
+
+    #[test]
+    fn test_embed_source_attaches_the_sources() {
+        let world = world("#include \"chapter.typ\"");
+        add_file(world, "/chapter.typ", "Chapter");
+        let result = json(compile_pdf(world, raw(r#"{"embed_source": true}"#)));
+        let pdf = pdf_text(&result);
+        assert!(pdf.contains("/EmbeddedFile"));
+        assert!(pdf.contains("(main.typ)"));
+        assert!(!pdf.contains("(chapter.typ)"));
+
+        let options = r#"{"embed_source": true, "embed_dependencies": true}"#;
+        let pdf = pdf_text(&json(compile_pdf(world, raw(options))));
+        assert!(pdf.contains("(main.typ)"));
+        assert!(pdf.contains("(chapter.typ)"));
+        free_world(world);
+    }
+
+    #[test]
+    fn test_tagged_lists_have_items() {
+        let world = world("- A\n- B\n\n+ C");
+        let result = json(compile_pdf(world, raw(r#"{"standards": ["ua-1"]}"#)));
+        let pdf = pdf_text(&result);
+        let count = |role: &str| pdf.matches(&format!("/S /{role}\n")).count();
+        assert_eq!(count("L"), 2);
+        // Each item has its bullet or number as a label and its body.
+        assert_eq!(count("LI"), 3);
+        assert_eq!(count("Lbl"), 3);
+        assert_eq!(count("LBody"), 3);
+        free_world(world);
+    }
+}
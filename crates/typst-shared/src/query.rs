@@ -1,25 +1,49 @@
 use std::mem;
+use crate::compile::{compile_paged, compile_reporting, run_compilation};
+use crate::exception::Except;
 use crate::extended_info::{ExtendedSourceDiagnostic, ExtendedWarned, Resolve};
 use crate::java_world::JavaWorld;
-use crate::memory_management::{JavaResult, ThickBytePtr};
+use crate::memory_management::{evict_after_compile, to_json, JavaResult, ThickBytePtr};
+use crate::throw;
 use serde::Serialize;
 use typst::comemo::Track;
 use typst::diag::{EcoString, HintedStrResult,  Warned};
-use typst::foundations::{Content, IntoValue, LocatableSelector, Scope};
+use typst::foundations::{Content, IntoValue, Label, LocatableSelector, Scope, Selector};
 use typst::layout::PagedDocument;
 use typst::routines::EvalMode;
 use typst::syntax::Span;
-use typst::utils::tick;
+use typst::utils::{tick, PicoStr};
 use typst::World;
 use typst_eval::eval_string;
 
+#[derive(Debug, Serialize)]
+pub struct QueryOutput {
+    /// The serialized matches.
+    pub result: String,
+    /// Whether there were more matches than `max_results`, which were left out.
+    pub truncated: bool,
+}
+
+/// Serializes the elements matching the selector as JSON (`fmt_type` 0),
+/// formatted as set with `set_pretty_json`, or as YAML (1). Only the first
+/// `max_results` matches are serialized, so that a selector matching lots of
+/// elements, like `selector(text)`, doesn't exhaust memory; non-positive limits
+/// serialize all matches.
 #[no_mangle]
 pub extern "C" fn query(
     world_ptr: *mut JavaWorld,
     selector_thick: ThickBytePtr,
     fmt_type: i32,
-) -> JavaResult<ExtendedWarned<Result<String, Vec<ExtendedSourceDiagnostic>>>> {
+    max_results: i32,
+) -> JavaResult<Except<ExtendedWarned<Result<QueryOutput, Vec<ExtendedSourceDiagnostic>>>>>
+{
     tick!();
+    if !(0..=1).contains(&fmt_type) {
+        return JavaResult::pack(Err(throw!(
+            "java.lang.IllegalArgumentException".to_string(),
+            Some(format!("Unexpected tag {fmt_type} for fmt_type"))
+        )));
+    }
     let mut world = unsafe { Box::from_raw(world_ptr) };
     tick!();
     let selector = selector_thick.to_str();
@@ -32,18 +56,26 @@ pub extern "C" fn query(
     // world.source(world.main()).map_err(|err| err.to_string()).unwrap();
 
     tick!();
-    let Warned { output, warnings } = typst::compile(&world);
+    let Warned { output, warnings } = run_compilation(world.timeout, || {
+        compile_reporting(world.as_ref(), world.warning_callback)
+    });
+    evict_after_compile();
 
     tick!();
     let serialized = output
         .map(|it| {
-            let data = retrieve(&world, selector.as_ref(), &it).unwrap();
-            format(data, fmt_type)
+            let mut data = retrieve(&world, selector.as_ref(), &it).unwrap();
+            let truncated = max_results > 0 && data.len() > max_results as usize;
+            if truncated {
+                data.truncate(max_results as usize);
+            }
+            let result = format(data, fmt_type, world.sort_keys);
+            QueryOutput { result, truncated }
         })
         .map_err(|it| it.resolve(world.as_ref()));
 
     tick!();
-    let result: ExtendedWarned<Result<String, Vec<ExtendedSourceDiagnostic>>> =
+    let result: ExtendedWarned<Result<QueryOutput, Vec<ExtendedSourceDiagnostic>>> =
         ExtendedWarned {
             output: serialized,
             warnings: warnings.resolve(world.as_ref()),
@@ -54,11 +86,40 @@ pub extern "C" fn query(
     let _ = Box::into_raw(world); // Not to drop the world!
 
     mem::forget(selector);
+    JavaResult::pack(Ok(result))
+}
+
+/// Finds the zero-based index of the page where the first element with the
+/// label is, or `None` if no element has it. The label may be given with or
+/// without its angle brackets.
+#[no_mangle]
+pub extern "C" fn label_page(
+    world_ptr: *mut JavaWorld,
+    label: ThickBytePtr,
+) -> JavaResult<ExtendedWarned<Result<Option<i64>, Vec<ExtendedSourceDiagnostic>>>> {
+    tick!();
+    let label_str = label.to_str();
+    let name = label_str
+        .strip_prefix('<')
+        .and_then(|it| it.strip_suffix('>'))
+        .unwrap_or(&label_str);
+    let label = Label::new(PicoStr::intern(name));
+
+    let result = compile_paged(world_ptr, |_, document, _| {
+        let introspector = &document.introspector;
+        Ok(introspector
+            .query(&Selector::Label(label))
+            .first()
+            .and_then(|elem| elem.location())
+            .map(|location| introspector.page(location).get() as i64 - 1))
+    });
+
+    mem::forget(label_str);
     JavaResult::pack(result)
 }
 
 /// Retrieve the matches for the selector.
-fn retrieve(
+pub fn retrieve(
     world: &dyn World,
     selector: &str,
     document: &PagedDocument,
@@ -89,19 +150,42 @@ fn retrieve(
 }
 
 /// Format the query result in the output format.
-fn format(elements: Vec<Content>, fmt_type: i32) -> String {
+fn format(elements: Vec<Content>, fmt_type: i32, sort_keys: bool) -> String {
     let mapped: Vec<_> =
         elements.into_iter().filter_map(|c| Some(c.into_value())).collect();
 
-    serialize(&mapped, fmt_type)
+    if sort_keys {
+        serialize(&crate::sort_keys::sort_keys(&mapped), fmt_type)
+    } else {
+        serialize(&mapped, fmt_type)
+    }
 }
 
 /// Serialize data to the output format.
 fn serialize(data: &impl Serialize, fmt_type: i32) -> String {
     match fmt_type {
-        0 => serde_json::to_string_pretty(data).expect("Unexpected error in serializing"),
-        1 => serde_json::to_string(data).expect("Unexpected error in serializing"),
-        2 => serde_yaml::to_string(data).expect("Unexpected error in serializing"),
-        _ => panic!("Unexpected tag {} for fmt_type", fmt_type),
+        0 => to_json(data).expect("Unexpected error in serializing"),
+        _ => serde_yaml::to_string(data).expect("Unexpected error in serializing"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, thick, world};
+
+    #[test]
+    fn test_query_truncates_to_max_results() {
+        let world = world("= One\n= Two\n= Three");
+        for (max, len, truncated) in [(2, 2, true), (3, 3, false), (0, 3, false)] {
+            let result = json(query(world, thick("heading"), 0, max));
+            let output = &result["Ok"]["output"]["Ok"];
+            let matches: serde_json::Value =
+                serde_json::from_str(output["result"].as_str().unwrap()).unwrap();
+            assert_eq!(matches.as_array().unwrap().len(), len);
+            assert_eq!(output["truncated"], truncated);
+        }
+        free_world(world);
     }
 }
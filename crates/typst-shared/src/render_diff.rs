@@ -0,0 +1,147 @@
+use crate::compile::compile_paged;
+use crate::extended_info::{ExtendedSourceDiagnostic, ExtendedWarned};
+use crate::java_world::JavaWorld;
+use crate::memory_management::{Base16ByteArray, JavaResult};
+use serde::{Deserialize, Serialize};
+use tiny_skia::{Pixmap, PremultipliedColorU8};
+use typst::utils::tick;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffResult {
+    pub width: i64,
+    pub height: i64,
+    /// How many pixels differ in any channel.
+    pub changed_pixels: i64,
+    /// The mean absolute difference of all channels of all pixels, from 0 for
+    /// identical renders to 1.
+    pub difference: f64,
+    /// A PNG of the first render, faded, with the changed pixels in red. `None`
+    /// if nothing changed.
+    pub image: Option<Base16ByteArray>,
+}
+
+/// Renders the zero-based `page` of the documents of both worlds and compares
+/// them pixel by pixel. A page that is missing from one of the documents counts
+/// as empty, as do the parts of the smaller render if the sizes differ.
+#[no_mangle]
+pub extern "C" fn render_diff(
+    world_ptr_a: *mut JavaWorld,
+    world_ptr_b: *mut JavaWorld,
+    page: i32,
+    ppi: f32,
+) -> JavaResult<ExtendedWarned<Result<DiffResult, Vec<ExtendedSourceDiagnostic>>>> {
+    tick!();
+    let render = |world_ptr| {
+        compile_paged(world_ptr, |_, document, _| {
+            Ok(usize::try_from(page)
+                .ok()
+                .and_then(|page| document.pages.get(page))
+                .map(|page| typst_render::render(page, ppi / 72.0)))
+        })
+    };
+    let a = render(world_ptr_a);
+    let b = render(world_ptr_b);
+    let mut warnings = a.warnings;
+    warnings.extend(b.warnings);
+    let output = match (a.output, b.output) {
+        (Ok(a), Ok(b)) => Ok(diff(a.as_ref(), b.as_ref())),
+        (a, b) => Err(a.err().into_iter().chain(b.err()).flatten().collect()),
+    };
+    JavaResult::pack(ExtendedWarned { output, warnings })
+}
+
+fn diff(a: Option<&Pixmap>, b: Option<&Pixmap>) -> DiffResult {
+    let size =
+        |pixmap: Option<&Pixmap>| pixmap.map_or((0, 0), |it| (it.width(), it.height()));
+    let ((width_a, height_a), (width_b, height_b)) = (size(a), size(b));
+    let (width, height) = (width_a.max(width_b), height_a.max(height_b));
+    let pixel = |pixmap: Option<&Pixmap>, x: u32, y: u32| {
+        pixmap
+            .and_then(|it| it.pixel(x, y))
+            .map_or([0; 4], |it| [it.red(), it.green(), it.blue(), it.alpha()])
+    };
+
+    let mut image = Pixmap::new(width, height);
+    let mut changed_pixels = 0;
+    let mut total = 0u64;
+    for y in 0..height {
+        for x in 0..width {
+            let (pa, pb) = (pixel(a, x, y), pixel(b, x, y));
+            let delta =
+                pa.iter().zip(pb).map(|(ca, cb)| ca.abs_diff(cb) as u64).sum::<u64>();
+            total += delta;
+            // Changed pixels in red, the rest as a faded gray of the first render.
+            let color = if delta > 0 {
+                changed_pixels += 1;
+                PremultipliedColorU8::from_rgba(255, 0, 0, 255)
+            } else {
+                let gray = ((pa[0] as u16 + pa[1] as u16 + pa[2] as u16) / 3) as u8;
+                let alpha = pa[3] / 4;
+                PremultipliedColorU8::from_rgba(gray / 4, gray / 4, gray / 4, alpha)
+            };
+            if let (Some(image), Some(color)) = (image.as_mut(), color) {
+                image.pixels_mut()[(y * width + x) as usize] = color;
+            }
+        }
+    }
+
+    let channels = width as u64 * height as u64 * 4;
+    DiffResult {
+        width: width as i64,
+        height: height as i64,
+        changed_pixels,
+        difference: if channels == 0 {
+            0.0
+        } else {
+            total as f64 / (channels * 255) as f64
+        },
+        image: image
+            .filter(|_| changed_pixels > 0)
+            .and_then(|it| it.encode_png().ok())
+            .map(Base16ByteArray),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, world};
+    use tiny_skia::Color;
+
+    #[test]
+    fn test_identical_renders_have_no_diff() {
+        let (a, b) = (world("Same"), world("Same"));
+        let result = json(render_diff(a, b, 0, 36.0));
+        let result = &result["output"]["Ok"];
+        assert_eq!(result["changed_pixels"], 0);
+        assert_eq!(result["difference"], 0.0);
+        assert!(result["image"].is_null());
+        free_world(a);
+        free_world(b);
+    }
+
+    #[test]
+    fn test_changed_text_is_marked() {
+        let (a, b) = (world("Before"), world("After"));
+        let result = json(render_diff(a, b, 0, 36.0));
+        let result = &result["output"]["Ok"];
+        assert!(result["changed_pixels"].as_i64().unwrap() > 0);
+        assert!(result["difference"].as_f64().unwrap() > 0.0);
+        let image = hex::decode(result["image"].as_str().unwrap()).unwrap();
+        assert!(image.starts_with(b"\x89PNG"));
+        free_world(a);
+        free_world(b);
+    }
+
+    #[test]
+    fn test_missing_render_counts_as_empty() {
+        let mut b = Pixmap::new(2, 3).unwrap();
+        b.fill(Color::BLACK);
+        let result = diff(None, Some(&b));
+        assert_eq!((result.width, result.height), (2, 3));
+        assert_eq!(result.changed_pixels, 6);
+        // Only the alpha channel differs.
+        assert_eq!(result.difference, 0.25);
+    }
+}
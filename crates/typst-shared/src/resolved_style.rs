@@ -0,0 +1,141 @@
+use crate::compile::{compile_reporting, run_compilation};
+use crate::exception::Except;
+use crate::extended_info::{ExtendedSourceDiagnostic, ExtendedWarned, Resolve};
+use crate::java_world::JavaWorld;
+use crate::memory_management::{evict_after_compile, JavaResult, ThickBytePtr};
+use crate::overlay_world::OverlayWorld;
+use crate::syntax::leaf_mode;
+use crate::throw;
+use std::mem;
+use typst::diag::Warned;
+use typst::foundations::{Label, Repr, Selector};
+use typst::introspection::MetadataElem;
+use typst::layout::PagedDocument;
+use typst::syntax::{is_ident, parse, LinkedNode, Side, Source};
+use typst::utils::{tick, PicoStr};
+use typst::World;
+
+/// Labels the probe inserted into the source.
+const PROBE_LABEL: &str = "__typst_shared_resolved_style";
+
+/// Returns the value that a style property, e.g. `text.size`, has at the byte
+/// `offset` of `source`, which is compiled in place of the world's main file.
+/// The value is given as Typst code, e.g. `14pt`, or `None` if the offset is
+/// in markup that is not laid out.
+///
+/// The offset must be in markup. Spans of the diagnostics past the offset are
+/// shifted by the probe that is inserted there.
+#[no_mangle]
+pub extern "C" fn resolved_style_at(
+    world_ptr: *mut JavaWorld,
+    source_ptr: ThickBytePtr,
+    offset: i64,
+    property_ptr: ThickBytePtr,
+) -> JavaResult<
+    Except<ExtendedWarned<Result<Option<String>, Vec<ExtendedSourceDiagnostic>>>>,
+> {
+    tick!();
+    let world = unsafe { Box::from_raw(world_ptr) };
+    let source = source_ptr.to_str();
+    let property = property_ptr.to_str();
+    let result = probe(&source, offset, &property).map(|probed| {
+        let probe_world = OverlayWorld::new(&world, Source::new(world.main(), probed));
+        resolve_probe(&probe_world)
+    });
+    let _ = Box::into_raw(world); // Not to drop the world!
+    mem::forget(source);
+    mem::forget(property);
+    JavaResult::pack(result)
+}
+
+/// Inserts markup at `offset` that records the property's value in metadata.
+fn probe(source: &str, offset: i64, property: &str) -> Except<String> {
+    if !property.contains('.') || !property.split('.').all(is_ident) {
+        return Err(throw!(
+            "java.lang.IllegalArgumentException".to_string(),
+            Some(format!("Expected a property like `text.size`, got `{property}`"))
+        ));
+    }
+    let offset = usize::try_from(offset)
+        .ok()
+        .filter(|&offset| offset <= source.len() && source.is_char_boundary(offset))
+        .ok_or_else(|| {
+            throw!(
+                "java.lang.IndexOutOfBoundsException".to_string(),
+                Some(format!("Offset {offset} is out of bounds"))
+            )
+        })?;
+    let root = parse(source);
+    let mode = LinkedNode::new(&root)
+        .leaf_at(offset, Side::Before)
+        .map_or(0, |leaf| leaf_mode(&leaf));
+    if mode != 0 {
+        return Err(throw!(
+            "java.lang.IllegalArgumentException".to_string(),
+            Some(format!("Offset {offset} is not in markup"))
+        ));
+    }
+    Ok(format!(
+        "{}#context [#metadata({property})<{PROBE_LABEL}>];{}",
+        &source[..offset],
+        &source[offset..],
+    ))
+}
+
+fn resolve_probe(
+    world: &OverlayWorld,
+) -> ExtendedWarned<Result<Option<String>, Vec<ExtendedSourceDiagnostic>>> {
+    let Warned { output, warnings } = run_compilation(world.world.timeout, || {
+        compile_reporting::<PagedDocument>(world, world.world.warning_callback)
+    });
+    evict_after_compile();
+    ExtendedWarned {
+        output: output
+            .map(|document| probed_value(&document))
+            .map_err(|it| it.resolve(world)),
+        warnings: warnings.resolve(world),
+    }
+}
+
+/// The value the probe recorded, if it was laid out.
+fn probed_value(document: &PagedDocument) -> Option<String> {
+    let label = Label::new(PicoStr::intern(PROBE_LABEL));
+    let elem = document.introspector.query_first(&Selector::Label(label))?;
+    Some(elem.to_packed::<MetadataElem>()?.value.repr().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, thick, world};
+
+    fn resolved(source: &str, offset: usize, property: &str) -> serde_json::Value {
+        let world = world("");
+        let result =
+            json(resolved_style_at(world, thick(source), offset as i64, thick(property)));
+        free_world(world);
+        result
+    }
+
+    #[test]
+    fn test_text_size_in_set_rule_region() {
+        let source = "Before #[#set text(size: 14pt)\nInside] after";
+        let inside = source.find("Inside").unwrap();
+        let result = resolved(source, inside, "text.size");
+        assert_eq!(result["Ok"]["output"]["Ok"], "14pt");
+        let result = resolved(source, source.len(), "text.size");
+        assert_eq!(result["Ok"]["output"]["Ok"], "11pt");
+    }
+
+    #[test]
+    fn test_offsets_outside_markup_fail() {
+        let source = "#set text(size: 14pt)";
+        let result = resolved(source, 5, "text.size");
+        assert_eq!(result["Err"]["class"], "java.lang.IllegalArgumentException");
+        let result = resolved(source, source.len() + 1, "text.size");
+        assert_eq!(result["Err"]["class"], "java.lang.IndexOutOfBoundsException");
+        let result = resolved(source, 0, "size");
+        assert_eq!(result["Err"]["class"], "java.lang.IllegalArgumentException");
+    }
+}
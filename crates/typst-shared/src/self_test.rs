@@ -0,0 +1,143 @@
+use crate::exception::Except;
+use crate::memory_management::JavaResult;
+use crate::memory_world::MemoryWorld;
+use crate::throw;
+use serde::{Deserialize, Serialize};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::Arc;
+use typst::diag::Warned;
+use typst::foundations::Dict;
+use typst::layout::PagedDocument;
+use typst::utils::tick;
+use typst::Library;
+use typst_kit::fonts::Fonts;
+use typst_pdf::PdfOptions;
+
+/// The document compiled by [`self_test`]. It touches text, math and shapes,
+/// so that most of the font and rendering machinery is exercised.
+const SELF_TEST_SOURCE: &str =
+    "= Self test\nHello, $x^2 + y^2$! #box(width: 1cm, height: 1cm, fill: red)";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub version: String,
+    pub subsystems: Vec<SubsystemReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubsystemReport {
+    pub name: String,
+    pub passed: bool,
+    pub message: Option<String>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.subsystems.iter().all(|it| it.passed)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn self_test() -> JavaResult<Except<SelfTestReport>> {
+    tick!();
+    let result = catch_unwind(run_self_test).map_err(|err| {
+        throw!(
+            "java.lang.IllegalStateException".to_string(),
+            Some(format!("Self test aborted: {}", panic_message(err.as_ref())))
+        )
+    });
+    tick!();
+    JavaResult::pack(result)
+}
+
+fn run_self_test() -> SelfTestReport {
+    let mut subsystems = vec![];
+
+    let fonts = Fonts::searcher()
+        .include_system_fonts(true)
+        .search_with(&(vec![] as Vec<PathBuf>));
+    let font_count = fonts.fonts.len();
+    subsystems.push(check("fonts", || {
+        if font_count == 0 {
+            return Err("no fonts were discovered".to_string());
+        }
+        Ok(format!("{font_count} fonts discovered"))
+    }));
+
+    let library = Library::builder().with_inputs(Dict::new()).build();
+    let world = MemoryWorld::new(library, Arc::new(fonts), SELF_TEST_SOURCE.to_string());
+
+    let mut document = None;
+    subsystems.push(check("compile", || {
+        let Warned { output, .. } = typst::compile::<PagedDocument>(&world);
+        let doc = output.map_err(|errors| describe_errors(&errors))?;
+        let pages = doc.pages.len();
+        document = Some(doc);
+        Ok(format!("{pages} pages laid out"))
+    }));
+
+    match &document {
+        Some(document) => {
+            subsystems.push(check("png", || {
+                let pixmap = typst_render::render(&document.pages[0], 1.0);
+                let buf = pixmap.encode_png().map_err(|err| err.to_string())?;
+                Ok(format!("{} bytes encoded", buf.len()))
+            }));
+            subsystems.push(check("pdf", || {
+                let buf = typst_pdf::pdf(document, &PdfOptions::default())
+                    .map_err(|errors| describe_errors(&errors))?;
+                if !buf.starts_with(b"%PDF-") {
+                    return Err("output does not start with a PDF header".to_string());
+                }
+                Ok(format!("{} bytes encoded", buf.len()))
+            }));
+        }
+        None => {
+            for name in ["png", "pdf"] {
+                subsystems.push(SubsystemReport {
+                    name: name.to_string(),
+                    passed: false,
+                    message: Some("skipped, compilation failed".to_string()),
+                });
+            }
+        }
+    }
+
+    SelfTestReport {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        subsystems,
+    }
+}
+
+/// Runs a single check, turning both errors and panics into a failed report.
+fn check(name: &str, f: impl FnOnce() -> Result<String, String>) -> SubsystemReport {
+    let (passed, message) = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(message)) => (true, message),
+        Ok(Err(message)) => (false, message),
+        Err(err) => (false, format!("panicked: {}", panic_message(err.as_ref()))),
+    };
+    SubsystemReport {
+        name: name.to_string(),
+        passed,
+        message: Some(message),
+    }
+}
+
+fn describe_errors(errors: &[typst::diag::SourceDiagnostic]) -> String {
+    errors
+        .iter()
+        .map(|it| it.message.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn panic_message(err: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = err.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = err.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
@@ -0,0 +1,30 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Converts the data into a JSON value in which the keys of every object, at any
+/// depth, are in lexicographic order.
+///
+/// Typst dictionaries keep their keys in the order they were inserted, which is
+/// what users expect to see, but makes the output depend on how a dictionary was
+/// built. Sorting makes equal dictionaries serialize identically at the cost of
+/// losing their authoring order.
+pub fn sort_keys(data: &impl Serialize) -> Value {
+    sort(serde_json::to_value(data).expect("Unexpected error in serializing"))
+}
+
+fn sort(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort(value)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sort).collect()),
+        other => other,
+    }
+}
@@ -1,7 +1,12 @@
+use crate::download::PackageConfig;
+use crate::draft::DraftLayout;
 use crate::extended_info::{ExtendedFileDescriptor, ExtendedFileResult};
+use crate::font_index::FontLoadStats;
 use crate::java_world::JavaWorld;
 use crate::memory_management::{Base16ByteArray, JavaResult, ThickBytePtr};
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::Write;
 use std::mem;
@@ -21,7 +26,7 @@ use typst_library::foundations::{
 use typst_library::model::{Numbering, NumberingPattern};
 use typst_library::routines::EvalMode;
 use typst_library::visualize::Color;
-use typst_library::{Features, Library, World};
+use typst_library::{Feature, Features, Library, World};
 use typst_macros::func;
 
 fn eval_with_world(string: &str, world: &dyn World) -> Value {
@@ -65,14 +70,114 @@ fn eval_no_world(string: &str) -> Value {
         file_callback: file_noop,
         fonts: fonts.fonts,
         files: Mutex::new(HashMap::new()),
+        virtual_files: Mutex::new(HashMap::new()),
         now: None,
         package_storage: None,
+        package_config: PackageConfig::default(),
         auto_load_central: false,
+        sort_keys: false,
+        link_map: HashMap::new(),
+        watermark: None,
+        page_size: None,
+        draft: DraftLayout::default(),
+        style_overrides: 0,
+        font_stats: FontLoadStats::default(),
+        timeout: None,
+        warning_callback: None,
     };
 
     eval_with_world(string, &java_world)
 }
 
+/// Features supported by this build, with the names they are known by in the CLI.
+const FEATURES: &[(Feature, &str)] = &[(Feature::Html, "html")];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FeatureInfo {
+    pub name: String,
+    /// Index of the bit representing this feature in `create_stdlib`'s bitset.
+    pub bit: i32,
+}
+
+#[no_mangle]
+pub extern "C" fn available_features() -> JavaResult<Vec<FeatureInfo>> {
+    let features = FEATURES
+        .iter()
+        .map(|(feature, name)| FeatureInfo {
+            name: name.to_string(),
+            bit: feature_bit(*feature) as i32,
+        })
+        .collect();
+    JavaResult::pack(features)
+}
+
+/// The index Typst uses for the feature in the `Features` bitset.
+fn feature_bit(feature: Feature) -> usize {
+    let Features(set) = [feature].into_iter().collect();
+    (0..).find(|&i| set.contains(i)).unwrap()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScopeEntry {
+    pub name: String,
+    /// One of `"function"`, `"type"`, `"module"` or `"value"`.
+    pub kind: String,
+    /// The category of the definition in the reference, e.g. `"layout"`.
+    pub category: Option<String>,
+    pub repr: String,
+    /// The parameters of native functions, like `text(..body, font: ..)`.
+    pub signature: Option<String>,
+}
+
+/// Lists the definitions of the global scope of a library without features, in
+/// the order they are defined.
+#[no_mangle]
+pub extern "C" fn list_global_scope() -> JavaResult<Vec<ScopeEntry>> {
+    let library = Library::builder().build();
+    let entries = library
+        .global
+        .scope()
+        .iter()
+        .map(|(name, binding)| {
+            let value = binding.read();
+            let kind = match value {
+                Value::Func(_) => "function",
+                Value::Type(_) => "type",
+                Value::Module(_) => "module",
+                _ => "value",
+            };
+            ScopeEntry {
+                name: name.to_string(),
+                kind: kind.to_string(),
+                category: binding.category().map(|it| it.name().to_string()),
+                repr: value.repr().to_string(),
+                signature: signature(name, value),
+            }
+        })
+        .collect();
+    JavaResult::pack(entries)
+}
+
+/// The parameters of a native function, or of a type's constructor.
+fn signature(name: &str, value: &Value) -> Option<String> {
+    let func = match value {
+        Value::Func(func) => func.clone(),
+        Value::Type(ty) => ty.constructor().ok()?,
+        _ => return None,
+    };
+    let params = func
+        .params()?
+        .iter()
+        .map(|param| match (param.variadic, param.positional) {
+            (true, _) => format!("..{}", param.name),
+            (false, true) => param.name.to_string(),
+            (false, false) => format!("{}: ..", param.name),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(format!("{name}({params})"))
+}
+
 #[no_mangle]
 pub extern "C" fn create_stdlib(
     features: c_int,
@@ -85,9 +190,10 @@ pub extern "C" fn create_stdlib(
 
     let mut features_bitset = SmallBitSet::default();
     tick!();
-    for i in 0..1 {
+    for (feature, _) in FEATURES {
+        let i = feature_bit(*feature);
         if features >> i & 1 == 1 {
-            features_bitset.insert(i as usize)
+            features_bitset.insert(i)
         }
     }
     tick!("{:?}", features_bitset);
@@ -134,21 +240,47 @@ fn test_repr(lhs: Value, rhs: Value) -> StrResult<NoneValue> {
     Ok(NoneValue)
 }
 
+thread_local! {
+    /// Lines printed by `print` on this thread, if they are being captured.
+    static PRINTED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, collecting everything `print` emits on this thread meanwhile
+/// instead of writing it to stdout.
+///
+/// Note that evaluation is memoized, so `print` calls of a module that has
+/// already been evaluated with the same inputs are not repeated.
+pub fn capture_prints<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    let outer = PRINTED.with(|it| it.replace(Some(vec![])));
+    let result = f();
+    let printed = PRINTED.with(|it| it.replace(outer)).unwrap_or_default();
+    (result, printed)
+}
+
 #[func]
 fn print(#[variadic] values: Vec<Value>) -> NoneValue {
-    let mut out = std::io::stdout().lock();
-    write!(out, "> ").unwrap();
-    for (i, value) in values.into_iter().enumerate() {
-        if i > 0 {
-            write!(out, ", ").unwrap();
+    let line = values
+        .into_iter()
+        .map(|value| format!("{value:?}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let captured = PRINTED.with(|it| match it.borrow_mut().as_mut() {
+        Some(printed) => {
+            printed.push(line.clone());
+            true
         }
-        write!(out, "{value:?}").unwrap();
+        None => false,
+    });
+    if !captured {
+        let mut out = std::io::stdout().lock();
+        writeln!(out, "> {line}").unwrap();
     }
-    writeln!(out).unwrap();
     NoneValue
 }
 
-/// Generates `count` lines of text based on the numbering.
+/// Generates `count` lines of text based on the numbering, which may be any
+/// pattern (`"A"`, `"i"`, `"一"`, ...) or a function. Lines are numbered from
+/// `start` on. No lines yield an empty string.
 #[func]
 fn lines(
     engine: &mut Engine,
@@ -157,10 +289,44 @@ fn lines(
     count: usize,
     #[default(Numbering::Pattern(NumberingPattern::from_str("A").unwrap()))]
     numbering: Numbering,
+    #[named]
+    #[default(1)]
+    start: usize,
 ) -> SourceResult<Value> {
-    (1..=count)
+    if count == 0 {
+        return Ok(Value::Str("".into()));
+    }
+    (start..start.saturating_add(count))
         .map(|n| numbering.apply(engine, context, &[n]))
         .collect::<SourceResult<Array>>()?
         .join(Some('\n'.into_value()), None)
         .at(span)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::json;
+
+    #[test]
+    fn test_global_scope_lists_elements() {
+        let entries = json(list_global_scope());
+        let entry = |name: &str| {
+            entries
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|entry| entry["name"] == name)
+                .unwrap()
+                .clone()
+        };
+        let text = entry("text");
+        assert_eq!(text["kind"], "function");
+        assert_eq!(text["category"], "text");
+        assert!(text["signature"].as_str().unwrap().contains("font: .."));
+        let figure = entry("figure");
+        assert_eq!(figure["kind"], "function");
+        assert_eq!(figure["category"], "model");
+        assert!(figure["signature"].as_str().unwrap().contains("caption: .."));
+    }
+}
@@ -1,6 +1,11 @@
-use crate::memory_management::{ThickBytePtr};
+use crate::exception::Except;
+use crate::memory_management::{JavaResult, ThickBytePtr};
+use crate::throw;
+use serde::{Deserialize, Serialize};
 use std::mem;
-use typst::syntax::{parse, parse_code, parse_math, SyntaxKind, SyntaxNode};
+use typst::syntax::{
+    ast, parse, parse_code, parse_math, LinkedNode, Side, Source, SyntaxKind, SyntaxNode,
+};
 use typst::utils::tick;
 use crate::memory_management::CVec;
 
@@ -194,6 +199,10 @@ fn flatten_into(
     }
 }
 
+fn encode_mark(mark: SyntaxMark, offset: i32) -> i64 {
+    ((mark.encode() as i64) << 32) + offset as i64
+}
+
 #[repr(C)]
 pub struct CFlattenedSyntaxTree {
     pub marks: CVec<i64>,
@@ -202,11 +211,7 @@ pub struct CFlattenedSyntaxTree {
 }
 
 fn cfy(tree: FlattenedSyntaxTree) -> CFlattenedSyntaxTree {
-    let marks: Vec<i64> = tree
-        .marks
-        .iter()
-        .map(|it| ((it.0.encode() as i64) << 32) + it.1 as i64)
-        .collect();
+    let marks: Vec<i64> = tree.marks.iter().map(|it| encode_mark(it.0, it.1)).collect();
     CFlattenedSyntaxTree {
         marks: marks.into(),
         errors: tree.errors.into(),
@@ -218,14 +223,531 @@ fn cfy(tree: FlattenedSyntaxTree) -> CFlattenedSyntaxTree {
 pub extern "C" fn parse_syntax(string: ThickBytePtr, mode: i32) -> CFlattenedSyntaxTree {
     tick!("{:?}, {}", string, mode);
     let input = string.to_str();
-    let node = match mode {
-        0 => parse(input.as_str()),      // Content
-        1 => parse_code(input.as_str()), // Code
-        2 => parse_math(input.as_str()), // Math
+    let node = parse_in_mode(input.as_str(), mode);
+    mem::forget(input);
+    cfy(flattened_tree(node))
+}
+
+pub fn parse_in_mode(input: &str, mode: i32) -> SyntaxNode {
+    match mode {
+        0 => parse(input),      // Content
+        1 => parse_code(input), // Code
+        2 => parse_math(input), // Math
         _ => panic!("Unexpected mode {} for syntax", mode),
+    }
+}
+
+/// Like [`parse_in_mode`], but an unknown mode is reported to Java instead of
+/// aborting the process.
+pub fn try_parse_in_mode(input: &str, mode: i32) -> Except<SyntaxNode> {
+    if (0..=2).contains(&mode) {
+        Ok(parse_in_mode(input, mode))
+    } else {
+        Err(throw!(
+            "java.lang.IllegalArgumentException".to_string(),
+            Some(format!("Unexpected mode {mode} for syntax"))
+        ))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeInfo {
+    pub kind: String,
+    pub start: i64,
+    pub end: i64,
+    /// Kinds of the node's ancestors, from its parent up to the root.
+    pub ancestors: Vec<String>,
+}
+
+/// Returns the innermost node containing the byte `offset`.
+/// An offset at the end of the source belongs to the nodes ending there.
+#[no_mangle]
+pub extern "C" fn node_at(
+    string: ThickBytePtr,
+    mode: i32,
+    offset: i64,
+) -> JavaResult<Option<NodeInfo>> {
+    tick!("{:?}, {}, {}", string, mode, offset);
+    let input = string.to_str();
+    let root = parse_in_mode(input.as_str(), mode);
+    let info = innermost_at(&LinkedNode::new(&root), offset.max(0) as usize).map(|node| {
+        let mut ancestors = vec![];
+        let mut parent = node.parent();
+        while let Some(it) = parent {
+            ancestors.push(it.kind().name().to_string());
+            parent = it.parent();
+        }
+        NodeInfo {
+            kind: node.kind().name().to_string(),
+            start: node.offset() as i64,
+            end: node.range().end as i64,
+            ancestors,
+        }
+    });
+    mem::forget(input);
+    JavaResult::pack(info)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StringLiteral {
+    /// The byte range of the literal including its quotes.
+    pub start: i64,
+    pub end: i64,
+    /// The literal's value with escape sequences resolved.
+    pub value: String,
+}
+
+/// Lists all string literals of the source in order of appearance.
+#[no_mangle]
+pub extern "C" fn string_literals(
+    string: ThickBytePtr,
+    mode: i32,
+) -> JavaResult<Except<Vec<StringLiteral>>> {
+    tick!("{:?}, {}", string, mode);
+    let input = string.to_str();
+    let literals = try_parse_in_mode(input.as_str(), mode).map(|root| {
+        let mut literals = vec![];
+        collect_string_literals(&LinkedNode::new(&root), &mut literals);
+        literals
+    });
+    mem::forget(input);
+    JavaResult::pack(literals)
+}
+
+fn collect_string_literals(node: &LinkedNode, literals: &mut Vec<StringLiteral>) {
+    if let Some(str) = node.cast::<ast::Str>() {
+        let range = node.range();
+        literals.push(StringLiteral {
+            start: range.start as i64,
+            end: range.end as i64,
+            value: str.get().to_string(),
+        });
+    }
+    for child in node.children() {
+        collect_string_literals(&child, literals);
+    }
+}
+
+/// Lists the headings, code blocks, equations, raw blocks and comments of the
+/// source, for an overview like a minimap. Each is given by three numbers:
+/// the kind, encoded as in [`parse_syntax`]'s node starts, and the byte range.
+/// Nested constructs follow the ones containing them.
+#[no_mangle]
+pub extern "C" fn span_outline(
+    string: ThickBytePtr,
+    mode: i32,
+) -> JavaResult<Except<Vec<i64>>> {
+    tick!("{:?}, {}", string, mode);
+    let input = string.to_str();
+    let outline = try_parse_in_mode(input.as_str(), mode).map(|root| {
+        let mut outline = vec![];
+        collect_outline(&LinkedNode::new(&root), &mut outline);
+        outline
+    });
+    mem::forget(input);
+    JavaResult::pack(outline)
+}
+
+fn collect_outline(node: &LinkedNode, outline: &mut Vec<i64>) {
+    if matches!(
+        node.kind(),
+        SyntaxKind::Heading
+            | SyntaxKind::CodeBlock
+            | SyntaxKind::Equation
+            | SyntaxKind::Raw
+            | SyntaxKind::LineComment
+            | SyntaxKind::BlockComment
+    ) {
+        let range = node.range();
+        outline.push(SyntaxMark::NodeStart(node.kind()).encode() as i64);
+        outline.push(range.start as i64);
+        outline.push(range.end as i64);
+    }
+    for child in node.children() {
+        collect_outline(&child, outline);
+    }
+}
+
+/// Returns the mode that text typed at the byte `offset` of a markup document
+/// would be in, numbered as for `parse_syntax`: 0 for markup, 1 for code and
+/// 2 for math.
+///
+/// The mode is decided by the character before the offset. Right after an
+/// opening delimiter (`#`, `$`, `[`, `{`), the offset is inside the delimited
+/// syntax. Right after a closing one (`$`, `]`, `}`), it is in the syntax that
+/// contains the delimited one, e.g. in the arguments of `#f[..]`, which are code.
+#[no_mangle]
+pub extern "C" fn mode_at(string: ThickBytePtr, offset: i64) -> i32 {
+    tick!("{:?}, {}", string, offset);
+    let input = string.to_str();
+    let root = parse(input.as_str());
+    let offset = (offset.max(0) as usize).min(input.len());
+    let mode = LinkedNode::new(&root)
+        .leaf_at(offset, Side::Before)
+        .map_or(0, |leaf| leaf_mode(&leaf));
+    mem::forget(input);
+    mode
+}
+
+/// The mode of text typed right after `leaf`, see [`mode_at`].
+pub fn leaf_mode(leaf: &LinkedNode) -> i32 {
+    if leaf.kind() == SyntaxKind::Hash {
+        return 1;
+    }
+    let mut node = Some(leaf.clone());
+    let delimiter = matches!(
+        leaf.kind(),
+        SyntaxKind::Dollar
+            | SyntaxKind::LeftBracket
+            | SyntaxKind::RightBracket
+            | SyntaxKind::LeftBrace
+            | SyntaxKind::RightBrace
+    );
+    if let Some(parent) = leaf.parent().filter(|_| delimiter) {
+        let inner = match parent.kind() {
+            SyntaxKind::Equation => Some(2),
+            SyntaxKind::ContentBlock => Some(0),
+            SyntaxKind::CodeBlock => Some(1),
+            _ => None,
+        };
+        if let Some(inner) = inner {
+            // Delimiters are the first and last child of the node they delimit.
+            if leaf.index() == 0 {
+                return inner;
+            }
+            node = parent.parent().cloned();
+        }
+    }
+    while let Some(current) = node {
+        match current.kind() {
+            SyntaxKind::Markup => return 0,
+            SyntaxKind::Code => return 1,
+            SyntaxKind::Math => return 2,
+            _ => {}
+        }
+        // Code embedded into markup or math with a hash.
+        if matches!(current.parent_kind(), Some(SyntaxKind::Markup | SyntaxKind::Math))
+            && current.prev_sibling_kind() == Some(SyntaxKind::Hash)
+        {
+            return 1;
+        }
+        node = current.parent().cloned();
+    }
+    0
+}
+
+/// What may be typed at a position, for filtering completion candidates.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CompletionContext {
+    /// Markup text.
+    Markup,
+    /// Math, where identifiers are symbols or functions.
+    Math,
+    /// An expression, like a variable, a literal or a call.
+    Expression,
+    /// The start of a statement, where keywords like `let`, `set` or `show`
+    /// may appear besides expressions.
+    Statement,
+    /// An argument of a call or set rule, positional or named.
+    Argument {
+        /// The source text of the called function, e.g. `text` or `math.op`.
+        function: String,
+    },
+    /// A field or method after a dot.
+    FieldAccess {
+        /// The source text of the expression before the dot.
+        base: String,
+    },
+}
+
+/// Tells what may be typed at the byte `offset` of the source, judging only by
+/// its syntax: markup, math, a statement, an expression, an argument of a call
+/// or a field of an expression.
+#[no_mangle]
+pub extern "C" fn completion_context(
+    string: ThickBytePtr,
+    mode: i32,
+    offset: i64,
+) -> JavaResult<Except<CompletionContext>> {
+    tick!("{:?}, {}, {}", string, mode, offset);
+    let input = string.to_str();
+    let offset = (offset.max(0) as usize).min(input.len());
+    let context = try_parse_in_mode(input.as_str(), mode).map(|root| {
+        match LinkedNode::new(&root).leaf_at(offset, Side::Before) {
+            Some(leaf) => leaf_context(&leaf, offset),
+            None => match mode {
+                1 => CompletionContext::Statement,
+                2 => CompletionContext::Math,
+                _ => CompletionContext::Markup,
+            },
+        }
+    });
+    mem::forget(input);
+    JavaResult::pack(context)
+}
+
+/// The context of text typed at `offset`, right after `leaf`.
+fn leaf_context(leaf: &LinkedNode, offset: usize) -> CompletionContext {
+    if let Some(base) = field_base(leaf, offset) {
+        return CompletionContext::FieldAccess {
+            base: base.get().clone().into_text().into(),
+        };
+    }
+    match leaf_mode(leaf) {
+        0 => return CompletionContext::Markup,
+        2 => return CompletionContext::Math,
+        _ => {}
+    }
+    if let Some(function) = called_function(leaf) {
+        return CompletionContext::Argument {
+            function: function.get().clone().into_text().into(),
+        };
+    }
+    if starts_statement(leaf) {
+        CompletionContext::Statement
+    } else {
+        CompletionContext::Expression
+    }
+}
+
+/// The expression whose field is typed: "text.|" or "text.fi|".
+fn field_base<'a>(leaf: &LinkedNode<'a>, offset: usize) -> Option<LinkedNode<'a>> {
+    let (dot, typed) = if leaf.kind() == SyntaxKind::Ident {
+        (leaf.prev_sibling()?, true)
+    } else {
+        (leaf.clone(), false)
+    };
+    let is_dot = dot.kind() == SyntaxKind::Dot
+        || (!typed
+            && matches!(dot.kind(), SyntaxKind::Text | SyntaxKind::MathText)
+            && dot.text() == "."
+            && dot.range().end == offset);
+    let base = dot.prev_sibling()?;
+    // In markup, only an embedded expression right before the dot has fields.
+    let embedded = base.parent_kind() != Some(SyntaxKind::Markup)
+        || (base.prev_sibling_kind() == Some(SyntaxKind::Hash)
+            && base.range().end == dot.offset());
+    (is_dot && embedded && base.is::<ast::Expr>()).then_some(base)
+}
+
+/// The function whose arguments contain `leaf` directly, or as the value of a
+/// named argument.
+fn called_function<'a>(leaf: &LinkedNode<'a>) -> Option<LinkedNode<'a>> {
+    if leaf.kind() == SyntaxKind::RightParen {
+        return None;
+    }
+    let mut args = leaf.parent()?;
+    if args.kind() == SyntaxKind::Named {
+        args = args.parent()?;
+    }
+    if args.kind() != SyntaxKind::Args {
+        return None;
+    }
+    let call = args.parent()?;
+    match call.kind() {
+        // The callee or target is the first child, before the arguments.
+        SyntaxKind::FuncCall | SyntaxKind::SetRule => {
+            call.children().find(|it| it.is::<ast::Expr>())
+        }
+        _ => None,
+    }
+}
+
+/// Whether text typed right after `leaf` starts a statement of code.
+fn starts_statement(leaf: &LinkedNode) -> bool {
+    let separates = |node: &LinkedNode| match node.kind() {
+        SyntaxKind::Hash | SyntaxKind::Semicolon => true,
+        SyntaxKind::LeftBrace => node.parent_kind() == Some(SyntaxKind::CodeBlock),
+        _ => false,
     };
+    match leaf.kind() {
+        // An identifier being typed, like a keyword.
+        SyntaxKind::Ident => {
+            leaf.parent_kind() == Some(SyntaxKind::Code)
+                || leaf.prev_sibling_kind() == Some(SyntaxKind::Hash)
+        }
+        kind if kind.is_trivia() => {
+            let newline = leaf.text().contains('\n')
+                && matches!(
+                    leaf.parent_kind(),
+                    Some(SyntaxKind::Code | SyntaxKind::CodeBlock)
+                );
+            newline || leaf.prev_leaf().is_none_or(|prev| separates(&prev))
+        }
+        _ => separates(leaf),
+    }
+}
+
+/// Descends to the innermost node whose range contains `offset`.
+pub fn innermost_at<'a>(root: &LinkedNode<'a>, offset: usize) -> Option<LinkedNode<'a>> {
+    let len = root.len();
+    if offset > len {
+        return None;
+    }
+    let contains = |node: &LinkedNode| {
+        let range = node.range();
+        range.start <= offset && (offset < range.end || (offset == len && range.end == len))
+    };
+    let mut node = root.clone();
+    while let Some(child) = node.children().filter(|it| it.len() > 0).find(|it| contains(it)) {
+        node = child;
+    }
+    Some(node)
+}
+
+/// A document kept open for editing. Edits reparse it incrementally
+/// and report which marks of the flattened tree changed.
+pub struct SyntaxSession {
+    mode: i32,
+    /// Markup is reparsed incrementally through the source,
+    /// other modes are parsed anew on every edit.
+    source: Source,
+    tree: FlattenedSyntaxTree,
+}
+
+#[no_mangle]
+pub extern "C" fn open_syntax_session(
+    string: ThickBytePtr,
+    mode: i32,
+) -> *mut SyntaxSession {
+    tick!("{:?}, {}", string, mode);
+    let input = string.to_str();
+    let source = Source::detached(input.as_str());
     mem::forget(input);
-    cfy(flattened_tree(node))
+    let tree = flattened_tree(parse_in_mode(source.text(), mode));
+    Box::into_raw(Box::new(SyntaxSession { mode, source, tree }))
+}
+
+/// The difference between the flattened trees before and after an edit:
+/// the marks `removed_start..removed_end` of the previous tree are to be replaced
+/// with `marks`. Marks after the removed ones are kept, their offsets move by `shift`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HighlightDelta {
+    pub removed_start: i64,
+    pub removed_end: i64,
+    /// Encoded as by `parse_syntax`. Error marks refer to `errors` of this delta.
+    pub marks: Vec<i64>,
+    pub errors: Vec<String>,
+    pub shift: i64,
+}
+
+/// Replaces the bytes `start..end` with `replacement`.
+#[no_mangle]
+pub extern "C" fn edit_syntax_session(
+    session_ptr: *mut SyntaxSession,
+    start: i64,
+    end: i64,
+    replacement: ThickBytePtr,
+) -> JavaResult<Except<HighlightDelta>> {
+    tick!("{}, {}, {:?}", start, end, replacement);
+    let mut session = unsafe { Box::from_raw(session_ptr) };
+    let replacement_str = replacement.to_str();
+    let result = session.edit(start, end, replacement_str.as_str());
+    mem::forget(replacement_str);
+    let _ = Box::into_raw(session); // Not to drop the session!
+    JavaResult::pack(result)
+}
+
+impl SyntaxSession {
+    fn edit(
+        &mut self,
+        start: i64,
+        end: i64,
+        replacement: &str,
+    ) -> Except<HighlightDelta> {
+        let text = self.source.text();
+        let (start, end) = (start.max(0) as usize, end.max(0) as usize);
+        if start > end
+            || end > text.len()
+            || !text.is_char_boundary(start)
+            || !text.is_char_boundary(end)
+        {
+            return Err(throw!(
+                "java.lang.IndexOutOfBoundsException".to_string(),
+                Some(format!("Invalid range {start}..{end} of {} bytes", text.len()))
+            ));
+        }
+        let root = if self.mode == 0 {
+            self.source.edit(start..end, replacement);
+            self.source.root().clone()
+        } else {
+            let new_text = format!("{}{replacement}{}", &text[..start], &text[end..]);
+            self.source.replace(&new_text);
+            parse_in_mode(self.source.text(), self.mode)
+        };
+        let tree = flattened_tree(root);
+        let shift = replacement.len() as i32 - (end - start) as i32;
+        let delta = diff_trees(&self.tree, &tree, shift);
+        self.tree = tree;
+        Ok(delta)
+    }
+}
+
+/// Finds the longest unchanged prefix and suffix of the marks.
+fn diff_trees(
+    old: &FlattenedSyntaxTree,
+    new: &FlattenedSyntaxTree,
+    shift: i32,
+) -> HighlightDelta {
+    let max = old.marks.len().min(new.marks.len());
+    let prefix = (0..max).take_while(|&i| same_mark(old, i, new, i, 0)).count();
+    let suffix = (0..max - prefix)
+        .take_while(|&i| {
+            same_mark(old, old.marks.len() - 1 - i, new, new.marks.len() - 1 - i, shift)
+        })
+        .count();
+
+    let mut errors = vec![];
+    let marks = new.marks[prefix..new.marks.len() - suffix]
+        .iter()
+        .map(|&(mark, offset)| {
+            let mark = match mark {
+                SyntaxMark::Error(idx) => {
+                    errors.push(error_message(new, idx).to_string());
+                    SyntaxMark::Error(errors.len() as i32 - 1)
+                }
+                other => other,
+            };
+            encode_mark(mark, offset)
+        })
+        .collect();
+
+    HighlightDelta {
+        removed_start: prefix as i64,
+        removed_end: (old.marks.len() - suffix) as i64,
+        marks,
+        errors,
+        shift: shift as i64,
+    }
+}
+
+fn same_mark(
+    a: &FlattenedSyntaxTree,
+    i: usize,
+    b: &FlattenedSyntaxTree,
+    j: usize,
+    shift: i32,
+) -> bool {
+    let ((mark_a, offset_a), (mark_b, offset_b)) = (a.marks[i], b.marks[j]);
+    offset_a + shift == offset_b
+        && match (mark_a, mark_b) {
+            (SyntaxMark::NodeStart(x), SyntaxMark::NodeStart(y)) => x == y,
+            (SyntaxMark::NodeEnd, SyntaxMark::NodeEnd) => true,
+            (SyntaxMark::Error(x), SyntaxMark::Error(y)) => {
+                error_message(a, x) == error_message(b, y)
+            }
+            _ => false,
+        }
+}
+
+fn error_message(tree: &FlattenedSyntaxTree, idx: i32) -> &str {
+    let start = tree.errors_starts[idx as usize] as usize;
+    let end = tree
+        .errors_starts
+        .get(idx as usize + 1)
+        .map_or(tree.errors.len(), |&it| it as usize);
+    std::str::from_utf8(&tree.errors[start..end]).unwrap_or_default()
 }
 
 #[no_mangle]
@@ -234,3 +756,90 @@ pub extern "C" fn release_flattened_tree(tree: CFlattenedSyntaxTree) {
     let _errors: Vec<u8> = tree.errors.into();
     let _errors_starts: Vec<i32> = tree.errors_starts.into();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{json, thick};
+
+    #[test]
+    fn test_string_literals() {
+        let result = json(string_literals(thick(r#"#let x = "a\nb" + "c""#), 0));
+        let literals = result["Ok"].as_array().unwrap();
+        assert_eq!(literals.len(), 2);
+        assert_eq!(literals[0]["value"], "a\nb");
+        assert_eq!((&literals[0]["start"], &literals[0]["end"]), (&9.into(), &15.into()));
+        assert_eq!(literals[1]["value"], "c");
+    }
+
+    #[test]
+    fn test_string_literals_reject_unknown_modes() {
+        let result = json(string_literals(thick("\"a\""), 3));
+        assert_eq!(result["Err"]["class"], "java.lang.IllegalArgumentException");
+    }
+
+    #[test]
+    fn test_span_outline() {
+        let result = json(span_outline(thick("= Head\n#{ 1 }\n$x$ // c"), 0));
+        let expected: Vec<i64> = [
+            (SyntaxKind::Heading, 0, 6),
+            (SyntaxKind::CodeBlock, 8, 13),
+            (SyntaxKind::Equation, 14, 17),
+            (SyntaxKind::LineComment, 18, 22),
+        ]
+        .into_iter()
+        .flat_map(|(kind, start, end)| {
+            [SyntaxMark::NodeStart(kind).encode() as i64, start, end]
+        })
+        .collect();
+        assert_eq!(result["Ok"], serde_json::json!(expected));
+        let result = json(span_outline(thick("x"), 5));
+        assert_eq!(result["Err"]["class"], "java.lang.IllegalArgumentException");
+    }
+
+    #[test]
+    fn test_completion_context() {
+        let context = |text: &str, mode, offset| {
+            json(completion_context(thick(text), mode, offset))["Ok"].clone()
+        };
+        let call = "Hello #text(size: 1pt)";
+        assert_eq!(context(call, 0, 3), serde_json::json!({"kind": "markup"}));
+        assert_eq!(
+            context(call, 0, 12),
+            serde_json::json!({"kind": "argument", "function": "text"})
+        );
+        assert_eq!(
+            context("#text.fi", 0, 8),
+            serde_json::json!({"kind": "field_access", "base": "text"})
+        );
+        assert_eq!(context("$x + $", 0, 3), serde_json::json!({"kind": "math"}));
+        assert_eq!(context("", 1, 0), serde_json::json!({"kind": "statement"}));
+        let result = json(completion_context(thick("x"), 3, 0));
+        assert_eq!(result["Err"]["class"], "java.lang.IllegalArgumentException");
+    }
+
+    #[test]
+    fn test_mode_at_boundaries() {
+        let text = "a #f[b] $c$ #{d}";
+        let modes = [
+            (-5, 0),  // Clamped to the start.
+            (0, 0),   // Nothing before.
+            (2, 0),   // Before the hash.
+            (3, 1),   // After the hash.
+            (4, 1),   // After the function name.
+            (5, 0),   // After the opening bracket.
+            (6, 0),   // Before the closing bracket.
+            (7, 1),   // After it, in the arguments.
+            (9, 2),   // After the opening dollar.
+            (10, 2),  // Before the closing dollar.
+            (11, 0),  // After it.
+            (14, 1),  // After the opening brace.
+            (16, 0),  // After the closing brace, at the end.
+            (100, 0), // Clamped to the end.
+        ];
+        for (offset, mode) in modes {
+            assert_eq!(mode_at(thick(text), offset), mode, "at {offset}");
+        }
+        assert_eq!(mode_at(thick(""), 0), 0);
+    }
+}
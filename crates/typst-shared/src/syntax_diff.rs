@@ -0,0 +1,307 @@
+use crate::exception::Except;
+use crate::memory_management::{JavaResult, ThickBytePtr};
+use crate::syntax::parse_in_mode;
+use crate::throw;
+use serde::{Deserialize, Serialize};
+use std::mem;
+use typst::syntax::SyntaxNode;
+use typst::utils::tick;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HunkKind {
+    /// The node only exists in the new source.
+    Added,
+    /// The node only exists in the old source.
+    Removed,
+    /// The node was replaced by a different one, e.g. a changed identifier.
+    Changed,
+}
+
+/// A syntax node that differs between the sources. Ranges are byte offsets.
+/// Added nodes have an empty old range at the place they were inserted at,
+/// removed ones an empty new range.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub kind: HunkKind,
+    /// The kind of the node in the new source, or in the old one if it was removed.
+    pub node_kind: String,
+    pub old_start: i64,
+    pub old_end: i64,
+    pub new_start: i64,
+    pub new_end: i64,
+}
+
+/// Parses both sources in the given mode (as for `parse_syntax`) and compares
+/// the trees. Hunks are as small as possible: a changed argument of a call
+/// yields a hunk for the argument only, not for the call.
+#[no_mangle]
+pub extern "C" fn structural_diff(
+    old_source: ThickBytePtr,
+    new_source: ThickBytePtr,
+    mode: i32,
+) -> JavaResult<Except<Vec<DiffHunk>>> {
+    tick!("{:?}, {:?}, {}", old_source, new_source, mode);
+    let old_str = old_source.to_str();
+    let new_str = new_source.to_str();
+    let result = if (0..=2).contains(&mode) {
+        let mut hunks = vec![];
+        diff_nodes(
+            (&parse_in_mode(&old_str, mode), 0),
+            (&parse_in_mode(&new_str, mode), 0),
+            &mut hunks,
+        );
+        Ok(hunks)
+    } else {
+        Err(throw!(
+            "java.lang.IllegalArgumentException".to_string(),
+            Some(format!("Unexpected mode {mode} for syntax"))
+        ))
+    };
+    mem::forget(old_str);
+    mem::forget(new_str);
+    JavaResult::pack(result)
+}
+
+/// How many tokens differ between two sources.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TokenDiffStats {
+    /// Tokens that only exist in the new source.
+    pub added: i64,
+    /// Tokens that only exist in the old source.
+    pub removed: i64,
+    /// Tokens that were replaced by a different one, e.g. a changed word.
+    pub changed: i64,
+}
+
+/// Parses both sources in the given mode (as for `parse_syntax`) and counts the
+/// tokens that differ, ignoring whitespace and comments. Unlike
+/// [`structural_diff`], tokens are compared as one sequence, so a token that
+/// moved to a different node is unchanged.
+#[no_mangle]
+pub extern "C" fn token_diff_stats(
+    old_source: ThickBytePtr,
+    new_source: ThickBytePtr,
+    mode: i32,
+) -> JavaResult<Except<TokenDiffStats>> {
+    tick!("{:?}, {:?}, {}", old_source, new_source, mode);
+    let old_str = old_source.to_str();
+    let new_str = new_source.to_str();
+    let result = if (0..=2).contains(&mode) {
+        let (old_root, new_root) =
+            (parse_in_mode(&old_str, mode), parse_in_mode(&new_str, mode));
+        let (mut old_tokens, mut new_tokens) = (vec![], vec![]);
+        collect_tokens(&old_root, &mut old_tokens);
+        collect_tokens(&new_root, &mut new_tokens);
+        Ok(count_token_diff(&old_tokens, &new_tokens))
+    } else {
+        Err(throw!(
+            "java.lang.IllegalArgumentException".to_string(),
+            Some(format!("Unexpected mode {mode} for syntax"))
+        ))
+    };
+    mem::forget(old_str);
+    mem::forget(new_str);
+    JavaResult::pack(result)
+}
+
+/// Collects the leaves of the tree that are neither whitespace nor comments.
+fn collect_tokens<'a>(node: &'a SyntaxNode, tokens: &mut Vec<&'a SyntaxNode>) {
+    if node.children().len() == 0 {
+        if !node.kind().is_trivia() {
+            tokens.push(node);
+        }
+        return;
+    }
+    for child in node.children() {
+        collect_tokens(child, tokens);
+    }
+}
+
+/// Matches equal tokens by their longest common subsequence. In the runs in
+/// between, tokens at the same position changed and surplus ones were added or
+/// removed.
+fn count_token_diff(old: &[&SyntaxNode], new: &[&SyntaxNode]) -> TokenDiffStats {
+    // Equal ends are matched right away, which keeps the table below small for
+    // local edits.
+    let prefix = old.iter().zip(new).take_while(|(o, n)| o.spanless_eq(n)).count();
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(o, n)| o.spanless_eq(n))
+        .count();
+    let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+
+    let (n, m) = (old.len(), new.len());
+    // lcs[i][j] is the length of the common subsequence of old[i..] and new[j..].
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i].spanless_eq(new[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut stats = TokenDiffStats::default();
+    let mut count_gap = |old_gap: usize, new_gap: usize| {
+        stats.changed += old_gap.min(new_gap) as i64;
+        stats.added += new_gap.saturating_sub(old_gap) as i64;
+        stats.removed += old_gap.saturating_sub(new_gap) as i64;
+    };
+    let (mut i, mut j) = (0, 0);
+    let (mut gap_i, mut gap_j) = (0, 0);
+    while i < n && j < m {
+        if old[i].spanless_eq(new[j]) {
+            count_gap(i - gap_i, j - gap_j);
+            i += 1;
+            j += 1;
+            (gap_i, gap_j) = (i, j);
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    count_gap(n - gap_i, m - gap_j);
+    stats
+}
+
+/// A node together with its offset in the source.
+type Located<'a> = (&'a SyntaxNode, usize);
+
+fn diff_nodes(old: Located, new: Located, hunks: &mut Vec<DiffHunk>) {
+    if old.0.spanless_eq(new.0) {
+        return;
+    }
+    if old.0.kind() != new.0.kind()
+        || old.0.children().len() == 0
+        || new.0.children().len() == 0
+    {
+        hunks.push(hunk(HunkKind::Changed, new.0, old, new));
+        return;
+    }
+    diff_children(&located_children(old), &located_children(new), hunks);
+}
+
+fn located_children((node, offset): Located) -> Vec<Located> {
+    let mut offset = offset;
+    node.children()
+        .map(|child| {
+            let located = (child, offset);
+            offset += child.len();
+            located
+        })
+        .collect()
+}
+
+/// Matches equal children by their longest common subsequence and compares
+/// the runs in between.
+fn diff_children(old: &[Located], new: &[Located], hunks: &mut Vec<DiffHunk>) {
+    let (n, m) = (old.len(), new.len());
+    // lcs[i][j] is the length of the common subsequence of old[i..] and new[j..].
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i].0.spanless_eq(new[j].0) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let (mut gap_i, mut gap_j) = (0, 0);
+    while i < n && j < m {
+        if old[i].0.spanless_eq(new[j].0) {
+            diff_gap(&old[gap_i..i], &new[gap_j..j], (old[i].1, new[j].1), hunks);
+            i += 1;
+            j += 1;
+            (gap_i, gap_j) = (i, j);
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    let end = (end_of(old).unwrap_or_default(), end_of(new).unwrap_or_default());
+    diff_gap(&old[gap_i..], &new[gap_j..], end, hunks);
+}
+
+/// Compares runs of children that have no equal counterpart. Children at the
+/// same position in both runs are compared in depth if they are of the same
+/// kind, surplus ones were added or removed. `end` are the offsets right after
+/// the runs.
+fn diff_gap(
+    old: &[Located],
+    new: &[Located],
+    end: (usize, usize),
+    hunks: &mut Vec<DiffHunk>,
+) {
+    let common = old.len().min(new.len());
+    for (&o, &n) in old.iter().zip(new) {
+        diff_nodes(o, n, hunks);
+    }
+    let insertion = old.get(common).map_or(end.0, |it| it.1);
+    for &n in &new[common..] {
+        hunks.push(hunk(HunkKind::Added, n.0, (n.0, insertion), n));
+    }
+    let deletion = new.get(common).map_or(end.1, |it| it.1);
+    for &o in &old[common..] {
+        hunks.push(hunk(HunkKind::Removed, o.0, o, (o.0, deletion)));
+    }
+}
+
+fn end_of(run: &[Located]) -> Option<usize> {
+    run.last().map(|(node, offset)| offset + node.len())
+}
+
+/// Builds a hunk. For added and removed nodes, the node on the other side only
+/// provides the position of the empty range.
+fn hunk(kind: HunkKind, node: &SyntaxNode, old: Located, new: Located) -> DiffHunk {
+    let old_len = if kind == HunkKind::Added { 0 } else { old.0.len() };
+    let new_len = if kind == HunkKind::Removed { 0 } else { new.0.len() };
+    DiffHunk {
+        kind,
+        node_kind: node.kind().name().to_string(),
+        old_start: old.1 as i64,
+        old_end: (old.1 + old_len) as i64,
+        new_start: new.1 as i64,
+        new_end: (new.1 + new_len) as i64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{json, thick};
+
+    fn stats(old: &str, new: &str) -> (i64, i64, i64) {
+        let result = json(token_diff_stats(thick(old), thick(new), 1));
+        let stats = &result["Ok"];
+        let count = |key: &str| stats[key].as_i64().unwrap();
+        (count("added"), count("removed"), count("changed"))
+    }
+
+    #[test]
+    fn test_token_diff_stats_counts_a_small_change() {
+        let (old, new) = ("let x = f(1, 2) // note", "let x = g(1, 2, 3)");
+        assert_eq!(stats(old, new), (2, 0, 1));
+        assert_eq!(stats(new, old), (0, 2, 1));
+    }
+
+    #[test]
+    fn test_token_diff_stats_ignores_whitespace_and_comments() {
+        assert_eq!(stats("let x = 1", "let  x=1 /* why */"), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_token_diff_stats_rejects_unknown_modes() {
+        let result = json(token_diff_stats(thick("a"), thick("b"), 3));
+        assert_eq!(result["Err"]["class"], "java.lang.IllegalArgumentException");
+    }
+}
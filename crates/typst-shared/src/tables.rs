@@ -0,0 +1,135 @@
+use crate::compile::compile_paged;
+use crate::extended_info::{
+    ExtendedSourceDiagnostic, ExtendedSpan, ExtendedWarned, Resolve,
+};
+use crate::java_world::JavaWorld;
+use crate::memory_management::JavaResult;
+use serde::{Deserialize, Serialize};
+use typst::comemo::Track;
+use typst::diag::SourceResult;
+use typst::engine::{Engine, Route, Sink, Traced};
+use typst::foundations::{Content, NativeElement, Packed, StyleChain};
+use typst::introspection::{Introspector, Locator};
+use typst::layout::grid::resolve::{table_to_cellgrid, Cell, Entry};
+use typst::model::{TableCell, TableElem};
+use typst::World;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableData {
+    /// Zero-based index of the page the table starts on.
+    pub page: i64,
+    pub span: ExtendedSpan,
+    /// How many of the first rows are the table's header.
+    pub header_rows: i64,
+    /// The cells, row by row. Every row has a cell for each column.
+    pub rows: Vec<Vec<TableCellData>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableCellData {
+    pub text: String,
+    pub colspan: i64,
+    pub rowspan: i64,
+    /// For positions covered by a cell spanning several columns or rows, the
+    /// position of that cell. `text` and the spans then repeat that cell's.
+    pub merged_into: Option<CellPosition>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CellPosition {
+    pub row: i64,
+    pub column: i64,
+}
+
+/// Lists the document's tables in order, with the cells at the positions they
+/// are laid out at, as with automatic placement, `x`, `y` and spans.
+#[no_mangle]
+pub extern "C" fn extract_tables(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<ExtendedWarned<Result<Vec<TableData>, Vec<ExtendedSourceDiagnostic>>>> {
+    JavaResult::pack(compile_paged(world_ptr, |world, document, _| {
+        let introspector = &document.introspector;
+        introspector
+            .query(&TableElem::elem().select())
+            .into_iter()
+            .filter_map(|elem| {
+                let table = elem.to_packed::<TableElem>()?;
+                let page = introspector.page(elem.location()?).get() as i64 - 1;
+                Some(table_data(world, introspector, table).map(|(header_rows, rows)| {
+                    TableData {
+                        page,
+                        span: elem.span().resolve(world),
+                        header_rows,
+                        rows,
+                    }
+                }))
+            })
+            .collect()
+    }))
+}
+
+/// Positions the cells of the table, like its layout does.
+fn table_data(
+    world: &dyn World,
+    introspector: &Introspector,
+    table: &Packed<TableElem>,
+) -> SourceResult<(i64, Vec<Vec<TableCellData>>)> {
+    let traced = Traced::default();
+    let mut sink = Sink::new();
+    let mut engine = Engine {
+        routines: &typst::ROUTINES,
+        world: world.track(),
+        introspector: introspector.track(),
+        traced: traced.track(),
+        sink: sink.track_mut(),
+        route: Route::default(),
+    };
+    // Queried elements have their styles applied, so the default ones suffice.
+    let grid =
+        table_to_cellgrid(table, &mut engine, Locator::root(), StyleChain::default())?;
+
+    let columns = grid.non_gutter_column_count();
+    let header_rows = grid.header.as_ref().map_or(0, |header| {
+        let end = header.unwrap().end;
+        if grid.has_gutter {
+            end / 2
+        } else {
+            end
+        }
+    });
+    let cell_data = |cell: &Cell, merged_into| TableCellData {
+        text: cell_text(&cell.body),
+        colspan: cell.colspan.get() as i64,
+        rowspan: cell.rowspan.get() as i64,
+        merged_into,
+    };
+    let rows = grid
+        .entries
+        .chunks(columns.max(1))
+        .map(|row| {
+            row.iter()
+                .filter_map(|entry| match entry {
+                    Entry::Cell(cell) => Some(cell_data(cell, None)),
+                    Entry::Merged { parent } => {
+                        let Entry::Cell(cell) = grid.entries.get(*parent)? else {
+                            return None;
+                        };
+                        let position = CellPosition {
+                            row: (parent / columns) as i64,
+                            column: (parent % columns) as i64,
+                        };
+                        Some(cell_data(cell, Some(position)))
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    Ok((header_rows as i64, rows))
+}
+
+fn cell_text(body: &Content) -> String {
+    match body.to_packed::<TableCell>() {
+        Some(cell) => cell.body.plain_text().to_string(),
+        None => body.plain_text().to_string(),
+    }
+}
@@ -0,0 +1,81 @@
+//! Helpers for the tests, which call the exported functions the way Java does.
+
+use crate::extended_info::{ExtendedFileDescriptor, ExtendedFileError, ExtendedFileResult};
+use crate::java_world::{new_world, JavaWorld};
+use crate::memory_management::{Base16ByteArray, JavaResult, ThickBytePtr};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::marker::PhantomData;
+use typst::syntax::{FileId, VirtualPath};
+use typst::Library;
+
+/// Held by the tests that create compiled documents, so that the ones counting
+/// them with `live_compiled_docs` see no others.
+pub static COMPILED_DOCS: Mutex<()> = Mutex::new(());
+
+/// Passes JSON in like Java does. The negative ticket tells that there is
+/// nothing to release on the Java side, so no freer is needed.
+pub fn raw<T>(json: &str) -> JavaResult<T> {
+    JavaResult { ticket: -1, value: thick(json), phantom: PhantomData }
+}
+
+/// Passes a value in like Java does, see [`raw`].
+pub fn java<T: Serialize>(value: &T) -> JavaResult<T> {
+    raw(&serde_json::to_string(value).unwrap())
+}
+
+/// A string passed in by Java.
+pub fn thick(text: &str) -> ThickBytePtr {
+    ThickBytePtr::from_str(text.to_string())
+}
+
+/// The JSON handed to Java.
+pub fn json<T>(result: JavaResult<T>) -> serde_json::Value {
+    serde_json::from_str(&result.value.to_str()).unwrap()
+}
+
+/// The main file callback of [`world`].
+pub extern "C" fn main_file() -> JavaResult<ExtendedFileDescriptor> {
+    java(&ExtendedFileDescriptor { pack: None, path: "/main.typ".to_string() })
+}
+
+/// The file callback of [`world`], which finds no file.
+pub extern "C" fn no_file(path: ThickBytePtr) -> JavaResult<ExtendedFileResult<Base16ByteArray>> {
+    let path = path.to_str();
+    let result = java(&Err::<Base16ByteArray, _>(ExtendedFileError::NotFound {
+        path: path.clone(),
+    }));
+    // The caller releases the path.
+    std::mem::forget(path);
+    result
+}
+
+/// A world whose main file `/main.typ` has the given text. All files are served
+/// from memory, see [`add_file`]; the file callback finds none.
+pub fn world(main: &str) -> *mut JavaWorld {
+    world_with_library(Box::into_raw(Box::new(Library::builder().build())), main)
+}
+
+/// Like [`world`], but with the given library, e.g. one from `create_stdlib`.
+pub fn world_with_library(library: *mut Library, main: &str) -> *mut JavaWorld {
+    let result = new_world(
+        library,
+        main_file,
+        no_file,
+        raw("null"),
+        0,
+        raw("{}"),
+        raw("null"),
+    );
+    assert!(!result.ptr.is_null(), "failed to create the world");
+    let world = result.ptr as *mut JavaWorld;
+    add_file(world, "/main.typ", main);
+    world
+}
+
+/// Adds a file to the world, or replaces it.
+pub fn add_file(world: *mut JavaWorld, path: &str, data: impl Into<Vec<u8>>) {
+    let world = unsafe { &*world };
+    let id = FileId::new(None, VirtualPath::new(path));
+    world.virtual_files.lock().insert(id, data.into());
+}
@@ -0,0 +1,49 @@
+use crate::compile::compile_paged;
+use crate::extended_info::{ExtendedSourceDiagnostic, ExtendedWarned};
+use crate::frames::{walk_frame, ExtendedRect};
+use crate::java_world::JavaWorld;
+use crate::memory_management::JavaResult;
+use serde::{Deserialize, Serialize};
+use typst::layout::{FrameItem, PagedDocument, Point, Size, Transform};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TextRun {
+    pub text: String,
+    /// Zero-based index of the page the run is on.
+    pub page: i64,
+    /// The box between the font's ascender and descender lines.
+    pub rect: ExtendedRect,
+}
+
+/// Lists the text runs of the document with their positions. Runs are returned
+/// page by page in the order they were laid out, which is the reading order.
+#[no_mangle]
+pub extern "C" fn extract_text_positions(
+    world_ptr: *mut JavaWorld,
+) -> JavaResult<ExtendedWarned<Result<Vec<TextRun>, Vec<ExtendedSourceDiagnostic>>>> {
+    JavaResult::pack(compile_paged(world_ptr, |_, document, _| {
+        Ok(collect_text_runs(&document))
+    }))
+}
+
+fn collect_text_runs(document: &PagedDocument) -> Vec<TextRun> {
+    let mut runs = vec![];
+    for (index, page) in document.pages.iter().enumerate() {
+        walk_frame(&page.frame, Transform::identity(), &mut |ts, pos, item| {
+            let FrameItem::Text(text) = item else { return };
+            let metrics = text.font.metrics();
+            let ascender = metrics.ascender.at(text.size);
+            let descender = metrics.descender.at(text.size);
+            runs.push(TextRun {
+                text: text.text.to_string(),
+                page: index as i64,
+                rect: ExtendedRect::bounding(
+                    ts,
+                    pos - Point::with_y(ascender),
+                    Size::new(text.width(), ascender - descender),
+                ),
+            });
+        });
+    }
+    runs
+}
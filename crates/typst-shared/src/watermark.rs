@@ -0,0 +1,265 @@
+use crate::compile::run_compilation;
+use crate::exception::Except;
+use crate::java_world::JavaWorld;
+use crate::memory_management::{Base16ByteArray, JavaResult, ThickBytePtr};
+use crate::overlay_world::OverlayWorld;
+use crate::throw;
+use serde::{Deserialize, Serialize};
+use std::mem;
+use tiny_skia::{Pixmap, PixmapPaint, Transform};
+use typst::diag::SourceResult;
+use typst::ecow::eco_format;
+use typst::foundations::Bytes;
+use typst::layout::{Abs, Page, PagedDocument, Point, Size};
+use typst::syntax::{FileId, Source, Span, VirtualPath};
+use typst::utils::tick;
+use typst_render::RenderOptions;
+
+/// The file the watermark is laid out in.
+const WATERMARK_FILE: &str = "/.watermark.typ";
+
+/// The file that holds the watermark's text or image.
+const WATERMARK_DATA: &str = "/.watermark";
+
+const PREAMBLE: &str = "#set page(width: auto, height: auto, margin: 0pt, fill: none)\n";
+
+/// A text or image stamped over the center of every page by `compile_svg` and
+/// `compile_png`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Watermark {
+    /// The text to stamp, e.g. "DRAFT". Ignored if there is an image.
+    #[serde(default)]
+    pub text: Option<String>,
+    /// A PNG, JPEG, GIF or SVG file to stamp instead of text.
+    #[serde(default)]
+    pub image: Option<Base16ByteArray>,
+    /// The font size of the text in points.
+    #[serde(default = "default_size")]
+    pub size: f64,
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    /// Clockwise rotation in degrees.
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+fn default_size() -> f64 {
+    72.0
+}
+
+fn default_opacity() -> f32 {
+    0.3
+}
+
+/// Sets the world's watermark from its JSON form, see [`Watermark`].
+/// `null` removes it; there is none by default.
+#[no_mangle]
+pub extern "C" fn set_watermark(
+    world_ptr: *mut JavaWorld,
+    watermark_json: ThickBytePtr,
+) -> JavaResult<Except<()>> {
+    tick!();
+    let mut world = unsafe { Box::from_raw(world_ptr) };
+    let watermark_str = watermark_json.to_str();
+    let result = serde_json::from_str::<Option<Watermark>>(&watermark_str)
+        .map(|watermark| world.watermark = watermark)
+        .map_err(|err| {
+            throw!(
+                "java.lang.IllegalArgumentException".to_string(),
+                Some(format!("Malformed watermark: {err}"))
+            )
+        });
+    let _ = Box::into_raw(world); // Not to drop the world!
+    mem::forget(watermark_str);
+    JavaResult::pack(result)
+}
+
+/// A laid out watermark, ready to be stamped onto rendered pages.
+pub struct Stamp {
+    page: Page,
+    opacity: f32,
+    rotation: f32,
+}
+
+impl Watermark {
+    /// Lays out the watermark with the world's fonts.
+    pub fn layout(&self, world: &JavaWorld) -> SourceResult<Stamp> {
+        let (body, data) = match (&self.image, &self.text) {
+            (Some(image), _) => {
+                (format!("#image({WATERMARK_DATA:?})"), Bytes::new(image.0.clone()))
+            }
+            (None, text) => (
+                format!(
+                    "#text(size: {}pt, fill: luma(50%), read({WATERMARK_DATA:?}))",
+                    self.size
+                ),
+                Bytes::from_string(text.clone().unwrap_or_default()),
+            ),
+        };
+        let id = FileId::new(None, VirtualPath::new(WATERMARK_FILE));
+        let watermark_world =
+            OverlayWorld::new(world, Source::new(id, format!("{PREAMBLE}{body}")))
+                .with_file(FileId::new(None, VirtualPath::new(WATERMARK_DATA)), data);
+        // The watermark is laid out after the document, with a time budget of
+        // its own. Running out of it only fails the watermark.
+        let document = run_compilation(world.timeout, || {
+            typst::compile::<PagedDocument>(&watermark_world)
+        })
+        .output
+        .map_err(|errors| {
+            // The watermark's source is made up, so its spans would point into a
+            // file that Java doesn't know.
+            errors
+                .into_iter()
+                .map(|mut error| {
+                    error.span = Span::detached();
+                    error.trace.clear();
+                    error.message = eco_format!("invalid watermark: {}", error.message);
+                    error
+                })
+                .collect()
+        })?;
+        Ok(Stamp {
+            page: document.pages.into_iter().next().expect("documents have a page"),
+            opacity: self.opacity.clamp(0.0, 1.0),
+            rotation: self.rotation,
+        })
+    }
+}
+
+impl Stamp {
+    /// Draws the watermark over the center of a page rendered at `ppi`.
+    pub fn stamp_png(&self, pixmap: &mut Pixmap, ppi: f32) {
+        let mark = typst_render::render(&self.page, ppi / 72.0);
+        let ts = Transform::from_translate(
+            pixmap.width() as f32 / 2.0,
+            pixmap.height() as f32 / 2.0,
+        )
+        .pre_rotate(self.rotation)
+        .pre_translate(-(mark.width() as f32) / 2.0, -(mark.height() as f32) / 2.0);
+        let paint = PixmapPaint { opacity: self.opacity, ..Default::default() };
+        pixmap.draw_pixmap(0, 0, mark.as_ref(), &paint, ts, None);
+    }
+
+    /// Draws the watermark like [`Stamp::stamp_png`] onto the rectangle at
+    /// `origin` of a page of the given size, which was rendered at `ppi`. Only
+    /// the part of the watermark over the rectangle is rasterized. `None` if
+    /// that part is too large to render.
+    pub fn stamp_png_region(
+        &self,
+        pixmap: &mut Pixmap,
+        ppi: f32,
+        page: Size,
+        origin: Point,
+    ) -> Option<()> {
+        let scale = ppi / 72.0;
+        let mark = self.page.frame.size();
+        // Maps points of the watermark to pixels of the rectangle.
+        let ts = Transform::from_translate(
+            (page.x / 2.0 - origin.x).to_f32() * scale,
+            (page.y / 2.0 - origin.y).to_f32() * scale,
+        )
+        .pre_rotate(self.rotation)
+        .pre_scale(scale, scale)
+        .pre_translate(-mark.x.to_f32() / 2.0, -mark.y.to_f32() / 2.0);
+
+        let (w, h) = (pixmap.width() as f32, pixmap.height() as f32);
+        let mut corners = [(0.0, 0.0), (w, 0.0), (0.0, h), (w, h)]
+            .map(|(x, y)| tiny_skia::Point::from_xy(x, y));
+        ts.invert()?.map_points(&mut corners);
+        let bound = |coord: fn(&tiny_skia::Point) -> f32, max: Abs| {
+            let coords = corners.iter().map(coord);
+            let start = coords.clone().fold(f32::INFINITY, f32::min).max(0.0);
+            let end = coords.fold(f32::NEG_INFINITY, f32::max).min(max.to_f32());
+            (start, end)
+        };
+        let (x0, x1) = bound(|p| p.x, mark.x);
+        let (y0, y1) = bound(|p| p.y, mark.y);
+        if x0 >= x1 || y0 >= y1 {
+            return Some(());
+        }
+
+        let part = typst_render::render_region(
+            &self.page,
+            scale,
+            Point::new(Abs::pt(x0.into()), Abs::pt(y0.into())),
+            Size::new(Abs::pt((x1 - x0).into()), Abs::pt((y1 - y0).into())),
+            RenderOptions::default(),
+        )?;
+        let ts = ts.pre_translate(x0, y0).pre_scale(1.0 / scale, 1.0 / scale);
+        let paint = PixmapPaint { opacity: self.opacity, ..Default::default() };
+        pixmap.draw_pixmap(0, 0, part.as_ref(), &paint, ts, None);
+        Some(())
+    }
+
+    /// Appends the watermark to the SVG of `page` as a nested SVG on top of the
+    /// page's content.
+    pub fn stamp_svg(&self, svg: String, page: &Page) -> String {
+        let Some(end) = svg.rfind("</svg>") else { return svg };
+        let size = page.frame.size();
+        let mark_size = self.page.frame.size();
+        let transform = format!(
+            "translate({} {}) rotate({}) translate({} {})",
+            size.x.to_pt() / 2.0,
+            size.y.to_pt() / 2.0,
+            self.rotation,
+            -mark_size.x.to_pt() / 2.0,
+            -mark_size.y.to_pt() / 2.0,
+        );
+        format!(
+            "{}<g opacity=\"{}\" transform=\"{transform}\">{}</g>{}",
+            &svg[..end],
+            self.opacity,
+            unitless(typst_svg::svg(&self.page), mark_size),
+            &svg[end..],
+        )
+    }
+}
+
+/// Removes the units from the size of an SVG of the given size, so that it is
+/// measured in the user units of the SVG it is nested into. Those are points
+/// for pages, while a nested `pt` would be converted to pixels, making the
+/// watermark a third larger than the transform centering it assumes.
+fn unitless(svg: String, size: Size) -> String {
+    let (width, height) = (size.x.to_pt(), size.y.to_pt());
+    svg.replacen(&format!("width=\"{width}pt\""), &format!("width=\"{width}\""), 1)
+        .replacen(&format!("height=\"{height}pt\""), &format!("height=\"{height}\""), 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::compile_svg;
+    use crate::memory_management::free_world;
+    use crate::test_utils::{json, thick, world};
+
+    #[test]
+    fn test_stamp_svg_is_centered() {
+        let world = world("#set page(width: 100pt, height: 200pt)\nHello");
+        let result = json(set_watermark(world, thick(r#"{"text": "DRAFT"}"#)));
+        assert_eq!(result, serde_json::json!({"Ok": null}));
+        let result = json(compile_svg(world, 0, 1));
+        let svg = result["output"]["Ok"][0].as_str().unwrap();
+        assert!(svg.contains("transform=\"translate(50 100) rotate(0) translate(-"));
+        // The nested SVG is measured in the page's points.
+        let nested = svg.rfind("<svg").unwrap();
+        let header = &svg[nested..nested + svg[nested..].find('>').unwrap()];
+        assert!(header.contains("width=\""));
+        assert!(!header.contains("pt\""));
+        assert!(svg[..svg.find('>').unwrap()].contains("pt\""));
+        free_world(world);
+    }
+
+    #[test]
+    fn test_watermark_errors_are_detached() {
+        let world = world("Hello");
+        let result = json(set_watermark(world, thick(r#"{"image": "00ff"}"#)));
+        assert_eq!(result, serde_json::json!({"Ok": null}));
+        let result = json(compile_svg(world, 0, 1));
+        let errors = result["output"]["Err"].as_array().unwrap();
+        let message = errors[0]["message"].as_str().unwrap();
+        assert!(message.starts_with("invalid watermark: "));
+        assert!(errors[0]["span"]["file"].is_null());
+        free_world(world);
+    }
+}
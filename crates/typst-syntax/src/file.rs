@@ -110,6 +110,13 @@ impl FileId {
         Self(v)
     }
 
+    /// Construct from a raw number, or `None` if no file was assigned that
+    /// number yet.
+    pub fn try_from_raw(v: NonZeroU16) -> Option<Self> {
+        let count = INTERNER.read().unwrap().from_id.len();
+        (usize::from(v.get()) <= count).then_some(Self(v))
+    }
+
     /// Extract the raw underlying number.
     pub const fn into_raw(self) -> NonZeroU16 {
         self.0
@@ -64,15 +64,73 @@ use crate::model::DocumentInfo;
 /// - Returns `Err(errors)` if there were fatal errors.
 #[typst_macros::time]
 pub fn compile<D>(world: &dyn World) -> Warned<SourceResult<D>>
+where
+    D: Document,
+{
+    compile_reporting(world, &mut |_| {})
+}
+
+/// Like [`compile`], but hands each warning to `report` as soon as it is final,
+/// instead of only returning them once compilation is done.
+///
+/// The warnings of evaluation are reported before layout starts. The ones of
+/// layout are only reported once it has converged, as the warnings of earlier
+/// attempts are discarded. Each returned warning is reported exactly once.
+#[typst_macros::time]
+pub fn compile_reporting<D>(
+    world: &dyn World,
+    report: &mut dyn FnMut(&SourceDiagnostic),
+) -> Warned<SourceResult<D>>
 where
     D: Document,
 {
     let mut sink = Sink::new();
-    let output = compile_impl::<D>(world.track(), Traced::default().track(), &mut sink)
-        .map_err(deduplicate);
+    let output =
+        compile_impl::<D>(world.track(), Traced::default().track(), &mut sink, report)
+            .map_err(deduplicate);
     Warned { output, warnings: sink.warnings() }
 }
 
+/// Compile sources into a fully layouted document, even if some of it failed.
+///
+/// Errors in show rules and similar recoverable errors only surface once layout
+/// is done. Instead of discarding the document then, this returns it together
+/// with the errors; the failing parts are missing from it.
+///
+/// - Returns `(Some(document), errors)` if there were no fatal errors.
+/// - Returns `(None, errors)` if there were fatal errors.
+#[typst_macros::time]
+pub fn compile_partial<D>(
+    world: &dyn World,
+) -> Warned<(Option<D>, EcoVec<SourceDiagnostic>)>
+where
+    D: Document,
+{
+    compile_partial_reporting(world, &mut |_| {})
+}
+
+/// Like [`compile_partial`], but reports warnings like [`compile_reporting`].
+#[typst_macros::time]
+pub fn compile_partial_reporting<D>(
+    world: &dyn World,
+    report: &mut dyn FnMut(&SourceDiagnostic),
+) -> Warned<(Option<D>, EcoVec<SourceDiagnostic>)>
+where
+    D: Document,
+{
+    let mut sink = Sink::new();
+    let traced = Traced::default();
+    let (document, errors) =
+        match layout_impl::<D>(world.track(), traced.track(), &mut sink, report) {
+            Ok(document) => (Some(document), sink.delayed()),
+            Err(errors) => (None, errors),
+        };
+    Warned {
+        output: (document, deduplicate(errors)),
+        warnings: sink.warnings(),
+    }
+}
+
 /// Compiles sources and returns all values and styles observed at the given
 /// `span` during compilation.
 #[typst_macros::time]
@@ -82,7 +140,7 @@ where
 {
     let mut sink = Sink::new();
     let traced = Traced::new(span);
-    compile_impl::<D>(world.track(), traced.track(), &mut sink).ok();
+    compile_impl::<D>(world.track(), traced.track(), &mut sink, &mut |_| {}).ok();
     sink.values()
 }
 
@@ -92,6 +150,25 @@ fn compile_impl<D: Document>(
     world: Tracked<dyn World + '_>,
     traced: Tracked<Traced>,
     sink: &mut Sink,
+    report: &mut dyn FnMut(&SourceDiagnostic),
+) -> SourceResult<D> {
+    let document = layout_impl::<D>(world, traced, sink, report)?;
+
+    // Promote delayed errors.
+    let delayed = sink.delayed();
+    if !delayed.is_empty() {
+        return Err(delayed);
+    }
+
+    Ok(document)
+}
+
+/// Evaluates and lays out the document, leaving delayed errors in the sink.
+fn layout_impl<D: Document>(
+    world: Tracked<dyn World + '_>,
+    traced: Tracked<Traced>,
+    sink: &mut Sink,
+    report: &mut dyn FnMut(&SourceDiagnostic),
 ) -> SourceResult<D> {
     if D::TARGET == Target::Html {
         warn_or_error_for_html(world, sink)?;
@@ -110,15 +187,18 @@ fn compile_impl<D: Document>(
         .map_err(|err| hint_invalid_main_file(world, err, main))?;
 
     // First evaluate the main source file into a module.
-    let content = typst_eval::eval(
+    let module = typst_eval::eval(
         &ROUTINES,
         world,
         traced,
         sink.track_mut(),
         Route::default().track(),
         &main,
-    )?
-    .content();
+    );
+
+    // The warnings so far are final, while layout's may still change.
+    sink.peek_warnings().iter().for_each(&mut *report);
+    let content = module?.content();
 
     let mut iter = 0;
     let mut subsink;
@@ -155,21 +235,20 @@ fn compile_impl<D: Document>(
         }
 
         if iter >= 5 {
-            subsink.warn(warning!(
-                Span::detached(), "layout did not converge within 5 attempts";
-                hint: "check if any states or queries are updating themselves"
-            ));
+            subsink.warn(
+                warning!(
+                    Span::detached(), "layout did not converge within 5 attempts";
+                    hint: "check if any states or queries are updating themselves"
+                )
+                .with_identifier("layout-not-converged"),
+            );
             break;
         }
     }
 
+    let reported = sink.peek_warnings().len();
     sink.extend_from_sink(subsink);
-
-    // Promote delayed errors.
-    let delayed = sink.delayed();
-    if !delayed.is_empty() {
-        return Err(delayed);
-    }
+    sink.peek_warnings()[reported..].iter().for_each(report);
 
     Ok(document)
 }
@@ -236,13 +315,16 @@ fn warn_or_error_for_html(
 ) -> SourceResult<()> {
     const ISSUE: &str = "https://github.com/typst/typst/issues/5512";
     if world.library().features.is_enabled(Feature::Html) {
-        sink.warn(warning!(
-            Span::detached(),
-            "html export is under active development and incomplete";
-            hint: "its behaviour may change at any time";
-            hint: "do not rely on this feature for production use cases";
-            hint: "see {ISSUE} for more information"
-        ));
+        sink.warn(
+            warning!(
+                Span::detached(),
+                "html export is under active development and incomplete";
+                hint: "its behaviour may change at any time";
+                hint: "do not rely on this feature for production use cases";
+                hint: "see {ISSUE} for more information"
+            )
+            .with_identifier("html-experimental"),
+        );
     } else {
         bail!(
             Span::detached(),
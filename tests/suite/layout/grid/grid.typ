@@ -283,3 +283,16 @@ The following:
   // Error: 3-50 cannot create grid with infinite width
   grid(gutter: infinite-length, columns: 2)[A][B]
 })
+
+--- table-query ---
+// Tables are locatable, so that their cells can be extracted after layout.
+// This must not change the numbering of the figures they are in.
+#hide[
+  #table(columns: 2)[A][B]
+  #figure(table[C])
+]
+#context {
+  test(query(table).len(), 2)
+  test(query(table).first().children.len(), 2)
+  test(counter(figure.where(kind: table)).get(), (1,))
+}
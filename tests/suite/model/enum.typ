@@ -231,3 +231,12 @@ a + 0.
   + E
 + = F
   G
+
+--- enum-query ---
+// Numbered lists are locatable, so that tagged PDF export can find their
+// structure.
+#hide[
+  + A
+  + B
+]
+#context test(query(enum).len(), 1)
@@ -304,3 +304,16 @@ World
   - C
 - = D
   E
+
+--- list-query ---
+// Lists are locatable, so that tagged PDF export can find their structure.
+#hide[
+  - A
+  - B
+]
+#context {
+  let lists = query(list)
+  test(lists.len(), 1)
+  test(lists.first().children.len(), 2)
+  test(lists.first().location().page(), 1)
+}
@@ -116,3 +116,11 @@ Not in list
 / Term A: 1
 / Term B: / Term C: 2
           / Term D: 3
+
+--- terms-query ---
+// Term lists are locatable, so that tagged PDF export can find their structure.
+#hide[
+  / A: B
+  / C: D
+]
+#context test(query(terms).len(), 1)
@@ -330,6 +330,8 @@
 --- import-cyclic ---
 // Cyclic import of this very file.
 // Error: 9-23 cyclic import
+// Hint: 9-23 the files import each other as /tests/suite/scripting/import.typ -> /tests/suite/scripting/import.typ
+// Hint: 9-23 move the definitions both files need into a separate file
 #import "./import.typ"
 
 --- import-cyclic-in-other-file ---
@@ -37,7 +37,7 @@
 #test(f(1), "world")
 
 --- recursion-maximum-depth ---
-// Error: 15-21 maximum function call depth exceeded
+// Error: 15-21 maximum recursion depth exceeded
 #let rec(n) = rec(n) + 1
 #rec(1)
 